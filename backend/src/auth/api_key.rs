@@ -30,6 +30,15 @@ pub async fn api_key_middleware(
     mut req: Request<Body>,
     next: Next,
 ) -> Result<Response, StatusCode> {
+    // Already authenticated by `signature_middleware`
+    if req.extensions().get::<AgentAuth>().is_some() {
+        return Ok(next.run(req).await);
+    }
+    if app_state.signature_settings.required {
+        tracing::warn!("Unsigned agent request refused: {}", req.uri().path());
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
     let api_key = req.headers()
         .get("X-API-Key")
         .and_then(|header| header.to_str().ok())
@@ -40,16 +49,30 @@ pub async fn api_key_middleware(
         return Err(StatusCode::UNAUTHORIZED);
     }
 
-    // TODO: In production, look up the org_id and agent_id by API key in database
-    // For now, parse from the key format: "org_<org_id>_<agent_id>_<random>"
+    // Keys issued through enrollment are looked up directly
+    if let Some(record) = app_state.api_keys.lookup(api_key) {
+        let agent_auth = AgentAuth::new(record.org_id, record.agent_id, app_state.api_key_prefix.clone());
+        req.extensions_mut().insert(agent_auth);
+        return Ok(next.run(req).await);
+    }
+
+    // Legacy keys encode their identity: "org_<org_id>_<agent_id>_<random>". Nothing proves
+    // the claim, so they are only honoured when enabled and until the org enrolls agents.
+    if !app_state.allow_legacy_api_keys {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
     let parts: Vec<&str> = api_key.split('_').collect();
     if parts.len() < 4 || parts[0] != "org" {
         return Err(StatusCode::UNAUTHORIZED);
     }
 
-    let org_id = parts.join("_");
-    let agent_id = parts.join("_");
-    
+    let org_id = parts[1].to_string();
+    let agent_id = parts[2].to_string();
+    if app_state.api_keys.org_has_keys(&org_id) {
+        tracing::warn!("Legacy API key refused for org with enrolled agents: org_id={}, agent_id={}", org_id, agent_id);
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
     let agent_auth = AgentAuth::new(org_id, agent_id, app_state.api_key_prefix.clone());
     req.extensions_mut().insert(agent_auth);
 
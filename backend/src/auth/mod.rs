@@ -1,2 +1,3 @@
 pub mod api_key;
-pub mod jwt;
\ No newline at end of file
+pub mod jwt;
+pub mod signature;
\ No newline at end of file
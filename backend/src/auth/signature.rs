@@ -0,0 +1,209 @@
+use axum::{
+    body::{self, Body},
+    extract::{OriginalUri, State},
+    middleware::Next,
+    response::Response,
+    http::{HeaderMap, Request, StatusCode},
+};
+use chrono::{DateTime, Utc};
+use anticheat_protocol::signing::{
+    self, CONTENT_SHA256_HEADER, KEY_ID_HEADER, SIGNATURE_HEADER, SIGNATURE_NONCE_HEADER,
+    SIGNATURE_TIMESTAMP_HEADER, UNSIGNED_PAYLOAD,
+};
+use crate::{auth::api_key::AgentAuth, config::AppState};
+
+/// Endpoints whose body may be left out of the signature because it is streamed
+const UNSIGNED_PAYLOAD_PATHS: &[&str] = &["/ingest/stream"];
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, StatusCode> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .ok_or(StatusCode::UNAUTHORIZED)
+}
+
+/// Authenticates agents by HMAC request signature, as an alternative to a bare `X-API-Key`.
+///
+/// Requests without `X-Signature` pass through to `api_key_middleware`. Signed requests
+/// must be fresh, use a nonce not seen before for the key, and match the body they carry.
+pub async fn signature_middleware(
+    State(app_state): State<AppState>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    if !req.headers().contains_key(SIGNATURE_HEADER) {
+        return Ok(next.run(req).await);
+    }
+
+    let headers = req.headers();
+    let signature = header(headers, SIGNATURE_HEADER)?;
+    let key_id = header(headers, KEY_ID_HEADER)?.to_string();
+    let nonce = header(headers, SIGNATURE_NONCE_HEADER)?.to_string();
+    let content_sha256 = header(headers, CONTENT_SHA256_HEADER)?.to_string();
+    let timestamp = header(headers, SIGNATURE_TIMESTAMP_HEADER)?
+        .parse::<i64>()
+        .ok()
+        .and_then(|secs| DateTime::<Utc>::from_timestamp(secs, 0))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    if nonce.is_empty() || nonce.len() > 128 {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    let settings = app_state.signature_settings;
+    if !settings.is_fresh(timestamp, Utc::now()) {
+        tracing::warn!("Stale request signature: key_id={}, timestamp={}", key_id, timestamp.to_rfc3339());
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let (secret, record) = app_state.api_keys
+        .lookup_by_key_id(&key_id)
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    // Nested routers see a stripped URI; the agent signed the one it requested
+    let uri = req.extensions()
+        .get::<OriginalUri>()
+        .map(|original| original.0.clone())
+        .unwrap_or_else(|| req.uri().clone());
+    let path = uri.path_and_query().map(|pq| pq.as_str()).unwrap_or("/");
+    let unsigned_payload = content_sha256 == UNSIGNED_PAYLOAD;
+    if unsigned_payload && !UNSIGNED_PAYLOAD_PATHS.contains(&uri.path()) {
+        tracing::warn!("Unsigned payload refused: key_id={}, path={}", key_id, uri.path());
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let canonical = signing::canonical_request(
+        req.method().as_str(),
+        path,
+        timestamp.timestamp(),
+        &nonce,
+        &content_sha256,
+    );
+    if !signing::verify(&secret, &canonical, signature) {
+        tracing::warn!("Invalid request signature: key_id={}", key_id);
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    if !unsigned_payload {
+        let (parts, body) = req.into_parts();
+        let bytes = body::to_bytes(body, app_state.max_request_size)
+            .await
+            .map_err(|_| StatusCode::PAYLOAD_TOO_LARGE)?;
+        if !signing::content_sha256(&bytes).eq_ignore_ascii_case(&content_sha256) {
+            tracing::warn!("Request body does not match its signed hash: key_id={}", key_id);
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+        req = Request::from_parts(parts, Body::from(bytes));
+    }
+
+    // The nonce is claimed only once the whole request is verified, so resending captured
+    // headers with another body, or one too large to read, cannot burn it
+    if !app_state.nonces.claim(&key_id, &nonce, timestamp + settings.max_skew) {
+        tracing::warn!("Replayed request nonce: key_id={}, nonce={}", key_id, nonce);
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let agent_auth = AgentAuth::new(record.org_id, record.agent_id, app_state.api_key_prefix.clone());
+    req.extensions_mut().insert(agent_auth);
+
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{middleware, routing::post, Extension, Router};
+    use tower::ServiceExt;
+    use super::*;
+
+    async fn whoami(Extension(agent_auth): Extension<AgentAuth>) -> String {
+        agent_auth.agent_id
+    }
+
+    fn app(app_state: &AppState) -> Router {
+        Router::new()
+            .route("/ingest/batch", post(whoami))
+            .route("/ingest/stream", post(whoami))
+            .layer(middleware::from_fn_with_state(app_state.clone(), signature_middleware))
+            .with_state(app_state.clone())
+    }
+
+    /// A request to `path` signed with `api_key`, claiming `signed_body` but carrying `body`
+    fn signed(api_key: &str, path: &str, nonce: &str, timestamp: i64, signed_body: Option<&[u8]>, body: &[u8]) -> Request<Body> {
+        let content_sha256 = signed_body.map_or_else(|| UNSIGNED_PAYLOAD.to_string(), signing::content_sha256);
+        let canonical = signing::canonical_request("POST", path, timestamp, nonce, &content_sha256);
+        Request::post(path)
+            .header(KEY_ID_HEADER, signing::key_id(api_key))
+            .header(SIGNATURE_TIMESTAMP_HEADER, timestamp.to_string())
+            .header(SIGNATURE_NONCE_HEADER, nonce)
+            .header(CONTENT_SHA256_HEADER, content_sha256)
+            .header(SIGNATURE_HEADER, signing::sign(&signing::signing_secret(api_key), &canonical))
+            .body(Body::from(body.to_vec()))
+            .unwrap()
+    }
+
+    async fn status(app_state: &AppState, request: Request<Body>) -> StatusCode {
+        app(app_state).oneshot(request).await.unwrap().status()
+    }
+
+    fn enrolled() -> (AppState, String) {
+        let (app_state, _receiver) = AppState::for_tests();
+        let api_key = app_state.api_keys.issue("org", "org_1", "agent_1", Utc::now());
+        (app_state, api_key)
+    }
+
+    #[tokio::test]
+    async fn valid_signatures_authenticate_the_agent() {
+        let (app_state, api_key) = enrolled();
+        let now = Utc::now().timestamp();
+        let response = app(&app_state)
+            .oneshot(signed(&api_key, "/ingest/batch", "n1", now, Some(b"{}"), b"{}"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"agent_1");
+    }
+
+    #[tokio::test]
+    async fn replayed_nonces_are_refused() {
+        let (app_state, api_key) = enrolled();
+        let now = Utc::now().timestamp();
+        let request = || signed(&api_key, "/ingest/batch", "n1", now, Some(b"{}"), b"{}");
+        assert_eq!(status(&app_state, request()).await, StatusCode::OK);
+        assert_eq!(status(&app_state, request()).await, StatusCode::UNAUTHORIZED);
+
+        // Nonces are per key
+        let other_key = app_state.api_keys.issue("org", "org_1", "agent_2", Utc::now());
+        assert_eq!(status(&app_state, signed(&other_key, "/ingest/batch", "n1", now, Some(b"{}"), b"{}")).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn a_body_that_does_not_match_its_hash_does_not_burn_the_nonce() {
+        let (app_state, api_key) = enrolled();
+        let now = Utc::now().timestamp();
+        let tampered = signed(&api_key, "/ingest/batch", "n1", now, Some(b"{}"), b"{\"events\":[]}");
+        assert_eq!(status(&app_state, tampered).await, StatusCode::UNAUTHORIZED);
+
+        let genuine = signed(&api_key, "/ingest/batch", "n1", now, Some(b"{}"), b"{}");
+        assert_eq!(status(&app_state, genuine).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn forged_stale_and_unsigned_requests_are_refused() {
+        let (app_state, api_key) = enrolled();
+        let now = Utc::now().timestamp();
+
+        let mut forged = signed(&api_key, "/ingest/batch", "n1", now, Some(b"{}"), b"{}");
+        forged.headers_mut().insert(SIGNATURE_HEADER, signing::sign("wrong secret", "").parse().unwrap());
+        assert_eq!(status(&app_state, forged).await, StatusCode::UNAUTHORIZED);
+
+        let stale = now - app_state.signature_settings.max_skew.num_seconds() - 1;
+        assert_eq!(status(&app_state, signed(&api_key, "/ingest/batch", "n2", stale, Some(b"{}"), b"{}")).await, StatusCode::UNAUTHORIZED);
+
+        let unknown_key = signed("org_unknown", "/ingest/batch", "n3", now, Some(b"{}"), b"{}");
+        assert_eq!(status(&app_state, unknown_key).await, StatusCode::UNAUTHORIZED);
+
+        // Only streams may leave the body unsigned
+        assert_eq!(status(&app_state, signed(&api_key, "/ingest/batch", "n4", now, None, b"{}")).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status(&app_state, signed(&api_key, "/ingest/stream", "n5", now, None, b"{}")).await, StatusCode::OK);
+    }
+}
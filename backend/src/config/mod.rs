@@ -1,26 +1,91 @@
 use axum::extract::FromRef;
 use axum_extra::extract::cookie::Key;
+use tokio::sync::broadcast;
+use crate::{handlers::realtime::RealtimeEvent, middleware::rate_limit::{RateLimitSettings, RateLimits}, tasks::ingest_workers::IngestQueue, models::{agent::AgentStore, agent_config::ConfigStore, api_key::ApiKeyStore, clock::TimestampBounds, enrollment::EnrollmentStore, event_schema::EventSchemaStore, health::HealthStore, idempotency::IdempotencyStore, quota::{QuotaLimits, QuotaStore}, signature::{NonceStore, SignatureSettings}, version_policy::VersionPolicyStore}};
 #[derive(Clone)]
 pub struct AppState {
 cookie_key: Key, // Made private
 pub jwt_secret: String,
 pub api_key_prefix: String,
+/// `ALLOW_LEGACY_API_KEYS`: accept unverified `org_<org_id>_<agent_id>_<random>` keys from orgs without enrolled agents
+pub allow_legacy_api_keys: bool,
+/// `MAX_REQUEST_SIZE`: body limit in bytes, also applied to decompressed ingest bodies
+pub max_request_size: usize,
+/// Accepted distance between event `detected_at` and server time
+pub timestamp_bounds: TimestampBounds,
+/// Freshness window of signed agent requests and whether signing is mandatory
+pub signature_settings: SignatureSettings,
+pub agents: AgentStore,
+pub api_keys: ApiKeyStore,
+pub enrollment_tokens: EnrollmentStore,
+pub health: HealthStore,
+pub idempotency: IdempotencyStore,
+pub configs: ConfigStore,
+pub version_policies: VersionPolicyStore,
+pub quotas: QuotaStore,
+pub event_schemas: EventSchemaStore,
+/// Limits on unauthenticated endpoints such as `/auth/login`
+pub rate_limits: RateLimits,
+/// Nonces of signed agent requests, to refuse replays
+pub nonces: NonceStore,
+/// Accepted events waiting for the ingest workers
+pub ingest_queue: IngestQueue,
+pub events: broadcast::Sender<RealtimeEvent>,
 }
 impl AppState {
-pub fn new(cookie_key: Key, jwt_secret: String, api_key_prefix: String) -> Self {
+#[allow(clippy::too_many_arguments)]
+pub fn new(cookie_key: Key, jwt_secret: String, api_key_prefix: String, allow_legacy_api_keys: bool, max_request_size: usize, timestamp_bounds: TimestampBounds, signature_settings: SignatureSettings, quota_limits: QuotaLimits, rate_limit_settings: RateLimitSettings, ingest_queue: IngestQueue) -> Self {
+let (events, _) = broadcast::channel(1024);
 Self {
 cookie_key,
 jwt_secret,
 api_key_prefix,
+allow_legacy_api_keys,
+max_request_size,
+timestamp_bounds,
+signature_settings,
+agents: AgentStore::new(),
+api_keys: ApiKeyStore::new(),
+enrollment_tokens: EnrollmentStore::new(),
+health: HealthStore::new(),
+idempotency: IdempotencyStore::new(),
+configs: ConfigStore::new(),
+version_policies: VersionPolicyStore::new(),
+quotas: QuotaStore::new(quota_limits),
+event_schemas: EventSchemaStore::new(),
+rate_limits: RateLimits::new(rate_limit_settings),
+nonces: NonceStore::new(),
+ingest_queue,
+events,
 }
 }
+/// State with default settings and an ingest queue nobody drains, for handler tests
+#[cfg(test)]
+pub(crate) fn for_tests() -> (Self, crate::tasks::ingest_workers::IngestReceiver) {
+Self::for_tests_with_queue(1000)
+}
+#[cfg(test)]
+pub(crate) fn for_tests_with_queue(queue_capacity: usize) -> (Self, crate::tasks::ingest_workers::IngestReceiver) {
+let (ingest_queue, receiver) = IngestQueue::new(&crate::tasks::ingest_workers::IngestWorkerSettings {
+workers: 1,
+queue_capacity,
+retry_after_secs: 1,
+});
+let app_state = Self::new(Key::generate(), "test-secret".to_string(), "org".to_string(), false, 1024 * 1024, TimestampBounds::default(), SignatureSettings::default(), QuotaLimits::default(), RateLimitSettings::default(), ingest_queue);
+(app_state, receiver)
+}
 pub fn cookie_key(&self) -> &Key {
 &self.cookie_key
 }
+/// Publishes to realtime subscribers; dropped silently when nobody is listening
+pub fn publish(&self, event: RealtimeEvent) {
+let _ = self.events.send(event);
+}
 }
 impl FromRef<AppState> for Key {
 fn from_ref(state: &AppState) -> Self {
 state.cookie_key.clone()
 }
 }
-pub mod config;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub mod config;
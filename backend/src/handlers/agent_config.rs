@@ -0,0 +1,223 @@
+use axum::{
+    extract::{Extension, Path, State},
+    response::IntoResponse,
+    Json,
+    http::StatusCode,
+};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+use chrono::Utc;
+use crate::{
+    auth::{api_key::AgentAuth, jwt::Claims},
+    config::AppState,
+    models::agent_config::{AgentConfigBody, ResolvedConfig},
+};
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateConfigProfileRequest {
+    #[validate(length(min = 1, max = 100, message = "Name must be 1-100 characters"))]
+    pub name: String,
+
+    #[validate(nested)]
+    pub body: AgentConfigBody,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateConfigProfileRequest {
+    #[validate(nested)]
+    pub body: AgentConfigBody,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct AssignConfigProfileRequest {
+    #[validate(length(max = 1000, message = "At most 1000 agents per assignment"))]
+    #[serde(default)]
+    pub agent_ids: Vec<String>,
+
+    #[validate(length(max = 100, message = "At most 100 tags per assignment"))]
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Create a configuration profile
+#[utoipa::path(
+    post,
+    path = "/v1/config-profiles",
+    request_body = CreateConfigProfileRequest,
+    responses(
+        (status = 201, description = "Profile created", body = crate::models::agent_config::ConfigProfile),
+        (status = 400, description = "Invalid request format"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin role required")
+    ),
+    security(("bearerAuth" = [])),
+    tag = "Agents"
+)]
+pub async fn create_profile(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreateConfigProfileRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    if claims.role != "admin" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if let Err(validation_errors) = payload.validate() {
+        tracing::warn!("Config profile validation failed: {:?}", validation_errors);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let profile = app_state.configs.create(&claims.org_id, payload.name, payload.body, Utc::now());
+    tracing::info!(
+        "Config profile created: id={}, version={}, org_id={}",
+        profile.id,
+        profile.version,
+        profile.org_id
+    );
+
+    Ok((StatusCode::CREATED, Json(profile)))
+}
+
+/// List configuration profiles of the org
+#[utoipa::path(
+    get,
+    path = "/v1/config-profiles",
+    responses(
+        (status = 200, description = "Configuration profiles", body = Vec<crate::models::agent_config::ConfigProfile>),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(("bearerAuth" = [])),
+    tag = "Agents"
+)]
+pub async fn list_profiles(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> impl IntoResponse {
+    Json(app_state.configs.list(&claims.org_id))
+}
+
+/// Replace a profile body, publishing a new version to its agents
+#[utoipa::path(
+    put,
+    path = "/v1/config-profiles/{id}",
+    params(("id" = String, Path, description = "Profile ID")),
+    request_body = UpdateConfigProfileRequest,
+    responses(
+        (status = 200, description = "Profile updated", body = crate::models::agent_config::ConfigProfile),
+        (status = 400, description = "Invalid request format"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin role required"),
+        (status = 404, description = "Profile not found")
+    ),
+    security(("bearerAuth" = [])),
+    tag = "Agents"
+)]
+pub async fn update_profile(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(profile_id): Path<String>,
+    Json(payload): Json<UpdateConfigProfileRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    if claims.role != "admin" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if let Err(validation_errors) = payload.validate() {
+        tracing::warn!("Config profile validation failed: {:?}", validation_errors);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let profile = app_state.configs
+        .update(&claims.org_id, &profile_id, payload.body, Utc::now())
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    tracing::info!(
+        "Config profile updated: id={}, version={}, org_id={}",
+        profile.id,
+        profile.version,
+        profile.org_id
+    );
+
+    Ok((StatusCode::OK, Json(profile)))
+}
+
+/// Assign a profile to agents and/or agent tags
+#[utoipa::path(
+    post,
+    path = "/v1/config-profiles/{id}/assignments",
+    params(("id" = String, Path, description = "Profile ID")),
+    request_body = AssignConfigProfileRequest,
+    responses(
+        (status = 204, description = "Profile assigned"),
+        (status = 400, description = "Invalid request format"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin role required"),
+        (status = 404, description = "Profile not found")
+    ),
+    security(("bearerAuth" = [])),
+    tag = "Agents"
+)]
+pub async fn assign_profile(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(profile_id): Path<String>,
+    Json(payload): Json<AssignConfigProfileRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    if claims.role != "admin" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if payload.validate().is_err() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    app_state.configs
+        .assign(&claims.org_id, &profile_id, &payload.agent_ids, &payload.tags)
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    tracing::info!(
+        "Config profile assigned: id={}, agents={}, tags={:?}, org_id={}",
+        profile_id,
+        payload.agent_ids.len(),
+        payload.tags,
+        claims.org_id
+    );
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Resolves the configuration that currently applies to an agent
+pub fn resolve_for_agent(app_state: &AppState, org_id: &str, agent_id: &str) -> ResolvedConfig {
+    let tags = app_state.agents
+        .get(org_id, agent_id)
+        .map(|agent| agent.tags)
+        .unwrap_or_default();
+    app_state.configs.resolve(org_id, agent_id, &tags)
+}
+
+/// Current configuration for the calling agent
+#[utoipa::path(
+    get,
+    path = "/agents/config",
+    responses(
+        (status = 200, description = "Resolved agent configuration", body = ResolvedConfig),
+        (status = 401, description = "Invalid or missing API key")
+    ),
+    security(("apiKeyAuth" = [])),
+    tag = "Agents"
+)]
+pub async fn agent_config(
+    State(app_state): State<AppState>,
+    Extension(agent_auth): Extension<AgentAuth>,
+) -> impl IntoResponse {
+    Json(resolve_for_agent(&app_state, &agent_auth.org_id, &agent_auth.agent_id))
+}
+
+pub fn admin_routes() -> axum::Router<AppState> {
+    axum::Router::new()
+        .route("/config-profiles", axum::routing::get(list_profiles).post(create_profile))
+        .route("/config-profiles/{id}", axum::routing::put(update_profile))
+        .route("/config-profiles/{id}/assignments", axum::routing::post(assign_profile))
+}
+
+/// Agent-facing routes, mounted behind the API key middleware
+pub fn agent_routes() -> axum::Router<AppState> {
+    axum::Router::new()
+        .route("/config", axum::routing::get(agent_config))
+}
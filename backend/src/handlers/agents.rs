@@ -0,0 +1,213 @@
+use std::collections::BTreeMap;
+use axum::{
+    extract::{Extension, Path, Query, State},
+    response::IntoResponse,
+    Json,
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+use chrono::{DateTime, Duration, Utc};
+use crate::{
+    auth::jwt::Claims,
+    config::AppState,
+    models::{health::HealthPoint, version_policy::{VersionPolicy, VersionStatus}},
+};
+
+/// Upper bound on points returned by a single health query
+const MAX_HEALTH_POINTS: i64 = 5000;
+
+#[derive(Debug, Deserialize, Validate, ToSchema, utoipa::IntoParams)]
+pub struct HealthQuery {
+    /// Start of the range (defaults to one hour before `to`)
+    pub from: Option<DateTime<Utc>>,
+    /// End of the range (defaults to now)
+    pub to: Option<DateTime<Utc>>,
+    /// Bucket width in seconds
+    #[validate(range(min = 1, max = 86400, message = "Step must be between 1 and 86400 seconds"))]
+    #[serde(default = "default_step")]
+    pub step: i64,
+}
+
+fn default_step() -> i64 { 60 }
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AgentHealthResponse {
+    pub agent_id: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub step: i64,
+    pub points: Vec<HealthPoint>,
+}
+
+/// CPU and memory usage history of an agent, reported through heartbeats
+#[utoipa::path(
+    get,
+    path = "/v1/agents/{id}/health",
+    params(
+        ("id" = String, Path, description = "Agent ID"),
+        HealthQuery
+    ),
+    responses(
+        (status = 200, description = "Agent health time series", body = AgentHealthResponse),
+        (status = 400, description = "Invalid query parameters"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Agent not found")
+    ),
+    security(("bearerAuth" = [])),
+    tag = "Agents"
+)]
+pub async fn agent_health(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(agent_id): Path<String>,
+    Query(query): Query<HealthQuery>,
+) -> Result<impl IntoResponse, StatusCode> {
+    if query.validate().is_err() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let org_id = &claims.org_id;
+    if app_state.agents.get(org_id, &agent_id).is_none() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or(to - Duration::hours(1));
+    if from >= to || (to - from).num_seconds() / query.step > MAX_HEALTH_POINTS {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let points = app_state.health.query(org_id, &agent_id, from, to, query.step);
+
+    Ok((
+        StatusCode::OK,
+        Json(AgentHealthResponse {
+            agent_id,
+            from,
+            to,
+            step: query.step,
+            points,
+        })
+    ))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct VersionCount {
+    pub version: String,
+    pub status: VersionStatus,
+    pub agents: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AgentVersionsResponse {
+    pub total: u64,
+    pub outdated: u64,
+    pub blocked: u64,
+    pub versions: Vec<VersionCount>,
+}
+
+/// Number of agents running each version, evaluated against the org version policy
+#[utoipa::path(
+    get,
+    path = "/v1/agents/versions",
+    responses(
+        (status = 200, description = "Agent version summary", body = AgentVersionsResponse),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(("bearerAuth" = [])),
+    tag = "Agents"
+)]
+pub async fn agent_versions(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> impl IntoResponse {
+    let org_id = &claims.org_id;
+
+    let mut counts: BTreeMap<String, u64> = BTreeMap::new();
+    for agent in app_state.agents.list(org_id) {
+        *counts.entry(agent.version).or_default() += 1;
+    }
+
+    let versions: Vec<VersionCount> = counts
+        .into_iter()
+        .map(|(version, agents)| VersionCount {
+            status: app_state.version_policies.evaluate(org_id, &version),
+            version,
+            agents,
+        })
+        .collect();
+    let count_of = |status: VersionStatus| -> u64 {
+        versions.iter().filter(|v| v.status == status).map(|v| v.agents).sum()
+    };
+
+    Json(AgentVersionsResponse {
+        total: versions.iter().map(|v| v.agents).sum(),
+        outdated: count_of(VersionStatus::Outdated),
+        blocked: count_of(VersionStatus::Blocked),
+        versions,
+    })
+}
+
+/// Current agent version policy of the org
+#[utoipa::path(
+    get,
+    path = "/v1/version-policy",
+    responses(
+        (status = 200, description = "Agent version policy", body = VersionPolicy),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(("bearerAuth" = [])),
+    tag = "Agents"
+)]
+pub async fn get_version_policy(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> impl IntoResponse {
+    Json(app_state.version_policies.get(&claims.org_id))
+}
+
+/// Replace the agent version policy of the org
+#[utoipa::path(
+    put,
+    path = "/v1/version-policy",
+    request_body = VersionPolicy,
+    responses(
+        (status = 200, description = "Policy updated", body = VersionPolicy),
+        (status = 400, description = "Invalid version or range"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin role required")
+    ),
+    security(("bearerAuth" = [])),
+    tag = "Agents"
+)]
+pub async fn put_version_policy(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<VersionPolicy>,
+) -> Result<impl IntoResponse, StatusCode> {
+    if claims.role != "admin" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if payload.validate().is_err() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let policy = app_state.version_policies
+        .set(&claims.org_id, payload, Utc::now())
+        .map_err(|e| {
+            tracing::warn!("Version policy rejected: {}", e);
+            StatusCode::BAD_REQUEST
+        })?;
+    tracing::info!("Version policy updated for org_id={}: {:?}", claims.org_id, policy);
+
+    Ok(Json(policy))
+}
+
+pub fn routes() -> axum::Router<AppState> {
+    axum::Router::new()
+        .route("/agents/versions", axum::routing::get(agent_versions))
+        .route("/agents/{id}/health", axum::routing::get(agent_health))
+        .route("/version-policy", axum::routing::get(get_version_policy).put(put_version_policy))
+}
@@ -2,12 +2,14 @@ use axum::{
     extract::{State},
     response::{IntoResponse, Response},
     Json,
-    http::StatusCode,
+    http::{header, StatusCode},
 };
 use axum_extra::extract::{CookieJar, cookie::Cookie};
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 use validator::Validate;
+use sha2::{Digest, Sha256};
+use std::time::Instant;
 use crate::{auth::jwt::Claims, config::AppState};
 
 #[derive(Debug, Deserialize, Validate, ToSchema)]
@@ -41,7 +43,8 @@ pub struct UserInfo {
     responses(
         (status = 200, description = "Login successful", body = LoginResponse),
         (status = 401, description = "Invalid credentials"),
-        (status = 400, description = "Invalid request format")
+        (status = 400, description = "Invalid request format"),
+        (status = 429, description = "Too many attempts for this client or account; see Retry-After", body = LoginResponse)
     ),
     tag = "Authentication"
 )]
@@ -64,9 +67,23 @@ pub async fn login(
         ));
     }
 
+    // Throttle and lock out per account, independently of the client IP
+    let account = payload.email.trim().to_lowercase();
+    let limits = &app_state.rate_limits;
+    let now = Instant::now();
+    if let Some(retry_after) = limits.login_lockouts.locked_for(&account, now) {
+        tracing::warn!("Login refused for locked account: {}", account_fingerprint(&account));
+        return Err(too_many_attempts(retry_after));
+    }
+    if let Err(retry_after) = limits.login_by_email.check(&account, now) {
+        tracing::warn!("Login rate limit exceeded for account: {}", account_fingerprint(&account));
+        return Err(too_many_attempts(retry_after));
+    }
+
     // For demo purposes - replace with actual user authentication
     // In production, verify against database with hashed passwords
     if payload.email == "demo@cluelyguard.com" && payload.password == "demo123456" {
+        limits.login_lockouts.record_success(&account);
         let user_id = uuid::Uuid::new_v4().to_string();
         let org_id = "demo_org_001".to_string();
         let role = "admin".to_string();
@@ -108,6 +125,9 @@ pub async fn login(
             }
         }
     } else {
+        if let Some(lockout) = limits.login_lockouts.record_failure(&account, now) {
+            tracing::warn!("Account {} locked for {}s after repeated login failures", account_fingerprint(&account), lockout.as_secs());
+        }
         Ok((jar, (StatusCode::UNAUTHORIZED, Json(LoginResponse {
             success: false,
             message: "Invalid email or password".to_string(),
@@ -116,6 +136,24 @@ pub async fn login(
     }
 }
 
+/// A short hash that correlates log lines for one account without writing its email to the logs
+fn account_fingerprint(account: &str) -> String {
+    let digest = format!("{:x}", Sha256::digest(account.as_bytes()));
+    digest[..12].to_string()
+}
+
+fn too_many_attempts(retry_after: u64) -> Response {
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, retry_after.max(1).to_string())],
+        Json(LoginResponse {
+            success: false,
+            message: "Too many login attempts, try again later".to_string(),
+            user: None,
+        }),
+    ).into_response()
+}
+
 pub fn routes() -> axum::Router<AppState> {
     axum::Router::new()
         .route("/login", axum::routing::post(login))
@@ -1,14 +1,20 @@
 use axum::{
-    extract::{Query, Extension},
+    extract::{Query, Extension, State},
     response::IntoResponse,
     Json,
     http::StatusCode,
 };
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use utoipa::ToSchema;
 use validator::Validate;
 use chrono::{DateTime, Utc};
-use crate::{auth::jwt::Claims, config::AppState};
+use crate::{auth::jwt::Claims, config::AppState, models::version_policy::VersionStatus};
+
+pub use anticheat_protocol::{
+    agent::Agent,
+    dashboard::{Alert, Detection, PageMeta, PagedResponse},
+    severity::Severity,
+};
 
 // Pagination and filtering types
 #[derive(Debug, Deserialize, Validate, ToSchema)]
@@ -25,47 +31,22 @@ pub struct PaginationParams {
 fn default_page() -> u32 { 1 }
 fn default_per_page() -> u32 { 20 }
 
-#[derive(Debug, Serialize, ToSchema)]
-pub struct PageMeta {
-    pub page: u32,
-    pub per_page: u32,
-    pub total: u64,
-    pub total_pages: u32,
-}
-
-#[derive(Debug, Serialize, ToSchema)]
-pub struct PagedResponse<T> {
-    pub data: Vec<T>,
-    pub meta: PageMeta,
-}
-
 // Detection types
 #[derive(Debug, Deserialize, Validate, ToSchema, utoipa::IntoParams)]
 pub struct DetectionFilters {
     #[serde(flatten)]
     pub pagination: PaginationParams,
     
-    pub severity: Option<String>,
+    /// Exact severity
+    pub severity: Option<Severity>,
+    /// This severity or anything more serious
+    pub min_severity: Option<Severity>,
     pub agent_id: Option<String>,
     pub detection_type: Option<String>,
     pub start_date: Option<DateTime<Utc>>,
     pub end_date: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize, ToSchema)]
-pub struct Detection {
-    pub id: String,
-    pub org_id: String,
-    pub agent_id: String,
-    pub detection_type: String,
-    pub severity: String,
-    pub title: String,
-    pub description: String,
-    pub metadata: serde_json::Value,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
-}
-
 // Agent types
 #[derive(Debug, Deserialize, Validate, ToSchema, utoipa::IntoParams)]
 pub struct AgentFilters {
@@ -75,19 +56,7 @@ pub struct AgentFilters {
     pub status: Option<String>,
     pub platform: Option<String>,
     pub version: Option<String>,
-}
-
-#[derive(Debug, Serialize, ToSchema)]
-pub struct Agent {
-    pub id: String,
-    pub org_id: String,
-    pub name: String,
-    pub platform: String,
-    pub version: String,
-    pub status: String,
-    pub last_heartbeat: Option<DateTime<Utc>>,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
+    pub version_status: Option<VersionStatus>,
 }
 
 // Alert types
@@ -97,25 +66,13 @@ pub struct AlertFilters {
     pub pagination: PaginationParams,
     
     pub status: Option<String>,
-    pub severity: Option<String>,
+    /// Exact severity
+    pub severity: Option<Severity>,
+    /// This severity or anything more serious
+    pub min_severity: Option<Severity>,
     pub rule_id: Option<String>,
 }
 
-#[derive(Debug, Serialize, ToSchema)]
-pub struct Alert {
-    pub id: String,
-    pub org_id: String,
-    pub rule_id: String,
-    pub detection_id: String,
-    pub severity: String,
-    pub status: String,
-    pub title: String,
-    pub description: String,
-    pub metadata: serde_json::Value,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
-}
-
 /// List detections with org-scoped access
 #[utoipa::path(
     get,
@@ -134,7 +91,7 @@ pub async fn list_detections(
     Query(filters): Query<DetectionFilters>,
 ) -> Result<impl IntoResponse, StatusCode> {
     // Validate query parameters
-    if let Err(_) = filters.validate() {
+    if filters.validate().is_err() {
         return Err(StatusCode::BAD_REQUEST);
     }
     
@@ -149,14 +106,20 @@ pub async fn list_detections(
             org_id: org_id.clone(),
             agent_id: "agent_001".to_string(),
             detection_type: "suspicious_process".to_string(),
-            severity: "high".to_string(),
+            severity: Severity::High,
             title: "Suspicious AI Process Detected".to_string(),
             description: "Detected ChatGPT API calls during gameplay".to_string(),
             metadata: serde_json::json!({"process": "chatgpt.exe", "confidence": 0.95}),
+            received_at: Utc::now(),
+            timestamp_flag: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
     ];
+    let mock_detections: Vec<Detection> = mock_detections
+        .into_iter()
+        .filter(|d| severity_matches(d.severity, filters.severity, filters.min_severity))
+        .collect();
     
     let total = mock_detections.len() as u64;
     let total_pages = ((total as f64) / (filters.pagination.per_page as f64)).ceil() as u32;
@@ -188,35 +151,39 @@ pub async fn list_detections(
     tag = "Agents"
 )]
 pub async fn list_agents(
+    State(app_state): State<AppState>,
     Extension(claims): Extension<Claims>,
     Query(filters): Query<AgentFilters>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    if let Err(_) = filters.validate() {
+    if filters.validate().is_err() {
         return Err(StatusCode::BAD_REQUEST);
     }
     
     let org_id = &claims.org_id;
     
-    // TODO: Replace with actual database query
-    let mock_agents = vec![
-        Agent {
-            id: "agent_001".to_string(),
-            org_id: org_id.clone(),
-            name: "Game Server #1".to_string(),
-            platform: "Windows".to_string(),
-            version: "1.0.0".to_string(),
-            status: "online".to_string(),
-            last_heartbeat: Some(Utc::now()),
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
-        }
-    ];
+    let agents: Vec<Agent> = app_state.agents
+        .list(org_id)
+        .into_iter()
+        .filter(|a| filters.status.as_ref().is_none_or(|s| a.status.to_string() == *s))
+        .filter(|a| filters.platform.as_ref().is_none_or(|p| a.platform == *p))
+        .filter(|a| filters.version.as_ref().is_none_or(|v| a.version == *v))
+        .map(|record| {
+            let version_status = app_state.version_policies.evaluate(org_id, &record.version);
+            record.into_agent(version_status)
+        })
+        .filter(|a| filters.version_status.is_none_or(|s| a.version_status == s))
+        .collect();
     
-    let total = mock_agents.len() as u64;
+    let total = agents.len() as u64;
     let total_pages = ((total as f64) / (filters.pagination.per_page as f64)).ceil() as u32;
+    let offset = ((filters.pagination.page - 1) * filters.pagination.per_page) as usize;
     
     let response = PagedResponse {
-        data: mock_agents,
+        data: agents
+            .into_iter()
+            .skip(offset)
+            .take(filters.pagination.per_page as usize)
+            .collect(),
         meta: PageMeta {
             page: filters.pagination.page,
             per_page: filters.pagination.per_page,
@@ -245,7 +212,7 @@ pub async fn list_alerts(
     Extension(claims): Extension<Claims>,
     Query(filters): Query<AlertFilters>,
 ) -> Result<impl IntoResponse, StatusCode> {
-    if let Err(_) = filters.validate() {
+    if filters.validate().is_err() {
         return Err(StatusCode::BAD_REQUEST);
     }
     
@@ -258,7 +225,7 @@ pub async fn list_alerts(
             org_id: org_id.clone(),
             rule_id: "rule_001".to_string(),
             detection_id: "det_001".to_string(),
-            severity: "high".to_string(),
+            severity: Severity::High,
             status: "new".to_string(),
             title: "High Severity Detection".to_string(),
             description: "Multiple suspicious processes detected".to_string(),
@@ -267,6 +234,10 @@ pub async fn list_alerts(
             updated_at: Utc::now(),
         }
     ];
+    let mock_alerts: Vec<Alert> = mock_alerts
+        .into_iter()
+        .filter(|a| severity_matches(a.severity, filters.severity, filters.min_severity))
+        .collect();
     
     let total = mock_alerts.len() as u64;
     let total_pages = ((total as f64) / (filters.pagination.per_page as f64)).ceil() as u32;
@@ -284,6 +255,11 @@ pub async fn list_alerts(
     Ok((StatusCode::OK, Json(response)))
 }
 
+/// Applies the exact `severity` and the `min_severity` range filters together
+fn severity_matches(severity: Severity, exact: Option<Severity>, min: Option<Severity>) -> bool {
+    exact.is_none_or(|s| severity == s) && min.is_none_or(|min| severity >= min)
+}
+
 pub fn routes() -> axum::Router<AppState> {
     axum::Router::new()
         .route("/detections", axum::routing::get(list_detections))
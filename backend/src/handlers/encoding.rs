@@ -0,0 +1,206 @@
+use std::io::Read;
+use axum::{
+    body::Bytes,
+    extract::{FromRequest, Request},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::de::DeserializeOwned;
+use crate::config::AppState;
+
+/// Largest zstd window accepted (8 MiB), so a crafted frame cannot make the decoder allocate more
+const ZSTD_WINDOW_LOG_MAX: u32 = 23;
+
+/// Request body negotiated from `Content-Type` and `Content-Encoding`.
+///
+/// Accepts JSON or MessagePack (`application/msgpack`, struct fields encoded as maps),
+/// either uncompressed or gzip/zstd compressed. The compressed body is bounded by the
+/// router's `DefaultBodyLimit`; the decompressed body by `MAX_REQUEST_SIZE` as well,
+/// so a small compressed payload cannot expand without limit.
+/// The second field is the decompressed body size in bytes.
+pub struct IngestBody<T>(pub T, pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BodyFormat {
+    Json,
+    MessagePack,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ContentCoding {
+    Identity,
+    Gzip,
+    Zstd,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BodyRejection {
+    #[error("Expected Content-Type application/json or application/msgpack")]
+    UnsupportedContentType,
+
+    #[error("Unsupported Content-Encoding {0}; use gzip, zstd or identity")]
+    UnsupportedEncoding(String),
+
+    #[error("Request body exceeds {0} bytes")]
+    TooLarge(usize),
+
+    #[error("Failed to read request body: {0}")]
+    Unreadable(String),
+
+    #[error("Failed to decompress request body")]
+    Corrupt,
+
+    #[error("Malformed request body: {0}")]
+    Malformed(String),
+
+    #[error("Request body does not match the expected schema: {0}")]
+    Invalid(String),
+}
+
+impl IntoResponse for BodyRejection {
+    fn into_response(self) -> Response {
+        let status = match &self {
+            BodyRejection::UnsupportedContentType | BodyRejection::UnsupportedEncoding(_) => {
+                StatusCode::UNSUPPORTED_MEDIA_TYPE
+            }
+            BodyRejection::TooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            BodyRejection::Unreadable(_) | BodyRejection::Corrupt | BodyRejection::Malformed(_) => {
+                StatusCode::BAD_REQUEST
+            }
+            BodyRejection::Invalid(_) => StatusCode::UNPROCESSABLE_ENTITY,
+        };
+        tracing::warn!("Rejected ingest body: {}", self);
+        (status, self.to_string()).into_response()
+    }
+}
+
+impl<T: DeserializeOwned> FromRequest<AppState> for IngestBody<T> {
+    type Rejection = BodyRejection;
+
+    async fn from_request(req: Request, state: &AppState) -> Result<Self, Self::Rejection> {
+        let format = BodyFormat::from_headers(req.headers())?;
+        let coding = ContentCoding::from_headers(req.headers())?;
+        let bytes = Bytes::from_request(req, state).await.map_err(|e| match e.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => BodyRejection::TooLarge(state.max_request_size),
+            _ => BodyRejection::Unreadable(e.body_text()),
+        })?;
+
+        let decoded = coding.decode(bytes, state.max_request_size).await?;
+        format.deserialize(&decoded).map(|value| IngestBody(value, decoded.len()))
+    }
+}
+
+impl BodyFormat {
+    fn from_headers(headers: &HeaderMap) -> Result<Self, BodyRejection> {
+        let content_type = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .ok_or(BodyRejection::UnsupportedContentType)?;
+        let essence = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+
+        match essence.as_str() {
+            "application/json" => Ok(BodyFormat::Json),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => Ok(BodyFormat::MessagePack),
+            other if other.starts_with("application/") && other.ends_with("+json") => Ok(BodyFormat::Json),
+            _ => Err(BodyRejection::UnsupportedContentType),
+        }
+    }
+
+    fn deserialize<T: DeserializeOwned>(self, body: &[u8]) -> Result<T, BodyRejection> {
+        match self {
+            BodyFormat::Json => serde_json::from_slice(body).map_err(|e| match e.classify() {
+                serde_json::error::Category::Data => BodyRejection::Invalid(e.to_string()),
+                _ => BodyRejection::Malformed(e.to_string()),
+            }),
+            BodyFormat::MessagePack => rmp_serde::from_slice(body).map_err(|e| match e {
+                rmp_serde::decode::Error::Syntax(message) => BodyRejection::Invalid(message),
+                other => BodyRejection::Malformed(other.to_string()),
+            }),
+        }
+    }
+}
+
+impl ContentCoding {
+    fn from_headers(headers: &HeaderMap) -> Result<Self, BodyRejection> {
+        let Some(value) = headers.get(header::CONTENT_ENCODING) else {
+            return Ok(ContentCoding::Identity);
+        };
+        let value = value
+            .to_str()
+            .map_err(|_| BodyRejection::UnsupportedEncoding("(non-ASCII)".to_string()))?
+            .trim()
+            .to_ascii_lowercase();
+
+        // Stacked codings such as "gzip, zstd" are not supported
+        match value.as_str() {
+            "" | "identity" => Ok(ContentCoding::Identity),
+            "gzip" | "x-gzip" => Ok(ContentCoding::Gzip),
+            "zstd" => Ok(ContentCoding::Zstd),
+            _ => Err(BodyRejection::UnsupportedEncoding(value)),
+        }
+    }
+
+    /// Decompression is CPU-bound, so it runs on the blocking pool rather than a runtime worker
+    async fn decode(self, body: Bytes, limit: usize) -> Result<Bytes, BodyRejection> {
+        if self == ContentCoding::Identity {
+            return Ok(body);
+        }
+        tokio::task::spawn_blocking(move || self.decode_blocking(body, limit))
+            .await
+            .map_err(|_| BodyRejection::Corrupt)?
+    }
+
+    fn decode_blocking(self, body: Bytes, limit: usize) -> Result<Bytes, BodyRejection> {
+        match self {
+            ContentCoding::Identity => Ok(body),
+            ContentCoding::Gzip => read_limited(flate2::read::MultiGzDecoder::new(&body[..]), limit),
+            ContentCoding::Zstd => {
+                let mut decoder = zstd::stream::read::Decoder::new(&body[..]).map_err(|_| BodyRejection::Corrupt)?;
+                decoder.window_log_max(ZSTD_WINDOW_LOG_MAX).map_err(|_| BodyRejection::Corrupt)?;
+                read_limited(decoder, limit)
+            }
+        }
+    }
+}
+
+/// Decompresses at most `limit` bytes, failing instead of reading further
+fn read_limited(reader: impl Read, limit: usize) -> Result<Bytes, BodyRejection> {
+    let mut decoded = Vec::new();
+    reader
+        .take(limit as u64 + 1)
+        .read_to_end(&mut decoded)
+        .map_err(|_| BodyRejection::Corrupt)?;
+    if decoded.len() > limit {
+        return Err(BodyRejection::TooLarge(limit));
+    }
+    Ok(decoded.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use super::*;
+
+    fn gzip(data: &[u8]) -> Bytes {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap().into()
+    }
+
+    #[tokio::test]
+    async fn decompresses_up_to_the_limit() {
+        let body = vec![b'a'; 1024];
+        assert_eq!(ContentCoding::Gzip.decode(gzip(&body), 1024).await.unwrap(), body);
+        let zstd = zstd::encode_all(&body[..], 3).unwrap();
+        assert_eq!(ContentCoding::Zstd.decode(zstd.into(), 1024).await.unwrap(), body);
+
+        assert!(matches!(
+            ContentCoding::Gzip.decode(gzip(&body), 1023).await,
+            Err(BodyRejection::TooLarge(1023))
+        ));
+        assert!(matches!(
+            ContentCoding::Gzip.decode(Bytes::from_static(b"not gzip"), 1024).await,
+            Err(BodyRejection::Corrupt)
+        ));
+    }
+}
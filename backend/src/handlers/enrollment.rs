@@ -0,0 +1,227 @@
+use axum::{
+    extract::{Extension, State},
+    response::IntoResponse,
+    Json,
+    http::StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+use crate::{
+    auth::jwt::Claims,
+    config::AppState,
+    models::{agent::{AgentRecord, AgentStatus}, enrollment::EnrollmentToken},
+};
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateEnrollmentTokenRequest {
+    #[validate(range(min = 1, max = 10000, message = "Max uses must be between 1 and 10000"))]
+    #[serde(default = "default_max_uses")]
+    pub max_uses: u32,
+
+    #[validate(range(min = 60, max = 604800, message = "TTL must be between 1 minute and 7 days"))]
+    #[serde(default = "default_ttl_secs")]
+    pub ttl_secs: u64,
+
+    /// Agent name template; `{hostname}` is replaced on enrollment
+    #[validate(length(min = 1, max = 200, message = "Name template must be 1-200 characters"))]
+    #[serde(default = "default_name_template")]
+    pub name_template: String,
+
+    #[validate(length(max = 20, message = "At most 20 tags are allowed"))]
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+fn default_max_uses() -> u32 { 1 }
+fn default_ttl_secs() -> u64 { 3600 }
+fn default_name_template() -> String { "{hostname}".to_string() }
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EnrollmentTokenResponse {
+    pub id: String,
+    /// Plaintext token; only returned once
+    pub token: String,
+    pub org_id: String,
+    pub name_template: String,
+    pub tags: Vec<String>,
+    pub max_uses: u32,
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct EnrollAgentRequest {
+    #[validate(length(min = 1, max = 100, message = "Token must be specified"))]
+    pub token: String,
+
+    #[validate(length(min = 1, max = 255, message = "Hostname must be 1-255 characters"))]
+    pub hostname: String,
+
+    #[validate(length(min = 1, max = 50, message = "Platform must be 1-50 characters"))]
+    pub platform: String,
+
+    #[validate(length(min = 1, max = 50, message = "Agent version must be 1-50 characters"))]
+    pub agent_version: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EnrollAgentResponse {
+    pub agent_id: String,
+    pub org_id: String,
+    pub name: String,
+    pub tags: Vec<String>,
+    /// Agent API key for `X-API-Key`; only returned once
+    pub api_key: String,
+}
+
+/// Create a short-lived enrollment token for new agents
+#[utoipa::path(
+    post,
+    path = "/v1/enrollment-tokens",
+    request_body = CreateEnrollmentTokenRequest,
+    responses(
+        (status = 201, description = "Enrollment token created", body = EnrollmentTokenResponse),
+        (status = 400, description = "Invalid request format"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin role required")
+    ),
+    security(("bearerAuth" = [])),
+    tag = "Agents"
+)]
+pub async fn create_enrollment_token(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<CreateEnrollmentTokenRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    if claims.role != "admin" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    if let Err(validation_errors) = payload.validate() {
+        tracing::warn!("Enrollment token validation failed: {:?}", validation_errors);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let now = Utc::now();
+    let token = EnrollmentToken {
+        id: Uuid::new_v4().to_string(),
+        org_id: claims.org_id.clone(),
+        name_template: payload.name_template,
+        tags: payload.tags,
+        max_uses: payload.max_uses,
+        uses: 0,
+        expires_at: now + Duration::seconds(payload.ttl_secs as i64),
+        created_by: claims.sub.clone(),
+        created_at: now,
+    };
+    let secret = app_state.enrollment_tokens.create(token.clone());
+
+    tracing::info!(
+        "Enrollment token created: id={}, org_id={}, max_uses={}, expires_at={}",
+        token.id,
+        token.org_id,
+        token.max_uses,
+        token.expires_at
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(EnrollmentTokenResponse {
+            id: token.id,
+            token: secret,
+            org_id: token.org_id,
+            name_template: token.name_template,
+            tags: token.tags,
+            max_uses: token.max_uses,
+            expires_at: token.expires_at,
+        })
+    ))
+}
+
+/// Exchange an enrollment token for an agent identity and API key
+#[utoipa::path(
+    post,
+    path = "/agents/enroll",
+    request_body = EnrollAgentRequest,
+    responses(
+        (status = 201, description = "Agent enrolled", body = EnrollAgentResponse),
+        (status = 400, description = "Invalid request format"),
+        (status = 401, description = "Invalid, expired or exhausted enrollment token")
+    ),
+    tag = "Agents"
+)]
+pub async fn enroll_agent(
+    State(app_state): State<AppState>,
+    Json(payload): Json<EnrollAgentRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    if let Err(validation_errors) = payload.validate() {
+        tracing::warn!("Enrollment validation failed: {:?}", validation_errors);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let now = Utc::now();
+    let token = match app_state.enrollment_tokens.redeem(&payload.token, now) {
+        Ok(token) => token,
+        Err(e) => {
+            // Do not tell the caller why the token was rejected
+            tracing::warn!("Enrollment rejected for host {}: {}", payload.hostname, e);
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    };
+
+    let agent_id = format!("agent_{}", Uuid::new_v4().simple());
+    let record = AgentRecord {
+        id: agent_id.clone(),
+        org_id: token.org_id.clone(),
+        name: token.agent_name(&payload.hostname),
+        platform: payload.platform,
+        version: payload.agent_version,
+        status: AgentStatus::Offline,
+        tags: token.tags.clone(),
+        cpu_usage: None,
+        memory_usage: None,
+        last_scan_at: None,
+        scan_count: 0,
+        last_heartbeat: None,
+        clock_skew_ms: None,
+        created_at: now,
+        updated_at: now,
+    };
+    let name = record.name.clone();
+    app_state.agents.register(record);
+
+    let api_key = app_state.api_keys.issue(&app_state.api_key_prefix, &token.org_id, &agent_id, now);
+
+    tracing::info!(
+        "Agent enrolled: agent_id={}, org_id={}, token_id={}, hostname={}",
+        agent_id,
+        token.org_id,
+        token.id,
+        payload.hostname
+    );
+
+    Ok((
+        StatusCode::CREATED,
+        Json(EnrollAgentResponse {
+            agent_id,
+            org_id: token.org_id,
+            name,
+            tags: token.tags,
+            api_key,
+        })
+    ))
+}
+
+/// Admin routes, mounted under the JWT-protected `/v1` tree
+pub fn admin_routes() -> axum::Router<AppState> {
+    axum::Router::new()
+        .route("/enrollment-tokens", axum::routing::post(create_enrollment_token))
+}
+
+/// Public agent routes; the enrollment token is the credential
+pub fn routes() -> axum::Router<AppState> {
+    axum::Router::new()
+        .route("/enroll", axum::routing::post(enroll_agent))
+}
@@ -0,0 +1,238 @@
+use axum::{
+    extract::{Extension, Path, State},
+    response::IntoResponse,
+    Json,
+    http::StatusCode,
+};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+use chrono::Utc;
+use crate::{
+    auth::jwt::Claims,
+    config::AppState,
+    models::event_schema::{EventTypePolicy, EventTypeSchema, Severity},
+};
+
+/// Largest accepted metadata schema, serialized
+const MAX_SCHEMA_BYTES: usize = 64 * 1024;
+
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct PutEventTypeRequest {
+    #[validate(length(max = 500, message = "Description too long"))]
+    pub description: Option<String>,
+
+    /// JSON Schema for the event `metadata`; omitted accepts any metadata
+    #[schema(value_type = Object)]
+    #[serde(default = "any_metadata")]
+    pub metadata_schema: serde_json::Value,
+
+    /// Severities events of this type may carry; empty allows any
+    #[serde(default)]
+    pub allowed_severities: Vec<Severity>,
+}
+
+fn any_metadata() -> serde_json::Value {
+    serde_json::json!({})
+}
+
+impl PutEventTypeRequest {
+    /// Checks what the derive cannot express: the schema size
+    fn check_limits(&self) -> Result<(), String> {
+        let schema_len = serde_json::to_vec(&self.metadata_schema).map_or(usize::MAX, |bytes| bytes.len());
+        if schema_len > MAX_SCHEMA_BYTES {
+            return Err(format!("Metadata schema exceeds {} bytes", MAX_SCHEMA_BYTES));
+        }
+        Ok(())
+    }
+}
+
+fn dedup_severities(mut severities: Vec<Severity>) -> Vec<Severity> {
+    severities.sort();
+    severities.dedup();
+    severities
+}
+
+/// List the event types registered for the org
+#[utoipa::path(
+    get,
+    path = "/v1/event-types",
+    responses(
+        (status = 200, description = "Registered event types with their metadata schemas", body = Vec<EventTypeSchema>),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(("bearerAuth" = [])),
+    tag = "Ingest"
+)]
+pub async fn list_event_types(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> impl IntoResponse {
+    Json(app_state.event_schemas.list(&claims.org_id))
+}
+
+/// Get one registered event type
+#[utoipa::path(
+    get,
+    path = "/v1/event-types/{event_type}",
+    params(("event_type" = String, Path, description = "Event type")),
+    responses(
+        (status = 200, description = "Event type", body = EventTypeSchema),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Event type not registered")
+    ),
+    security(("bearerAuth" = [])),
+    tag = "Ingest"
+)]
+pub async fn get_event_type(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(event_type): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    app_state.event_schemas
+        .get(&claims.org_id, &event_type)
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Register an event type, or replace its schema and allowed severities
+#[utoipa::path(
+    put,
+    path = "/v1/event-types/{event_type}",
+    params(("event_type" = String, Path, description = "Event type, 1-100 characters")),
+    request_body = PutEventTypeRequest,
+    responses(
+        (status = 200, description = "Event type registered", body = EventTypeSchema),
+        (status = 400, description = "Invalid request format or metadata schema"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin role required")
+    ),
+    security(("bearerAuth" = [])),
+    tag = "Ingest"
+)]
+pub async fn put_event_type(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(event_type): Path<String>,
+    Json(payload): Json<PutEventTypeRequest>,
+) -> Result<impl IntoResponse, StatusCode> {
+    if claims.role != "admin" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if event_type.is_empty() || event_type.len() > 100 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Err(validation_errors) = payload.validate() {
+        tracing::warn!("Event type validation failed: {:?}", validation_errors);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if let Err(e) = payload.check_limits() {
+        tracing::warn!("Event type validation failed: {}", e);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let schema = app_state.event_schemas
+        .upsert(
+            &claims.org_id,
+            &event_type,
+            payload.description,
+            payload.metadata_schema,
+            dedup_severities(payload.allowed_severities),
+            Utc::now(),
+        )
+        .map_err(|e| {
+            tracing::warn!("Event type rejected: event_type={}, {}", event_type, e);
+            StatusCode::BAD_REQUEST
+        })?;
+    tracing::info!("Event type registered: event_type={}, org_id={}", schema.event_type, claims.org_id);
+
+    Ok(Json(schema))
+}
+
+/// Remove an event type from the registry
+#[utoipa::path(
+    delete,
+    path = "/v1/event-types/{event_type}",
+    params(("event_type" = String, Path, description = "Event type")),
+    responses(
+        (status = 204, description = "Event type removed"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin role required"),
+        (status = 404, description = "Event type not registered")
+    ),
+    security(("bearerAuth" = [])),
+    tag = "Ingest"
+)]
+pub async fn delete_event_type(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Path(event_type): Path<String>,
+) -> Result<impl IntoResponse, StatusCode> {
+    if claims.role != "admin" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    app_state.event_schemas
+        .delete(&claims.org_id, &event_type)
+        .map_err(|_| StatusCode::NOT_FOUND)?;
+    tracing::info!("Event type removed: event_type={}, org_id={}", event_type, claims.org_id);
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// How ingest treats event types missing from the registry
+#[utoipa::path(
+    get,
+    path = "/v1/event-type-policy",
+    responses(
+        (status = 200, description = "Event type policy", body = EventTypePolicy),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(("bearerAuth" = [])),
+    tag = "Ingest"
+)]
+pub async fn get_event_type_policy(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> impl IntoResponse {
+    Json(app_state.event_schemas.policy(&claims.org_id))
+}
+
+/// Replace the event type policy of the org
+#[utoipa::path(
+    put,
+    path = "/v1/event-type-policy",
+    request_body = EventTypePolicy,
+    responses(
+        (status = 200, description = "Policy updated", body = EventTypePolicy),
+        (status = 400, description = "Invalid request format"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin role required")
+    ),
+    security(("bearerAuth" = [])),
+    tag = "Ingest"
+)]
+pub async fn put_event_type_policy(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<EventTypePolicy>,
+) -> Result<impl IntoResponse, StatusCode> {
+    if claims.role != "admin" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let policy = app_state.event_schemas.set_policy(&claims.org_id, payload, Utc::now());
+    tracing::info!("Event type policy updated for org_id={}: {:?}", claims.org_id, policy);
+
+    Ok(Json(policy))
+}
+
+pub fn routes() -> axum::Router<AppState> {
+    axum::Router::new()
+        .route("/event-types", axum::routing::get(list_event_types))
+        .route(
+            "/event-types/{event_type}",
+            axum::routing::get(get_event_type).put(put_event_type).delete(delete_event_type),
+        )
+        .route("/event-type-policy", axum::routing::get(get_event_type_policy).put(put_event_type_policy))
+}
@@ -1,76 +1,65 @@
 use axum::{
-    extract::Extension,
-    response::IntoResponse,
+    body::Body,
+    extract::{Extension, State},
+    response::{IntoResponse, Response},
     Json,
-    http::StatusCode,
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
 };
-use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use futures::StreamExt;
 use validator::Validate;
 use chrono::{DateTime, Utc};
-use crate::{auth::api_key::AgentAuth, config::AppState};
+use crate::{auth::api_key::AgentAuth, config::AppState, handlers::realtime::RealtimeEvent, models::{clock::{OutOfBoundsAction, TimestampFlag}, health::HealthSample, quota::{QuotaRefusal, RateLimit}, version_policy::VersionStatus}, tasks::ingest_workers::IngestJob};
+use super::{agent_config::resolve_for_agent, encoding::IngestBody};
 
-#[derive(Debug, Deserialize, Validate, ToSchema)]
-pub struct IngestBatchRequest {
-    #[validate(length(min = 1, max = 1000, message = "Events array must contain 1-1000 items"))]
-    pub events: Vec<DetectionEvent>,
-    pub heartbeat: Option<AgentHeartbeat>,
-}
-
-#[derive(Debug, Deserialize, Validate, ToSchema, Serialize)]
-pub struct DetectionEvent {
-    #[validate(length(min = 1, max = 100, message = "Event type must be 1-100 characters"))]
-    pub event_type: String,
-    
-    #[validate(length(min = 1, max = 50, message = "Severity must be specified"))]
-    pub severity: String,
-    
-    #[validate(length(max = 500, message = "Title too long"))]
-    pub title: Option<String>,
-    
-    #[validate(length(max = 2000, message = "Description too long"))]
-    pub description: Option<String>,
-    
-    pub metadata: serde_json::Value,
-    pub detected_at: DateTime<Utc>,
-}
-
-#[derive(Debug, Deserialize, Validate, ToSchema)]
-pub struct AgentHeartbeat {
-    pub agent_version: String,
-    pub platform: String,
-    pub cpu_usage: Option<f32>,
-    pub memory_usage: Option<f32>,
-    pub last_scan_at: Option<DateTime<Utc>>,
-    pub scan_count: Option<u64>,
-}
+pub use anticheat_protocol::ingest::{
+    AgentHeartbeat, DetectionEvent, IngestBatchRequest, IngestResponse, LineError, StreamIngestResponse,
+    IDEMPOTENCY_KEY_HEADER,
+};
 
-#[derive(Debug, Serialize, ToSchema)]
-pub struct IngestResponse {
-    pub success: bool,
-    pub processed: u32,
-    pub failed: u32,
-    pub errors: Vec<String>,
-}
+/// Longest accepted `Idempotency-Key` header value
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
 
 /// Ingest batch of detection events from agents
 #[utoipa::path(
     post,
     path = "/ingest/batch",
-    request_body = IngestBatchRequest,
+    request_body(
+        description = "JSON or MessagePack (struct fields as maps), optionally gzip or zstd compressed via `Content-Encoding`",
+        content(
+            (IngestBatchRequest = "application/json"),
+            (IngestBatchRequest = "application/msgpack")
+        )
+    ),
+    params(
+        ("Idempotency-Key" = Option<String>, Header, description = "Identifies the batch; resending a key already seen within the retention window reports every event as a duplicate"),
+        ("Content-Encoding" = Option<String>, Header, description = "`gzip` or `zstd` for a compressed body")
+    ),
     responses(
-        (status = 202, description = "Batch accepted for processing", body = IngestResponse),
-        (status = 400, description = "Invalid request format"),
+        (status = 202, description = "Batch (or heartbeat-only batch with no events) accepted for processing; events seen before are counted in `duplicates`", body = IngestResponse),
+        (status = 400, description = "Invalid request format or Idempotency-Key"),
         (status = 401, description = "Invalid or missing API key"),
-        (status = 413, description = "Payload too large")
+        (status = 413, description = "Payload too large, before or after decompression, or more events or bytes than an ingest quota allows in a whole window (`QUOTA_REQUEST_TOO_LARGE`); split the batch", body = IngestResponse),
+        (status = 415, description = "Unsupported Content-Type or Content-Encoding"),
+        (status = 426, description = "Agent version blocked by organization policy", body = IngestResponse),
+        (status = 429, description = "Agent or org ingest quota exhausted; nothing was accepted, retry after `Retry-After` seconds. Only valid events not seen before count against the quota, so replays are never refused", body = IngestResponse,
+            headers(
+                ("Retry-After" = u64, description = "Seconds until the exhausted quota resets"),
+                ("X-RateLimit-Limit" = u64, description = "Limit of the exhausted quota"),
+                ("X-RateLimit-Remaining" = u64, description = "Always 0"),
+                ("X-RateLimit-Reset" = u64, description = "Seconds until the quota resets")
+            )),
+        (status = 503, description = "Ingest queue full; nothing was accepted, retry the same batch after `Retry-After` seconds", body = IngestResponse,
+            headers(("Retry-After" = u64, description = "Seconds to wait before retrying")))
     ),
     security(("apiKeyAuth" = [])),
     tag = "Ingest"
 )]
 pub async fn batch_ingest(
+    State(app_state): State<AppState>,
     Extension(agent_auth): Extension<AgentAuth>,
-    Json(payload): Json<IngestBatchRequest>,
-) -> Result<impl IntoResponse, StatusCode> {
+    headers: HeaderMap,
+    IngestBody(payload, body_len): IngestBody<IngestBatchRequest>,
+) -> Result<Response, StatusCode> {
     // Validate the entire payload
     if let Err(validation_errors) = payload.validate() {
         tracing::warn!("Ingest validation failed: {:?}", validation_errors);
@@ -80,13 +69,74 @@ pub async fn batch_ingest(
                 success: false,
                 processed: 0,
                 failed: payload.events.len() as u32,
+                duplicates: 0,
                 errors: vec!["Invalid request format".to_string()],
+                error_code: None,
+                config: None,
+            })
+        ).into_response());
+    }
+
+    let idempotency_key = match headers.get(IDEMPOTENCY_KEY_HEADER) {
+        None => None,
+        Some(value) => match value.to_str().map(str::trim) {
+            Ok(key) if !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LEN => Some(key.to_string()),
+            _ => {
+                tracing::warn!("Ingest with invalid Idempotency-Key header");
+                return Ok((
+                    StatusCode::BAD_REQUEST,
+                    Json(IngestResponse {
+                        success: false,
+                        processed: 0,
+                        failed: payload.events.len() as u32,
+                        duplicates: 0,
+                        errors: vec![format!(
+                            "Idempotency-Key must be 1-{} visible ASCII characters",
+                            MAX_IDEMPOTENCY_KEY_LEN
+                        )],
+                        error_code: None,
+                        config: None,
+                    })
+                ).into_response());
+            }
+        },
+    };
+
+    // A heartbeat-only batch has nothing else to accept
+    if payload.events.is_empty() && payload.heartbeat.as_ref().is_some_and(|h| h.validate().is_err()) {
+        tracing::warn!("Heartbeat-only batch with invalid heartbeat");
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(IngestResponse {
+                success: false,
+                processed: 0,
+                failed: 0,
+                duplicates: 0,
+                errors: vec!["Invalid heartbeat format".to_string()],
+                error_code: None,
+                config: None,
             })
-        ));
+        ).into_response());
     }
 
     let org_id = &agent_auth.org_id;
     let agent_id = &agent_auth.agent_id;
+
+    let reported_version = payload.heartbeat.as_ref().map(|heartbeat| heartbeat.agent_version.as_str());
+    if let Some(version) = blocked_version(&app_state, org_id, agent_id, reported_version) {
+        return Ok((
+            StatusCode::UPGRADE_REQUIRED,
+            Json(IngestResponse {
+                success: false,
+                processed: 0,
+                failed: payload.events.len() as u32,
+                duplicates: 0,
+                errors: vec![blocked_version_message(&version)],
+                error_code: Some(AGENT_VERSION_BLOCKED.to_string()),
+                config: None,
+            })
+        ).into_response());
+    }
     
     tracing::info!(
         "Processing {} events from agent {} in org {}",
@@ -95,44 +145,150 @@ pub async fn batch_ingest(
         org_id
     );
 
-    let mut processed = 0u32;
-    let mut failed = 0u32;
-    let mut errors = Vec::new();
+    // Validate up front so queue space and quota are only spent on events that will be queued
+    let received_at = Utc::now();
+    let validated: Vec<Result<Option<TimestampFlag>, String>> = payload.events
+        .iter()
+        .map(|event| validate_event(&app_state, org_id, agent_id, event, received_at))
+        .collect();
 
-    // Process each event
-    for event in &payload.events {
-        // Validate individual event
-        if let Err(e) = event.validate() {
-            tracing::warn!("Event validation failed: {:?}", e);
-            errors.push("Invalid event format".to_string());
-            failed += 1;
-            continue;
-        }
-
-        // TODO: In production:
-        // 1. Store event in database with org_id and agent_id
-        // 2. Enqueue event ID for async rule processing
-        // 3. Publish to real-time channel for dashboard updates
-        
-        // For now, just log and increment counter
+    // Claim the batch key and event IDs before any capacity check. Claiming is atomic, so a
+    // retry of a batch that already got through, even one racing the original, is answered
+    // with its duplicates instead of being refused or charged again.
+    let replayed_batch = idempotency_key
+        .as_deref()
+        .is_some_and(|key| !app_state.idempotency.claim_batch(org_id, agent_id, key, received_at));
+    if replayed_batch {
         tracing::info!(
-            "Event processed: type={}, severity={}, org_id={}, agent_id={}",
-            event.event_type,
-            event.severity,
+            "Skipping events of replayed batch: idempotency_key={}, org_id={}, agent_id={}",
+            idempotency_key.as_deref().unwrap_or_default(),
             org_id,
             agent_id
         );
-        
-        processed += 1;
     }
+    let claimed: Vec<bool> = payload.events
+        .iter()
+        .zip(&validated)
+        .map(|(event, validated)| {
+            !replayed_batch && validated.is_ok() && claim_event(&app_state, org_id, agent_id, event, received_at)
+        })
+        .collect();
+    let new_events = claimed.iter().filter(|claimed| **claimed).count();
+    // A refused batch gives its claims back, so the agent can resend it as-is
+    let release_claims = || {
+        if let Some(key) = idempotency_key.as_deref().filter(|_| !replayed_batch) {
+            app_state.idempotency.release_batch(org_id, agent_id, key);
+        }
+        for (event, _) in payload.events.iter().zip(&claimed).filter(|(_, claimed)| **claimed) {
+            if let Some(event_id) = &event.event_id {
+                app_state.idempotency.release_event(org_id, agent_id, event_id);
+            }
+        }
+    };
+
+    let Some(mut slots) = app_state.ingest_queue.try_reserve(new_events) else {
+        release_claims();
+        let retry_after = app_state.ingest_queue.retry_after_secs();
+        tracing::warn!(
+            "Ingest queue full, refusing {} events: depth={}, org_id={}, agent_id={}",
+            payload.events.len(),
+            app_state.ingest_queue.depth(),
+            org_id,
+            agent_id
+        );
+        return Ok((
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, retry_after.to_string())],
+            Json(IngestResponse {
+                success: false,
+                processed: 0,
+                failed: payload.events.len() as u32,
+                duplicates: 0,
+                errors: vec![format!("Ingest queue is full, retry in {}s", retry_after)],
+                error_code: Some("INGEST_QUEUE_FULL".to_string()),
+                config: None,
+            })
+        ).into_response());
+    };
+
+    // Only events that will be queued are charged, along with their share of the body;
+    // heartbeat-only batches and replays are exempt, so a throttled agent still reports in
+    let mut rate_limit = None;
+    if new_events > 0 {
+        let bytes = (body_len as u64 * new_events as u64).div_ceil(payload.events.len() as u64);
+        match app_state.quotas.try_consume(org_id, agent_id, new_events as u64, bytes, received_at) {
+            Ok(tightest) => rate_limit = tightest,
+            Err(refusal) => {
+                release_claims();
+                let exceeded = refusal.rate_limit();
+                tracing::warn!(
+                    "Ingest quota exceeded, refusing {} events: quota={}, limit={}, too_large={}, org_id={}, agent_id={}",
+                    payload.events.len(),
+                    exceeded.kind,
+                    exceeded.limit,
+                    matches!(refusal, QuotaRefusal::TooLarge(_)),
+                    org_id,
+                    agent_id
+                );
+                let refused = |errors, error_code: &str| IngestResponse {
+                    success: false,
+                    processed: 0,
+                    failed: payload.events.len() as u32,
+                    duplicates: 0,
+                    errors,
+                    error_code: Some(error_code.to_string()),
+                    config: None,
+                };
+                // Waiting cannot help a batch that is over the limit by itself; it must be split
+                let response = match refusal {
+                    QuotaRefusal::Exceeded(_) => (
+                        StatusCode::TOO_MANY_REQUESTS,
+                        [(header::RETRY_AFTER, exceeded.reset_secs.to_string())],
+                        Json(refused(vec![quota_exceeded_message(&exceeded)], QUOTA_EXCEEDED)),
+                    ).into_response(),
+                    QuotaRefusal::TooLarge(_) => (
+                        StatusCode::PAYLOAD_TOO_LARGE,
+                        Json(refused(vec![quota_too_large_message(&exceeded)], QUOTA_REQUEST_TOO_LARGE)),
+                    ).into_response(),
+                };
+                return Ok(with_rate_limit(response, Some(exceeded)));
+            }
+        }
+    }
+
+    let mut processed = 0u32;
+    let mut failed = 0u32;
+    let mut duplicates = 0u32;
+    let mut errors = Vec::new();
+
+    // A replayed batch skips its events but still delivers the heartbeat.
+    // Storage, rules and realtime publishing of new events happen in the ingest workers.
+    if replayed_batch {
+        duplicates = payload.events.len() as u32;
+    } else {
+        for (index, event) in payload.events.iter().enumerate() {
+            match &validated[index] {
+                Err(error) => {
+                    errors.push(IngestResponse::event_error(index, error));
+                    failed += 1;
+                }
+                Ok(_) if !claimed[index] => duplicates += 1,
+                Ok(timestamp_flag) => {
+                    slots.send(IngestJob::new(org_id, agent_id, event.clone(), received_at, *timestamp_flag));
+                    processed += 1;
+                }
+            }
+        }
+    }
+    drop(slots);
 
     // Process heartbeat if present
+    let mut config = None;
     if let Some(heartbeat) = &payload.heartbeat {
         if let Err(e) = heartbeat.validate() {
             tracing::warn!("Heartbeat validation failed: {:?}", e);
             errors.push("Invalid heartbeat format".to_string());
         } else {
-            // TODO: Update agent status in database
             tracing::info!(
                 "Agent heartbeat: version={}, platform={}, org_id={}, agent_id={}",
                 heartbeat.agent_version,
@@ -140,24 +296,717 @@ pub async fn batch_ingest(
                 org_id,
                 agent_id
             );
+            let version_status = app_state.version_policies.evaluate(org_id, &heartbeat.agent_version);
+            if version_status != VersionStatus::Supported {
+                tracing::warn!(
+                    "Agent running {} version: version={}, org_id={}, agent_id={}",
+                    version_status,
+                    heartbeat.agent_version,
+                    org_id,
+                    agent_id
+                );
+            }
+
+            let now = Utc::now();
+            if let Some(change) = app_state.agents.record_heartbeat(org_id, agent_id, heartbeat, now) {
+                app_state.publish(RealtimeEvent::AgentStatusChanged(change));
+            }
+            app_state.health.record(org_id, agent_id, HealthSample {
+                at: now,
+                cpu_usage: heartbeat.cpu_usage,
+                memory_usage: heartbeat.memory_usage,
+            });
+
+            let current = resolve_for_agent(&app_state, org_id, agent_id);
+            if heartbeat.config_version.unwrap_or(0) != current.version {
+                config = Some(current);
+            }
         }
     }
 
     let success = failed == 0;
     let status_code = if success { StatusCode::ACCEPTED } else { StatusCode::PARTIAL_CONTENT };
 
-    Ok((
+    let response = (
         status_code,
         Json(IngestResponse {
             success,
             processed,
             failed,
+            duplicates,
             errors,
+            error_code: None,
+            config,
         })
-    ))
+    ).into_response();
+    Ok(with_rate_limit(response, rate_limit))
+}
+
+/// Streaming ingest of newline-delimited `DetectionEvent` JSON, for backfilling offline data.
+///
+/// The body may be of any length: lines are validated and queued as they arrive,
+/// so memory stays bounded by `MAX_STREAM_LINE_BYTES`. Blank lines are skipped.
+/// When the ingest queue is full the upload is slowed down rather than refused.
+/// A line that fails leaves the rest of the upload unaffected; give events an
+/// `event_id` so re-sending a partially ingested upload only stores what is missing.
+/// Each queued line counts against the ingest quotas; the upload stops at the first line over
+/// quota, which is reported as failed, and nothing after it is read.
+#[utoipa::path(
+    post,
+    path = "/ingest/stream",
+    request_body(content = String, content_type = "application/x-ndjson", description = "One DetectionEvent JSON object per line"),
+    responses(
+        (status = 200, description = "Every line was ingested (or skipped as a duplicate)", body = StreamIngestResponse),
+        (status = 206, description = "Some lines failed; see `errors` for their line numbers", body = StreamIngestResponse),
+        (status = 401, description = "Invalid or missing API key"),
+        (status = 426, description = "Agent version blocked by organization policy", body = StreamIngestResponse),
+        (status = 429, description = "Ingest quota exhausted; the line reported last in `errors` and every line after it were not ingested", body = StreamIngestResponse,
+            headers(("Retry-After" = u64, description = "Seconds until the exhausted quota resets"))),
+        (status = 503, description = "Ingest workers stopped; the line reported last in `errors` and every line after it were not ingested", body = StreamIngestResponse)
+    ),
+    security(("apiKeyAuth" = [])),
+    tag = "Ingest"
+)]
+pub async fn stream_ingest(
+    State(app_state): State<AppState>,
+    Extension(agent_auth): Extension<AgentAuth>,
+    body: Body,
+) -> Response {
+    let org_id = &agent_auth.org_id;
+    let agent_id = &agent_auth.agent_id;
+
+    let mut report = StreamIngestResponse {
+        success: true,
+        lines: 0,
+        processed: 0,
+        failed: 0,
+        duplicates: 0,
+        errors: Vec::new(),
+        errors_truncated: false,
+        error_code: None,
+    };
+
+    if let Some(version) = blocked_version(&app_state, org_id, agent_id, None) {
+        report.success = false;
+        report.errors.push(LineError { line: 0, error: blocked_version_message(&version) });
+        report.error_code = Some(AGENT_VERSION_BLOCKED.to_string());
+        return (StatusCode::UPGRADE_REQUIRED, Json(report)).into_response();
+    }
+
+    tracing::info!("Streaming ingest started: org_id={}, agent_id={}", org_id, agent_id);
+
+    let mut lines = LineSplitter::new(MAX_STREAM_LINE_BYTES);
+    let mut chunks = body.into_data_stream();
+    let mut interrupted = false;
+    let mut rate_limit = None;
+    let mut stopped = None;
+    'body: while let Some(chunk) = chunks.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                // Whatever was read so far stays ingested; the agent resumes from the report
+                tracing::warn!("Streaming ingest body aborted after {} lines: {}", report.lines, e);
+                interrupted = true;
+                break;
+            }
+        };
+        for line in lines.push(&chunk) {
+            if let Err(stop) = ingest_line(&app_state, org_id, agent_id, &mut report, &mut rate_limit, line).await {
+                stopped = Some(stop);
+                break 'body;
+            }
+        }
+    }
+    if let Some(line) = lines.finish().filter(|_| !interrupted && stopped.is_none()) {
+        stopped = ingest_line(&app_state, org_id, agent_id, &mut report, &mut rate_limit, line).await.err();
+    }
+
+    tracing::info!(
+        "Streaming ingest finished: lines={}, processed={}, failed={}, duplicates={}, org_id={}, agent_id={}",
+        report.lines,
+        report.processed,
+        report.failed,
+        report.duplicates,
+        org_id,
+        agent_id
+    );
+
+    match stopped {
+        Some(StreamStop::Throttled) => {
+            let retry_after = rate_limit.map_or(0, |limit| limit.reset_secs);
+            report.success = false;
+            report.error_code = Some(QUOTA_EXCEEDED.to_string());
+            let response = (StatusCode::TOO_MANY_REQUESTS, [(header::RETRY_AFTER, retry_after.to_string())], Json(report));
+            return with_rate_limit(response.into_response(), rate_limit);
+        }
+        Some(StreamStop::Unavailable) => {
+            report.success = false;
+            report.error_code = Some(INGEST_UNAVAILABLE.to_string());
+            return (StatusCode::SERVICE_UNAVAILABLE, Json(report)).into_response();
+        }
+        None => {}
+    }
+    if interrupted {
+        report.success = false;
+        report.error_code = Some("STREAM_INTERRUPTED".to_string());
+        return (StatusCode::BAD_REQUEST, Json(report)).into_response();
+    }
+    report.success = report.failed == 0;
+    let status_code = if report.success { StatusCode::OK } else { StatusCode::PARTIAL_CONTENT };
+    with_rate_limit((status_code, Json(report)).into_response(), rate_limit)
+}
+
+const INGEST_UNAVAILABLE: &str = "INGEST_UNAVAILABLE";
+
+/// Why a stream upload ended early. The line that stopped it is reported as failed;
+/// nothing after it is read, so the agent resends from that line on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StreamStop {
+    /// An ingest quota is exhausted
+    Throttled,
+    /// The ingest workers have stopped
+    Unavailable,
+}
+
+/// Queues the event on one line, waiting for queue space rather than refusing the upload.
+/// Fails the line and ends the upload once an ingest quota is exhausted or the queue is closed;
+/// `rate_limit` tracks the quota closest to exhaustion for the response headers.
+/// Only lines that will be queued are charged: invalid lines and duplicates are free.
+async fn ingest_line(
+    app_state: &AppState,
+    org_id: &str,
+    agent_id: &str,
+    report: &mut StreamIngestResponse,
+    rate_limit: &mut Option<RateLimit>,
+    line: Line,
+) -> Result<(), StreamStop> {
+    let received_at = Utc::now();
+    let number = line.number;
+    let bytes = match &line.content {
+        LineContent::Text(text) => text.len() as u64,
+        LineContent::TooLong => 0,
+    };
+    let Some((event, timestamp_flag)) = parse_line(app_state, org_id, agent_id, report, line, received_at) else {
+        return Ok(());
+    };
+    // Claimed first, so a duplicate is free even when another upload races this one;
+    // a line that is then refused gives its claim back
+    if !claim_event(app_state, org_id, agent_id, &event, received_at) {
+        report.duplicates += 1;
+        return Ok(());
+    }
+    let release_claim = || {
+        if let Some(event_id) = &event.event_id {
+            app_state.idempotency.release_event(org_id, agent_id, event_id);
+        }
+    };
+
+    // The slot is taken before the quota is charged, so a line the queue cannot take costs nothing
+    let Some(slot) = app_state.ingest_queue.reserve().await else {
+        release_claim();
+        tracing::error!("Ingest queue closed, stopping stream at line {}: org_id={}, agent_id={}", number, org_id, agent_id);
+        report.failed += 1;
+        report.errors.push(LineError {
+            line: number,
+            error: "Ingest workers are not running; this line and the rest of the upload were not ingested".to_string(),
+        });
+        return Err(StreamStop::Unavailable);
+    };
+
+    match app_state.quotas.try_consume(org_id, agent_id, 1, bytes, received_at) {
+        Ok(tightest) => *rate_limit = tightest,
+        // Only this line can never fit, so the rest of the upload goes on
+        Err(QuotaRefusal::TooLarge(too_large)) => {
+            release_claim();
+            tracing::warn!(
+                "Stream line larger than ingest quota: line={}, quota={}, limit={}, org_id={}, agent_id={}",
+                number,
+                too_large.kind,
+                too_large.limit,
+                org_id,
+                agent_id
+            );
+            push_line_error(report, number, quota_too_large_message(&too_large));
+            return Ok(());
+        }
+        Err(QuotaRefusal::Exceeded(exceeded)) => {
+            release_claim();
+            tracing::warn!(
+                "Ingest quota exceeded, stopping stream at line {}: quota={}, limit={}, org_id={}, agent_id={}",
+                number,
+                exceeded.kind,
+                exceeded.limit,
+                org_id,
+                agent_id
+            );
+            report.failed += 1;
+            report.errors.push(LineError { line: number, error: quota_exceeded_message(&exceeded) });
+            *rate_limit = Some(exceeded);
+            return Err(StreamStop::Throttled);
+        }
+    }
+
+    slot.send(IngestJob::new(org_id, agent_id, event, received_at, timestamp_flag));
+    report.processed += 1;
+    Ok(())
+}
+
+/// Parses and validates one line, recording failures in the report; blank lines yield `None` silently
+fn parse_line(
+    app_state: &AppState,
+    org_id: &str,
+    agent_id: &str,
+    report: &mut StreamIngestResponse,
+    line: Line,
+    received_at: DateTime<Utc>,
+) -> Option<(DetectionEvent, Option<TimestampFlag>)> {
+    let event = match line.content {
+        LineContent::Text(text) if text.trim_ascii().is_empty() => return None,
+        LineContent::Text(text) => serde_json::from_slice::<DetectionEvent>(&text)
+            .map_err(|e| format!("Invalid JSON: {}", e)),
+        LineContent::TooLong => Err(format!("Line exceeds {} bytes", MAX_STREAM_LINE_BYTES)),
+    };
+    report.lines += 1;
+
+    let validated = event.and_then(|event| {
+        validate_event(app_state, org_id, agent_id, &event, received_at).map(|flag| (event, flag))
+    });
+    match validated {
+        Ok(validated) => Some(validated),
+        Err(error) => {
+            push_line_error(report, line.number, error);
+            None
+        }
+    }
+}
+
+/// Counts a failed line, keeping its error unless `MAX_STREAM_ERRORS` were already reported
+fn push_line_error(report: &mut StreamIngestResponse, line: u64, error: String) {
+    report.failed += 1;
+    if report.errors.len() < MAX_STREAM_ERRORS {
+        report.errors.push(LineError { line, error });
+    } else {
+        report.errors_truncated = true;
+    }
+}
+
+/// Longest line accepted by `/ingest/stream`; longer lines are reported and skipped
+const MAX_STREAM_LINE_BYTES: usize = 1024 * 1024;
+
+/// Failed lines listed in a stream report; further failures are only counted
+const MAX_STREAM_ERRORS: usize = 1000;
+
+struct Line {
+    number: u64,
+    content: LineContent,
+}
+
+enum LineContent {
+    Text(Vec<u8>),
+    TooLong,
+}
+
+/// Splits body chunks into lines without ever buffering more than `max_len` bytes
+struct LineSplitter {
+    max_len: usize,
+    buffer: Vec<u8>,
+    /// The current line went past `max_len`; its remaining bytes are discarded
+    overflowed: bool,
+    number: u64,
+}
+
+impl LineSplitter {
+    fn new(max_len: usize) -> Self {
+        Self { max_len, buffer: Vec::new(), overflowed: false, number: 0 }
+    }
+
+    /// Lines completed by this chunk; a trailing partial line is kept for the next one
+    fn push(&mut self, mut chunk: &[u8]) -> Vec<Line> {
+        let mut lines = Vec::new();
+        while let Some(end) = chunk.iter().position(|b| *b == b'\n') {
+            self.append(&chunk[..end]);
+            lines.push(self.take());
+            chunk = &chunk[end + 1..];
+        }
+        self.append(chunk);
+        lines
+    }
+
+    /// The final line when the body does not end with a newline
+    fn finish(mut self) -> Option<Line> {
+        (!self.buffer.is_empty() || self.overflowed).then(|| self.take())
+    }
+
+    fn append(&mut self, bytes: &[u8]) {
+        if self.overflowed {
+            return;
+        }
+        if self.buffer.len() + bytes.len() > self.max_len {
+            self.overflowed = true;
+            self.buffer = Vec::new();
+            return;
+        }
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    fn take(&mut self) -> Line {
+        self.number += 1;
+        let content = if std::mem::take(&mut self.overflowed) {
+            LineContent::TooLong
+        } else {
+            LineContent::Text(std::mem::take(&mut self.buffer))
+        };
+        Line { number: self.number, content }
+    }
+}
+
+const INVALID_EVENT: &str = "Invalid event format";
+
+/// Checks the event format, its severity and metadata against the org's event type registry,
+/// and its `detected_at` against the timestamp bounds. An out-of-bounds timestamp that is
+/// only flagged is returned rather than failing the event.
+fn validate_event(
+    app_state: &AppState,
+    org_id: &str,
+    agent_id: &str,
+    event: &DetectionEvent,
+    received_at: DateTime<Utc>,
+) -> Result<Option<TimestampFlag>, String> {
+    if let Err(e) = event.validate() {
+        tracing::warn!("Event validation failed: {:?}", e);
+        return Err(INVALID_EVENT.to_string());
+    }
+    app_state.event_schemas
+        .check(org_id, &event.event_type, event.severity, &event.metadata)
+        .inspect_err(|e| tracing::warn!("Event rejected by event type registry: {}, org_id={}", e, org_id))?;
+
+    let bounds = &app_state.timestamp_bounds;
+    let Some(flag) = bounds.check(event.detected_at, received_at) else {
+        return Ok(None);
+    };
+    let clock_skew_ms = app_state.agents.get(org_id, agent_id).and_then(|agent| agent.clock_skew_ms);
+    tracing::warn!(
+        "Event timestamp out of bounds: detected_at={}, received_at={}, clock_skew_ms={:?}, org_id={}, agent_id={}",
+        event.detected_at,
+        received_at,
+        clock_skew_ms,
+        org_id,
+        agent_id
+    );
+    match bounds.action {
+        OutOfBoundsAction::Flag => Ok(Some(flag)),
+        OutOfBoundsAction::Reject => Err(bounds.describe(flag, event.detected_at)),
+    }
+}
+
+/// Records the event's ID for deduplication; `false` when the agent already sent it
+fn claim_event(
+    app_state: &AppState,
+    org_id: &str,
+    agent_id: &str,
+    event: &DetectionEvent,
+    received_at: DateTime<Utc>,
+) -> bool {
+    let Some(event_id) = &event.event_id else {
+        return true;
+    };
+    let claimed = app_state.idempotency.claim_event(org_id, agent_id, event_id, received_at);
+    if !claimed {
+        tracing::debug!("Duplicate event skipped: event_id={}, org_id={}, agent_id={}", event_id, org_id, agent_id);
+    }
+    claimed
+}
+
+const QUOTA_EXCEEDED: &str = "QUOTA_EXCEEDED";
+const QUOTA_REQUEST_TOO_LARGE: &str = "QUOTA_REQUEST_TOO_LARGE";
+
+fn quota_exceeded_message(exceeded: &RateLimit) -> String {
+    format!(
+        "Ingest quota exceeded: {} (limit {}), retry in {}s",
+        exceeded.kind,
+        exceeded.limit,
+        exceeded.reset_secs
+    )
+}
+
+fn quota_too_large_message(too_large: &RateLimit) -> String {
+    format!(
+        "Request alone exceeds the ingest quota: {} (limit {}); send it in smaller parts",
+        too_large.kind,
+        too_large.limit
+    )
+}
+
+/// Adds `X-RateLimit-Limit`, `X-RateLimit-Remaining` and `X-RateLimit-Reset` for the given quota
+fn with_rate_limit(mut response: Response, rate_limit: Option<RateLimit>) -> Response {
+    let Some(rate_limit) = rate_limit else {
+        return response;
+    };
+    let headers = response.headers_mut();
+    for (name, value) in [
+        ("x-ratelimit-limit", rate_limit.limit),
+        ("x-ratelimit-remaining", rate_limit.remaining),
+        ("x-ratelimit-reset", rate_limit.reset_secs),
+    ] {
+        headers.insert(HeaderName::from_static(name), HeaderValue::from(value));
+    }
+    response
+}
+
+const AGENT_VERSION_BLOCKED: &str = "AGENT_VERSION_BLOCKED";
+
+/// Enforces the org version policy on the reported (or last known) agent version,
+/// returning the version when it is blocked
+fn blocked_version(app_state: &AppState, org_id: &str, agent_id: &str, reported: Option<&str>) -> Option<String> {
+    let version = reported
+        .map(str::to_string)
+        .or_else(|| app_state.agents.get(org_id, agent_id).map(|agent| agent.version))?;
+    if !app_state.version_policies.rejects(org_id, &version) {
+        return None;
+    }
+    tracing::warn!(
+        "Rejecting ingest from blocked agent version: version={}, org_id={}, agent_id={}",
+        version,
+        org_id,
+        agent_id
+    );
+    Some(version)
+}
+
+fn blocked_version_message(version: &str) -> String {
+    format!("Agent version {} is blocked by organization policy", version)
 }
 
 pub fn routes() -> axum::Router<AppState> {
     axum::Router::new()
         .route("/batch", axum::routing::post(batch_ingest))
-}
\ No newline at end of file
+        .route("/stream", axum::routing::post(stream_ingest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body;
+    use anticheat_protocol::Severity;
+    use crate::models::{
+        quota::QuotaLimits,
+        version_policy::{BlockedAction, VersionPolicy},
+    };
+
+    fn agent() -> AgentAuth {
+        AgentAuth::new("org_1".to_string(), "agent_1".to_string(), "org".to_string())
+    }
+
+    fn event(event_id: &str) -> DetectionEvent {
+        let mut event = DetectionEvent::new("speed_hack", Severity::High);
+        event.event_id = Some(event_id.to_string());
+        event
+    }
+
+    async fn send_batch(app_state: &AppState, key: &str, events: Vec<DetectionEvent>) -> (StatusCode, IngestResponse) {
+        send_payload(app_state, key, IngestBatchRequest::new(events)).await
+    }
+
+    async fn send_payload(app_state: &AppState, key: &str, payload: IngestBatchRequest) -> (StatusCode, IngestResponse) {
+        let (status, _, response) = send_with_headers(app_state, key, payload).await;
+        (status, response)
+    }
+
+    async fn send_with_headers(
+        app_state: &AppState,
+        key: &str,
+        payload: IngestBatchRequest,
+    ) -> (StatusCode, HeaderMap, IngestResponse) {
+        let mut headers = HeaderMap::new();
+        headers.insert(IDEMPOTENCY_KEY_HEADER, HeaderValue::from_str(key).unwrap());
+        let body_len = serde_json::to_vec(&payload).unwrap().len();
+        let response = batch_ingest(State(app_state.clone()), Extension(agent()), headers, IngestBody(payload, body_len))
+            .await
+            .expect("handler response");
+        let (parts, body) = response.into_parts();
+        let bytes = body::to_bytes(body, usize::MAX).await.unwrap();
+        (parts.status, parts.headers, serde_json::from_slice(&bytes).unwrap())
+    }
+
+    #[tokio::test]
+    async fn full_queue_refuses_the_batch_without_claiming_or_charging() {
+        let (app_state, _receiver) = AppState::for_tests_with_queue(1);
+        app_state.quotas.set_limits("org_1", QuotaLimits { org_events_per_minute: Some(100), ..QuotaLimits::default() });
+
+        let (status, headers, response) =
+            send_with_headers(&app_state, "k1", IngestBatchRequest::new(vec![event("e1"), event("e2")])).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(headers.get(header::RETRY_AFTER).unwrap(), "1");
+        assert_eq!(response.error_code.as_deref(), Some("INGEST_QUEUE_FULL"));
+        assert_eq!((response.processed, response.failed), (0, 2));
+
+        assert!(!app_state.idempotency.batch_seen("org_1", "agent_1", "k1"));
+        assert!(!app_state.idempotency.event_seen("org_1", "agent_1", "e1"));
+        assert_eq!(app_state.quotas.org_usage("org_1", Utc::now()).0.events_today, 0);
+        assert_eq!(app_state.ingest_queue.depth(), 0, "a refused batch holds no queue slots");
+
+        // One event fits; with nobody draining the queue, the next batch is refused
+        assert_eq!(send_batch(&app_state, "k2", vec![event("e1")]).await.0, StatusCode::ACCEPTED);
+        assert_eq!(send_batch(&app_state, "k3", vec![event("e2")]).await.0, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(!app_state.idempotency.batch_seen("org_1", "agent_1", "k3"));
+        assert_eq!(app_state.quotas.org_usage("org_1", Utc::now()).0.events_today, 1);
+    }
+
+    #[tokio::test]
+    async fn replayed_idempotency_key_is_not_throttled_or_charged() {
+        let (app_state, _receiver) = AppState::for_tests();
+        app_state.quotas.set_limits("org_1", QuotaLimits { agent_events_per_minute: Some(2), ..QuotaLimits::default() });
+
+        let (status, first) = send_batch(&app_state, "batch-1", vec![event("e1"), event("e2")]).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(first.processed, 2);
+
+        // The quota is used up, yet the retry still gets its duplicate report
+        let (status, retry) = send_batch(&app_state, "batch-1", vec![event("e1"), event("e2")]).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!((retry.processed, retry.duplicates), (0, 2));
+
+        let (usage, _) = app_state.quotas.org_usage("org_1", Utc::now());
+        assert_eq!(usage.events_this_minute, 2);
+
+        let (status, fresh) = send_batch(&app_state, "batch-2", vec![event("e3")]).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(fresh.error_code.as_deref(), Some(QUOTA_EXCEEDED));
+        // The refused batch gave back its claims, so it can be resent once the quota allows
+        assert!(!app_state.idempotency.batch_seen("org_1", "agent_1", "batch-2"));
+        assert!(!app_state.idempotency.event_seen("org_1", "agent_1", "e3"));
+    }
+
+    #[tokio::test]
+    async fn batches_larger_than_a_quota_are_refused_as_too_large() {
+        let (app_state, _receiver) = AppState::for_tests();
+        app_state.quotas.set_limits("org_1", QuotaLimits { agent_events_per_minute: Some(2), ..QuotaLimits::default() });
+
+        let (status, headers, response) =
+            send_with_headers(&app_state, "batch-1", IngestBatchRequest::new(vec![event("e1"), event("e2"), event("e3")])).await;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(response.error_code.as_deref(), Some(QUOTA_REQUEST_TOO_LARGE));
+        assert!(headers.get(header::RETRY_AFTER).is_none(), "waiting would not help");
+        assert!(!app_state.idempotency.batch_seen("org_1", "agent_1", "batch-1"));
+        assert_eq!(app_state.quotas.org_usage("org_1", Utc::now()).0.events_this_minute, 0);
+
+        // The same events split to fit go through
+        assert_eq!(send_batch(&app_state, "batch-2", vec![event("e1"), event("e2")]).await.0, StatusCode::ACCEPTED);
+    }
+
+    #[tokio::test]
+    async fn concurrent_retries_are_charged_once() {
+        let (app_state, _receiver) = AppState::for_tests();
+        app_state.quotas.set_limits("org_1", QuotaLimits { agent_events_per_minute: Some(10), ..QuotaLimits::default() });
+
+        let ((first_status, first), (second_status, second)) = tokio::join!(
+            send_batch(&app_state, "batch-1", vec![event("e1"), event("e2")]),
+            send_batch(&app_state, "batch-1", vec![event("e1"), event("e2")]),
+        );
+        assert_eq!((first_status, second_status), (StatusCode::ACCEPTED, StatusCode::ACCEPTED));
+        assert_eq!(first.processed + second.processed, 2);
+        assert_eq!(first.duplicates + second.duplicates, 2);
+
+        let (usage, _) = app_state.quotas.org_usage("org_1", Utc::now());
+        assert_eq!(usage.events_this_minute, 2);
+    }
+
+    #[tokio::test]
+    async fn events_already_seen_are_not_charged() {
+        let (app_state, _receiver) = AppState::for_tests();
+        app_state.quotas.set_limits("org_1", QuotaLimits { agent_events_per_minute: Some(2), ..QuotaLimits::default() });
+
+        send_batch(&app_state, "batch-1", vec![event("e1")]).await;
+        let (status, mixed) = send_batch(&app_state, "batch-2", vec![event("e1"), event("e2"), event("e2")]).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!((mixed.processed, mixed.duplicates), (1, 2));
+
+        let (usage, _) = app_state.quotas.org_usage("org_1", Utc::now());
+        assert_eq!(usage.events_this_minute, 2);
+    }
+
+    #[tokio::test]
+    async fn stream_reports_progress_when_the_queue_closes() {
+        let (app_state, receiver) = AppState::for_tests();
+        drop(receiver);
+        let ndjson = [event("e1"), event("e2")]
+            .iter()
+            .map(|event| serde_json::to_string(event).unwrap() + "\n")
+            .collect::<String>();
+
+        let response = stream_ingest(State(app_state.clone()), Extension(agent()), Body::from(ndjson)).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let bytes = body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let report: StreamIngestResponse = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!((report.lines, report.processed, report.failed), (1, 0, 1));
+        assert_eq!(report.errors[0].line, 1);
+        assert_eq!(report.error_code.as_deref(), Some(INGEST_UNAVAILABLE));
+        assert!(!app_state.idempotency.event_seen("org_1", "agent_1", "e1"), "the refused line can be resent");
+        assert_eq!(app_state.quotas.org_usage("org_1", Utc::now()).0.events_today, 0);
+    }
+
+    #[tokio::test]
+    async fn blocked_agent_versions_are_refused() {
+        let (app_state, _receiver) = AppState::for_tests();
+        let policy = VersionPolicy {
+            blocked_versions: vec!["1.4.0".to_string()],
+            blocked_action: BlockedAction::Reject,
+            ..VersionPolicy::default()
+        };
+        app_state.version_policies.set("org_1", policy, Utc::now()).unwrap();
+
+        let mut payload = IngestBatchRequest::new(vec![event("e1")]);
+        payload.heartbeat = Some(AgentHeartbeat::new("1.4.0", "linux"));
+        let (status, response) = send_payload(&app_state, "k1", payload).await;
+        assert_eq!(status, StatusCode::UPGRADE_REQUIRED);
+        assert_eq!(response.error_code.as_deref(), Some(AGENT_VERSION_BLOCKED));
+        assert_eq!((response.processed, response.failed), (0, 1));
+
+        // Nothing was recorded, so the same batch goes through from a supported version
+        let mut payload = IngestBatchRequest::new(vec![event("e1")]);
+        payload.heartbeat = Some(AgentHeartbeat::new("1.4.1", "linux"));
+        let (status, response) = send_payload(&app_state, "k1", payload).await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(response.processed, 1);
+    }
+
+    fn describe(line: Line) -> (u64, Option<usize>) {
+        match line.content {
+            LineContent::Text(text) => (line.number, Some(text.len())),
+            LineContent::TooLong => (line.number, None),
+        }
+    }
+
+    #[test]
+    fn lines_are_split_across_chunks() {
+        let mut lines = LineSplitter::new(MAX_STREAM_LINE_BYTES);
+        assert!(lines.push(b"{\"a\"").is_empty());
+        let complete: Vec<_> = lines.push(b":1}\n\n{}").into_iter().map(describe).collect();
+        assert_eq!(complete, [(1, Some(7)), (2, Some(0))]);
+        assert_eq!(lines.finish().map(describe), Some((3, Some(2))));
+    }
+
+    #[test]
+    fn lines_over_one_mebibyte_are_dropped_without_buffering() {
+        let mut lines = LineSplitter::new(MAX_STREAM_LINE_BYTES);
+        let limit = vec![b'x'; MAX_STREAM_LINE_BYTES];
+        let mut complete: Vec<_> = lines.push(&limit).into_iter().chain(lines.push(b"\n")).map(describe).collect();
+
+        for _ in 0..4 {
+            complete.extend(lines.push(&limit).into_iter().map(describe));
+            assert!(lines.buffer.len() <= MAX_STREAM_LINE_BYTES);
+        }
+        assert!(lines.buffer.is_empty(), "an overflowing line is discarded as it arrives");
+        complete.extend(lines.push(b"\n{}\n").into_iter().map(describe));
+
+        assert_eq!(complete, [(1, Some(MAX_STREAM_LINE_BYTES)), (2, None), (3, Some(2))]);
+        assert!(lines.finish().is_none());
+    }
+
+    #[test]
+    fn an_unterminated_overlong_last_line_is_reported() {
+        let mut lines = LineSplitter::new(4);
+        assert!(lines.push(b"12345").is_empty());
+        assert_eq!(lines.finish().map(describe), Some((1, None)));
+    }
+}
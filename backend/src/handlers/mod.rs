@@ -2,10 +2,16 @@ use axum::{Json, http::StatusCode};
 use serde::{Serialize, Deserialize};
 use utoipa::ToSchema;
 
+pub mod agent_config;
+pub mod agents;
 pub mod auth;
 pub mod dashboard_api;
+pub mod encoding;
+pub mod enrollment;
+pub mod event_types;
 pub mod ingest;
 pub mod realtime;
+pub mod usage;
 
 #[utoipa::path(
     get,
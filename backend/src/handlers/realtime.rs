@@ -1,52 +1,118 @@
 use axum::{
     extract::{
-        ws::{Message, WebSocket, WebSocketUpgrade}, Extension
+        ws::{Message, WebSocket, WebSocketUpgrade}, Extension, State
     },
     response::IntoResponse,
     routing::get,
     Router,
 };
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::broadcast;
 use tracing::info;
-use crate::{auth::api_key::AgentAuth, config::AppState};
+use anticheat_protocol::{dashboard::TimestampFlag, severity::Severity};
+use crate::{auth::api_key::AgentAuth, config::AppState, models::agent::AgentStatusChange};
 
+/// Server-pushed events, forwarded to every socket of the owning org
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum RealtimeEvent {
+    AgentStatusChanged(AgentStatusChange),
+    DetectionReceived(DetectionReceived),
+}
+
+impl RealtimeEvent {
+    pub fn org_id(&self) -> &str {
+        match self {
+            RealtimeEvent::AgentStatusChanged(change) => &change.org_id,
+            RealtimeEvent::DetectionReceived(detection) => &detection.org_id,
+        }
+    }
+}
+
+/// A detection event that finished processing
+#[derive(Debug, Clone, Serialize)]
+pub struct DetectionReceived {
+    pub org_id: String,
+    pub agent_id: String,
+    pub event_type: String,
+    pub severity: Severity,
+    pub title: Option<String>,
+    pub detected_at: DateTime<Utc>,
+    pub received_at: DateTime<Utc>,
+    pub timestamp_flag: Option<TimestampFlag>,
+}
 
 pub async fn ws_dashboard(
     ws: WebSocketUpgrade,
+    State(app_state): State<AppState>,
     Extension(agent_auth): Extension<AgentAuth>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(|socket| handle_socket(socket, agent_auth))
+    let events = app_state.events.subscribe();
+    ws.on_upgrade(|socket| handle_socket(socket, agent_auth, events))
 }
 
-async fn handle_socket(mut socket: WebSocket, agent_auth: AgentAuth) {
+async fn handle_socket(
+    mut socket: WebSocket,
+    agent_auth: AgentAuth,
+    mut events: broadcast::Receiver<RealtimeEvent>,
+) {
     info!("WebSocket connection established for org_id: {}, agent_id: {}", agent_auth.org_id, agent_auth.agent_id);
 
-    while let Some(msg) = socket.recv().await {
-        if let Ok(msg) = msg {
-            match msg {
-                Message::Text(t) => {
-                    info!("Received text message (length {}) from org_id: {}", t.len(), agent_auth.org_id);
-                    // For now, just echo back with org_id context
-                    if let Err(e) = socket.send(Message::Text(format!("Org {}: You said: {}", agent_auth.org_id, t).into())).await {
-                        info!("Error sending message: {}", e);
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                match event {
+                    Ok(event) if event.org_id() == agent_auth.org_id => {
+                        let payload = match serde_json::to_string(&event) {
+                            Ok(payload) => payload,
+                            Err(e) => {
+                                info!("Failed to serialize realtime event: {}", e);
+                                continue;
+                            }
+                        };
+                        if let Err(e) = socket.send(Message::Text(payload.into())).await {
+                            info!("Error sending message: {}", e);
+                            return;
+                        }
                     }
+                    Ok(_) => {}
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        info!("Realtime subscriber for org_id: {} lagged, skipped {} events", agent_auth.org_id, skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return,
                 }
-                Message::Binary(b) => {
-                    info!("Received binary message (length {}) from org_id: {}", b.len(), agent_auth.org_id);
-                }
-                Message::Ping(p) => {
-                    info!("Received ping from org_id: {}: {:?}", agent_auth.org_id, p);
-                }
-                Message::Pong(p) => {
-                    info!("Received pong from org_id: {}: {:?}", agent_auth.org_id, p);
-                }
-                Message::Close(c) => {
-                    info!("WebSocket disconnected for org_id: {}: {:?}", agent_auth.org_id, c);
-                    break;
+            }
+            msg = socket.recv() => {
+                let Some(msg) = msg else { return };
+                if let Ok(msg) = msg {
+                    match msg {
+                        Message::Text(t) => {
+                            info!("Received text message (length {}) from org_id: {}", t.len(), agent_auth.org_id);
+                            // For now, just echo back with org_id context
+                            if let Err(e) = socket.send(Message::Text(format!("Org {}: You said: {}", agent_auth.org_id, t).into())).await {
+                                info!("Error sending message: {}", e);
+                            }
+                        }
+                        Message::Binary(b) => {
+                            info!("Received binary message (length {}) from org_id: {}", b.len(), agent_auth.org_id);
+                        }
+                        Message::Ping(p) => {
+                            info!("Received ping from org_id: {}: {:?}", agent_auth.org_id, p);
+                        }
+                        Message::Pong(p) => {
+                            info!("Received pong from org_id: {}: {:?}", agent_auth.org_id, p);
+                        }
+                        Message::Close(c) => {
+                            info!("WebSocket disconnected for org_id: {}: {:?}", agent_auth.org_id, c);
+                            return;
+                        }
+                    }
+                } else {
+                    info!("Client disconnected for org_id: {}", agent_auth.org_id);
+                    return;
                 }
             }
-        } else {
-            info!("Client disconnected for org_id: {}", agent_auth.org_id);
-            return;
         }
     }
 }
@@ -54,4 +120,4 @@ async fn handle_socket(mut socket: WebSocket, agent_auth: AgentAuth) {
 pub fn routes() -> Router<AppState> {
     Router::new()
         .route("/dashboard", get(ws_dashboard))
-}
\ No newline at end of file
+}
@@ -0,0 +1,103 @@
+use axum::{
+    extract::{Extension, State},
+    response::IntoResponse,
+    Json,
+    http::StatusCode,
+};
+use serde::Serialize;
+use utoipa::ToSchema;
+use chrono::{DateTime, Utc};
+use crate::{
+    auth::jwt::Claims,
+    config::AppState,
+    models::quota::{AgentUsage, QuotaLimits, Usage},
+};
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UsageResponse {
+    pub org_id: String,
+    pub limits: QuotaLimits,
+    pub usage: Usage,
+    /// Agents that ingested today, heaviest first
+    pub agents: Vec<AgentUsage>,
+    pub generated_at: DateTime<Utc>,
+}
+
+/// Ingest usage of the org and its agents against the quotas
+#[utoipa::path(
+    get,
+    path = "/v1/usage",
+    responses(
+        (status = 200, description = "Usage in the current minute and UTC day", body = UsageResponse),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(("bearerAuth" = [])),
+    tag = "Ingest"
+)]
+pub async fn get_usage(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> impl IntoResponse {
+    let now = Utc::now();
+    let (usage, agents) = app_state.quotas.org_usage(&claims.org_id, now);
+    Json(UsageResponse {
+        limits: app_state.quotas.limits(&claims.org_id),
+        org_id: claims.org_id,
+        usage,
+        agents,
+        generated_at: now,
+    })
+}
+
+/// Ingest quotas in effect for the org
+#[utoipa::path(
+    get,
+    path = "/v1/quotas",
+    responses(
+        (status = 200, description = "Ingest quotas; null fields are unlimited", body = QuotaLimits),
+        (status = 401, description = "Unauthorized")
+    ),
+    security(("bearerAuth" = [])),
+    tag = "Ingest"
+)]
+pub async fn get_quotas(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+) -> impl IntoResponse {
+    Json(app_state.quotas.limits(&claims.org_id))
+}
+
+/// Replace the ingest quotas of the org, overriding the server defaults
+#[utoipa::path(
+    put,
+    path = "/v1/quotas",
+    request_body = QuotaLimits,
+    responses(
+        (status = 200, description = "Quotas updated", body = QuotaLimits),
+        (status = 400, description = "Invalid request format"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Admin role required")
+    ),
+    security(("bearerAuth" = [])),
+    tag = "Ingest"
+)]
+pub async fn put_quotas(
+    State(app_state): State<AppState>,
+    Extension(claims): Extension<Claims>,
+    Json(payload): Json<QuotaLimits>,
+) -> Result<impl IntoResponse, StatusCode> {
+    if claims.role != "admin" {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let limits = app_state.quotas.set_limits(&claims.org_id, payload);
+    tracing::info!("Ingest quotas updated for org_id={}: {:?}", claims.org_id, limits);
+
+    Ok(Json(limits))
+}
+
+pub fn routes() -> axum::Router<AppState> {
+    axum::Router::new()
+        .route("/usage", axum::routing::get(get_usage))
+        .route("/quotas", axum::routing::get(get_quotas).put(put_quotas))
+}
@@ -5,4 +5,5 @@ pub mod middleware;
 pub mod models;
 pub mod openapi;
 pub mod router;
+pub mod tasks;
 pub mod telemetry;
\ No newline at end of file
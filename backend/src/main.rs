@@ -4,7 +4,7 @@ use tower_http::{trace::TraceLayer, cors::CorsLayer, services::ServeDir};
 use tower::ServiceBuilder;
 use std::net::SocketAddr;
 use tokio::net::TcpListener;
-use anticheat::{router, telemetry, config::AppState};
+use anticheat::{router, tasks, telemetry, config::AppState, middleware::rate_limit::RateLimitSettings, models::{clock::TimestampBounds, quota::QuotaLimits, signature::SignatureSettings}};
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -18,6 +18,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let api_key_prefix = std::env::var("API_KEY_PREFIX")
         .unwrap_or_else(|_| "org".to_string());
 
+    // Legacy self-describing agent keys are unverified; off unless explicitly enabled
+    let allow_legacy_api_keys = match std::env::var("ALLOW_LEGACY_API_KEYS").as_deref() {
+        Err(_) | Ok("false") | Ok("0") => false,
+        Ok("true") | Ok("1") => true,
+        Ok(other) => panic!("ALLOW_LEGACY_API_KEYS must be 'true' or 'false', got '{}'", other),
+    };
+
     // Generate or load cookie secret securely
     let cookie_secret = if cfg!(debug_assertions) {
         // Development only - generate random key
@@ -37,8 +44,38 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         panic!("Cookie secret must be at least 64 characters long");
     }
 
+    // Request body size limit (10MB default)
+    let max_request_size: usize = std::env::var("MAX_REQUEST_SIZE")
+        .unwrap_or_else(|_| "10485760".to_string())
+        .parse()
+        .expect("MAX_REQUEST_SIZE must be a valid number");
+
     let cookie_key = Key::from(cookie_secret.as_bytes());
-    let app_state = AppState::new(cookie_key, jwt_secret, api_key_prefix);
+    let ingest_settings = tasks::ingest_workers::IngestWorkerSettings::from_env();
+    let (ingest_queue, ingest_receiver) = tasks::ingest_workers::IngestQueue::new(&ingest_settings);
+    let app_state = AppState::new(
+        cookie_key,
+        jwt_secret,
+        api_key_prefix,
+        allow_legacy_api_keys,
+        max_request_size,
+        TimestampBounds::from_env(),
+        SignatureSettings::from_env(),
+        QuotaLimits::from_env(),
+        RateLimitSettings::from_env(),
+        ingest_queue,
+    );
+
+    // Workers processing accepted events off the ingest queue
+    tasks::ingest_workers::spawn(app_state.clone(), ingest_receiver, ingest_settings);
+    // Background sweep that marks silent agents stale/offline
+    tasks::agent_status::spawn(app_state.clone(), tasks::agent_status::AgentStatusSettings::from_env());
+    // Background expiry of agent health samples
+    tasks::health_retention::spawn(app_state.clone(), tasks::health_retention::HealthRetentionSettings::from_env());
+    // Background expiry of ingest idempotency keys
+    tasks::idempotency_retention::spawn(app_state.clone(), tasks::idempotency_retention::IdempotencyRetentionSettings::from_env());
+    // Background expiry of signed request nonces
+    tasks::nonce_retention::spawn(app_state.clone(), tasks::nonce_retention::NonceRetentionSettings::from_env());
 
     // Environment-based CORS configuration
     let allowed_origins_str = std::env::var("ALLOWED_ORIGINS")
@@ -59,6 +96,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .allow_methods([
             axum::http::Method::GET,
             axum::http::Method::POST,
+            axum::http::Method::PUT,
             axum::http::Method::PATCH,
             axum::http::Method::DELETE,
         ])
@@ -67,19 +105,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             axum::http::HeaderName::from_static("content-type"),
             axum::http::HeaderName::from_static("authorization"),
             axum::http::HeaderName::from_static("x-api-key"),
+            axum::http::HeaderName::from_static("x-key-id"),
+            axum::http::HeaderName::from_static("x-signature"),
+            axum::http::HeaderName::from_static("x-signature-timestamp"),
+            axum::http::HeaderName::from_static("x-signature-nonce"),
+            axum::http::HeaderName::from_static("x-content-sha256"),
+            axum::http::HeaderName::from_static("idempotency-key"),
+            axum::http::HeaderName::from_static("content-encoding"),
         ])
         .allow_credentials(true);
 
-    // TODO: Implement custom rate limiting middleware
-    // tower-governor has compatibility issues with current Axum version
-    // Consider implementing a custom solution using Arc<Mutex<HashMap>> for IP tracking
-
-    // Request body size limit (10MB default)
-    let max_request_size: usize = std::env::var("MAX_REQUEST_SIZE")
-        .unwrap_or_else(|_| "10485760".to_string())
-        .parse()
-        .expect("MAX_REQUEST_SIZE must be a valid number");
-
     let app = Router::new()
         .merge(router::create_router(app_state))
         .fallback_service(ServeDir::new("../web"))
@@ -93,7 +128,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
     tracing::info!("listening on {}", addr);
     let listener = TcpListener::bind(&addr).await?;
-    axum::serve(listener, app.into_make_service()).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
 
     Ok(())
 }
@@ -1 +1 @@
-// Middleware module (placeholder for now)
\ No newline at end of file
+pub mod rate_limit;
@@ -0,0 +1,548 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    http::{header, HeaderMap, Request, StatusCode},
+};
+use crate::config::AppState;
+
+/// Buckets or lockouts tracked before the least recently used is dropped
+const MAX_TRACKED_KEYS: usize = 10_000;
+
+/// Longest lockout after repeated login failures
+const MAX_LOCKOUT: Duration = Duration::from_secs(3600);
+
+/// Entries by key, evicting the least recently used one once `capacity` are tracked.
+/// Every operation is O(log n), so a flood of new keys cannot stall the lock.
+struct RecentlyUsed<V> {
+    capacity: usize,
+    entries: HashMap<String, (V, u64)>,
+    /// Keys by the tick they were last used at, oldest first
+    order: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+impl<V> RecentlyUsed<V> {
+    fn new(capacity: usize) -> Self {
+        Self { capacity: capacity.max(1), entries: HashMap::new(), order: BTreeMap::new(), tick: 0 }
+    }
+
+    /// The entry for `key`, created with `default` if missing, marked as most recently used
+    fn touch(&mut self, key: &str, default: impl FnOnce() -> V) -> &mut V {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some((_, used)) = self.entries.get_mut(key) {
+            self.order.remove(used);
+            *used = tick;
+        } else {
+            if self.entries.len() >= self.capacity {
+                if let Some((_, oldest)) = self.order.pop_first() {
+                    self.entries.remove(&oldest);
+                }
+            }
+            self.entries.insert(key.to_string(), (default(), tick));
+        }
+        self.order.insert(tick, key.to_string());
+        &mut self.entries.get_mut(key).expect("entry was just inserted").0
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some((_, used)) = self.entries.remove(key) {
+            self.order.remove(&used);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets keyed by an arbitrary string, all with the same capacity and refill rate
+#[derive(Clone)]
+pub struct RateLimiter {
+    burst: f64,
+    refill_per_sec: f64,
+    buckets: Arc<Mutex<RecentlyUsed<TokenBucket>>>,
+}
+
+impl RateLimiter {
+    /// Allows `burst` requests at once, refilled at `per_minute`
+    pub fn new(burst: u32, per_minute: u32) -> Self {
+        Self {
+            burst: f64::from(burst.max(1)),
+            refill_per_sec: f64::from(per_minute.max(1)) / 60.0,
+            buckets: Arc::new(Mutex::new(RecentlyUsed::new(MAX_TRACKED_KEYS))),
+        }
+    }
+
+    /// Takes a token for `key`, or returns the seconds until one is available
+    pub fn check(&self, key: &str, now: Instant) -> Result<(), u64> {
+        let mut buckets = self.buckets.lock().expect("rate limiter lock poisoned");
+        let bucket = buckets.touch(key, || TokenBucket { tokens: self.burst, updated: now });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(((1.0 - bucket.tokens) / self.refill_per_sec).ceil() as u64)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct FailureRecord {
+    failures: u32,
+    last_failure: Instant,
+    locked_until: Option<Instant>,
+}
+
+/// Failure records by account. Accounts below the threshold live in a bounded LRU;
+/// once locked, an account moves to `locked` and stays there until both its lockout
+/// and its failure window have passed, so a flood of other accounts cannot evict it.
+/// Locking an account takes `threshold` failures under the per-account login limit,
+/// which bounds how fast `locked` can grow.
+struct LockoutState {
+    counting: RecentlyUsed<FailureRecord>,
+    locked: HashMap<String, (FailureRecord, Instant)>,
+    /// Locked accounts by the time they can be forgotten, soonest first
+    expiries: BTreeSet<(Instant, String)>,
+}
+
+impl LockoutState {
+    fn forget_expired(&mut self, now: Instant) {
+        while let Some((expires, _)) = self.expiries.first() {
+            if *expires > now {
+                break;
+            }
+            let (_, email) = self.expiries.pop_first().expect("first entry exists");
+            self.locked.remove(&email);
+        }
+    }
+
+    fn remove(&mut self, email: &str) {
+        self.counting.remove(email);
+        if let Some((_, expires)) = self.locked.remove(email) {
+            self.expiries.remove(&(expires, email.to_string()));
+        }
+    }
+}
+
+/// Consecutive login failures per account, locking it out with exponential backoff
+#[derive(Clone)]
+pub struct LoginLockouts {
+    threshold: u32,
+    base_lockout: Duration,
+    failure_window: Duration,
+    accounts: Arc<Mutex<LockoutState>>,
+}
+
+impl LoginLockouts {
+    pub fn new(threshold: u32, base_lockout: Duration, failure_window: Duration) -> Self {
+        Self {
+            threshold: threshold.max(1),
+            base_lockout,
+            failure_window,
+            accounts: Arc::new(Mutex::new(LockoutState {
+                counting: RecentlyUsed::new(MAX_TRACKED_KEYS),
+                locked: HashMap::new(),
+                expiries: BTreeSet::new(),
+            })),
+        }
+    }
+
+    /// Seconds the account remains locked, if it is
+    pub fn locked_for(&self, email: &str, now: Instant) -> Option<u64> {
+        let accounts = self.accounts.lock().expect("login lockout lock poisoned");
+        let locked_until = accounts.locked.get(email)?.0.locked_until?;
+        (locked_until > now).then(|| locked_until.duration_since(now).as_secs().max(1))
+    }
+
+    /// Counts a failure; from `threshold` failures on, each one doubles the lockout
+    pub fn record_failure(&self, email: &str, now: Instant) -> Option<Duration> {
+        let mut accounts = self.accounts.lock().expect("login lockout lock poisoned");
+        accounts.forget_expired(now);
+        let mut record = match accounts.locked.remove(email) {
+            Some((record, expires)) => {
+                accounts.expiries.remove(&(expires, email.to_string()));
+                record
+            }
+            None => *accounts.counting.touch(email, || FailureRecord { failures: 0, last_failure: now, locked_until: None }),
+        };
+        if now.saturating_duration_since(record.last_failure) >= self.failure_window {
+            record.failures = 0;
+        }
+        record.failures += 1;
+        record.last_failure = now;
+
+        let lockout = (record.failures >= self.threshold).then(|| {
+            let doublings = (record.failures - self.threshold).min(16);
+            self.base_lockout.saturating_mul(1 << doublings).min(MAX_LOCKOUT)
+        });
+        if let Some(lockout) = lockout {
+            record.locked_until = Some(now + lockout);
+        }
+
+        if record.locked_until.is_some() {
+            // Kept for the failure window too, so the next lockout still escalates
+            let expires = (now + self.failure_window).max(record.locked_until.unwrap_or(now));
+            accounts.counting.remove(email);
+            accounts.locked.insert(email.to_string(), (record, expires));
+            accounts.expiries.insert((expires, email.to_string()));
+        } else {
+            *accounts.counting.touch(email, || record) = record;
+        }
+        lockout
+    }
+
+    pub fn record_success(&self, email: &str) {
+        self.accounts
+            .lock()
+            .expect("login lockout lock poisoned")
+            .remove(email);
+    }
+}
+
+/// An address or CIDR range of a reverse proxy whose `X-Forwarded-For` is trusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrustedProxy {
+    network: IpAddr,
+    prefix: u8,
+}
+
+impl TrustedProxy {
+    /// Parses `10.0.0.1`, `10.0.0.0/8` or `fd00::/8`
+    pub fn parse(value: &str) -> Option<Self> {
+        let (addr, prefix) = match value.trim().split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (value.trim(), None),
+        };
+        let network: IpAddr = addr.parse().ok()?;
+        let max_prefix = if network.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse::<u8>().ok().filter(|p| *p <= max_prefix)?,
+            None => max_prefix,
+        };
+        Some(Self { network, prefix })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(self.prefix)).unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(self.prefix)).unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimitSettings {
+    pub ip_burst: u32,
+    pub ip_per_minute: u32,
+    pub enroll_burst: u32,
+    pub enroll_per_minute: u32,
+    pub login_burst: u32,
+    pub login_per_minute: u32,
+    pub lockout_threshold: u32,
+    pub lockout: Duration,
+    pub failure_window: Duration,
+    pub trusted_proxies: Vec<TrustedProxy>,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        Self {
+            ip_burst: 20,
+            ip_per_minute: 60,
+            enroll_burst: 20,
+            enroll_per_minute: 60,
+            login_burst: 5,
+            login_per_minute: 10,
+            lockout_threshold: 5,
+            lockout: Duration::from_secs(60),
+            failure_window: Duration::from_secs(900),
+            trusted_proxies: Vec::new(),
+        }
+    }
+}
+
+impl RateLimitSettings {
+    /// Reads `RATE_LIMIT_IP_BURST` (default 20), `RATE_LIMIT_IP_PER_MINUTE` (60),
+    /// `RATE_LIMIT_ENROLL_BURST` (20), `RATE_LIMIT_ENROLL_PER_MINUTE` (60),
+    /// `RATE_LIMIT_LOGIN_BURST` (5), `RATE_LIMIT_LOGIN_PER_MINUTE` (10),
+    /// `LOGIN_LOCKOUT_THRESHOLD` (5 failures), `LOGIN_LOCKOUT_SECS` (60),
+    /// `LOGIN_FAILURE_WINDOW_SECS` (900) and `TRUSTED_PROXIES` (comma-separated IPs or CIDRs)
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let number = |name: &str, default: u32| {
+            std::env::var(name)
+                .map(|value| value.parse::<u32>().unwrap_or_else(|_| panic!("{} must be a valid number", name)))
+                .unwrap_or(default)
+        };
+        let secs = |name: &str, default: Duration| {
+            Duration::from_secs(number(name, default.as_secs() as u32).into())
+        };
+        let trusted_proxies = std::env::var("TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .filter(|value| !value.trim().is_empty())
+            .map(|value| {
+                TrustedProxy::parse(value)
+                    .unwrap_or_else(|| panic!("TRUSTED_PROXIES entry '{}' is not an IP address or CIDR", value))
+            })
+            .collect();
+
+        Self {
+            ip_burst: number("RATE_LIMIT_IP_BURST", defaults.ip_burst),
+            ip_per_minute: number("RATE_LIMIT_IP_PER_MINUTE", defaults.ip_per_minute),
+            enroll_burst: number("RATE_LIMIT_ENROLL_BURST", defaults.enroll_burst),
+            enroll_per_minute: number("RATE_LIMIT_ENROLL_PER_MINUTE", defaults.enroll_per_minute),
+            login_burst: number("RATE_LIMIT_LOGIN_BURST", defaults.login_burst),
+            login_per_minute: number("RATE_LIMIT_LOGIN_PER_MINUTE", defaults.login_per_minute),
+            lockout_threshold: number("LOGIN_LOCKOUT_THRESHOLD", defaults.lockout_threshold),
+            lockout: secs("LOGIN_LOCKOUT_SECS", defaults.lockout),
+            failure_window: secs("LOGIN_FAILURE_WINDOW_SECS", defaults.failure_window),
+            trusted_proxies,
+        }
+    }
+}
+
+/// Rate limiters for unauthenticated endpoints
+#[derive(Clone)]
+pub struct RateLimits {
+    pub by_ip: RateLimiter,
+    /// Separate from `by_ip` so provisioning a fleet can be allowed a higher rate than login
+    pub enrollment_by_ip: RateLimiter,
+    pub login_by_email: RateLimiter,
+    pub login_lockouts: LoginLockouts,
+    trusted_proxies: Arc<Vec<TrustedProxy>>,
+}
+
+impl RateLimits {
+    pub fn new(settings: RateLimitSettings) -> Self {
+        Self {
+            by_ip: RateLimiter::new(settings.ip_burst, settings.ip_per_minute),
+            enrollment_by_ip: RateLimiter::new(settings.enroll_burst, settings.enroll_per_minute),
+            login_by_email: RateLimiter::new(settings.login_burst, settings.login_per_minute),
+            login_lockouts: LoginLockouts::new(settings.lockout_threshold, settings.lockout, settings.failure_window),
+            trusted_proxies: Arc::new(settings.trusted_proxies),
+        }
+    }
+
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|proxy| proxy.contains(ip))
+    }
+
+    /// The client address: the peer, or when the peer is a trusted proxy, the
+    /// right-most `X-Forwarded-For` entry that is not itself a trusted proxy
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.is_trusted(peer) {
+            return peer;
+        }
+        let forwarded = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect::<Vec<_>>();
+
+        let mut client = peer;
+        for entry in forwarded.into_iter().rev() {
+            // A malformed entry cannot be attributed; stop at the last trusted hop
+            let Ok(ip) = entry.parse::<IpAddr>() else {
+                break;
+            };
+            client = ip;
+            if !self.is_trusted(ip) {
+                break;
+            }
+        }
+        client
+    }
+}
+
+/// Token-bucket limit per client IP for unauthenticated endpoints
+pub async fn ip_rate_limit_middleware(
+    State(app_state): State<AppState>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    limit_by_ip(&app_state.rate_limits, &app_state.rate_limits.by_ip, req, next).await
+}
+
+/// Token-bucket limit per client IP for agent enrollment
+pub async fn enrollment_rate_limit_middleware(
+    State(app_state): State<AppState>,
+    req: Request<Body>,
+    next: Next,
+) -> Response {
+    limit_by_ip(&app_state.rate_limits, &app_state.rate_limits.enrollment_by_ip, req, next).await
+}
+
+async fn limit_by_ip(limits: &RateLimits, limiter: &RateLimiter, req: Request<Body>, next: Next) -> Response {
+    let key = match req.extensions().get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(peer)) => limits.client_ip(peer.ip(), req.headers()).to_string(),
+        None => "unknown".to_string(),
+    };
+
+    if let Err(retry_after) = limiter.check(&key, Instant::now()) {
+        tracing::warn!("Rate limit exceeded: ip={}, path={}", key, req.uri().path());
+        return too_many_requests(retry_after, "Too many requests");
+    }
+    next.run(req).await
+}
+
+pub fn too_many_requests(retry_after: u64, message: &str) -> Response {
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, retry_after.max(1).to_string())],
+        message.to_string(),
+    ).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn buckets_refill_at_the_configured_rate() {
+        let limiter = RateLimiter::new(2, 60);
+        let start = Instant::now();
+        assert_eq!(limiter.check("ip", start), Ok(()));
+        assert_eq!(limiter.check("ip", start), Ok(()));
+        assert_eq!(limiter.check("ip", start), Err(1));
+        assert_eq!(limiter.check("other", start), Ok(()));
+
+        assert_eq!(limiter.check("ip", start + SECOND), Ok(()));
+        assert_eq!(limiter.check("ip", start + SECOND), Err(1));
+        // Refill stops at the burst size
+        assert_eq!(limiter.check("ip", start + 60 * SECOND), Ok(()));
+        assert_eq!(limiter.check("ip", start + 60 * SECOND), Ok(()));
+        assert_eq!(limiter.check("ip", start + 60 * SECOND), Err(1));
+    }
+
+    #[test]
+    fn enrollment_has_its_own_bucket_per_ip() {
+        let limits = RateLimits::new(RateLimitSettings { ip_burst: 1, enroll_burst: 3, ..RateLimitSettings::default() });
+        let now = Instant::now();
+        assert_eq!(limits.by_ip.check("10.0.0.1", now), Ok(()));
+        assert!(limits.by_ip.check("10.0.0.1", now).is_err());
+        for _ in 0..3 {
+            assert_eq!(limits.enrollment_by_ip.check("10.0.0.1", now), Ok(()));
+        }
+        assert!(limits.enrollment_by_ip.check("10.0.0.1", now).is_err());
+    }
+
+    #[test]
+    fn least_recently_used_bucket_is_evicted() {
+        let limiter = RateLimiter::new(1, 1);
+        limiter.buckets.lock().unwrap().capacity = 2;
+        let now = Instant::now();
+        assert_eq!(limiter.check("a", now), Ok(()));
+        assert_eq!(limiter.check("b", now), Ok(()));
+        assert!(limiter.check("a", now).is_err());
+
+        // "b" is now the least recently used and makes room for "c"
+        assert_eq!(limiter.check("c", now), Ok(()));
+        let buckets = limiter.buckets.lock().unwrap();
+        assert_eq!(buckets.entries.len(), 2);
+        assert_eq!(buckets.order.len(), 2);
+        assert!(buckets.entries.contains_key("a"));
+        assert!(!buckets.entries.contains_key("b"));
+    }
+
+    #[test]
+    fn lockouts_double_from_the_threshold_and_reset_on_success() {
+        let lockouts = LoginLockouts::new(3, 10 * SECOND, 900 * SECOND);
+        let now = Instant::now();
+        assert_eq!(lockouts.record_failure("user@example.com", now), None);
+        assert_eq!(lockouts.record_failure("user@example.com", now), None);
+        assert_eq!(lockouts.locked_for("user@example.com", now), None);
+
+        assert_eq!(lockouts.record_failure("user@example.com", now), Some(10 * SECOND));
+        assert_eq!(lockouts.locked_for("user@example.com", now), Some(10));
+        assert_eq!(lockouts.record_failure("user@example.com", now), Some(20 * SECOND));
+        assert_eq!(lockouts.record_failure("user@example.com", now), Some(40 * SECOND));
+        assert_eq!(lockouts.locked_for("user@example.com", now + 40 * SECOND), None);
+
+        lockouts.record_success("user@example.com");
+        assert_eq!(lockouts.record_failure("user@example.com", now), None);
+    }
+
+    #[test]
+    fn lockouts_are_capped_and_forgotten_after_the_failure_window() {
+        let lockouts = LoginLockouts::new(1, 10 * SECOND, 900 * SECOND);
+        let now = Instant::now();
+        for _ in 0..20 {
+            lockouts.record_failure("user@example.com", now);
+        }
+        assert_eq!(lockouts.locked_for("user@example.com", now), Some(MAX_LOCKOUT.as_secs()));
+
+        let later = now + 900 * SECOND;
+        assert_eq!(lockouts.record_failure("user@example.com", later), Some(10 * SECOND));
+    }
+
+    #[test]
+    fn least_recently_failed_account_is_evicted() {
+        let lockouts = LoginLockouts::new(2, 10 * SECOND, 900 * SECOND);
+        lockouts.accounts.lock().unwrap().counting.capacity = 2;
+        let now = Instant::now();
+        lockouts.record_failure("a", now);
+        lockouts.record_failure("b", now);
+        lockouts.record_failure("c", now);
+
+        // "a" was forgotten, so its next failure is counted as the first
+        assert_eq!(lockouts.record_failure("a", now), None);
+        assert_eq!(lockouts.record_failure("c", now), Some(10 * SECOND));
+    }
+
+    #[test]
+    fn locked_accounts_are_not_evicted_by_other_failures() {
+        let lockouts = LoginLockouts::new(1, 10 * SECOND, 900 * SECOND);
+        lockouts.accounts.lock().unwrap().counting.capacity = 1;
+        let now = Instant::now();
+        assert_eq!(lockouts.record_failure("victim", now), Some(10 * SECOND));
+        for i in 0..100 {
+            lockouts.record_failure(&format!("other{}@example.com", i), now);
+        }
+        assert_eq!(lockouts.locked_for("victim", now), Some(10));
+
+        // The failure count survives the lockout, so the next one still escalates
+        let later = now + 20 * SECOND;
+        assert_eq!(lockouts.locked_for("victim", later), None);
+        assert_eq!(lockouts.record_failure("victim", later), Some(20 * SECOND));
+    }
+
+    #[test]
+    fn locked_accounts_are_forgotten_once_lockout_and_window_pass() {
+        let lockouts = LoginLockouts::new(1, 10 * SECOND, 900 * SECOND);
+        let now = Instant::now();
+        lockouts.record_failure("a", now);
+        lockouts.record_failure("b", now + 900 * SECOND);
+
+        let accounts = lockouts.accounts.lock().unwrap();
+        assert!(!accounts.locked.contains_key("a"));
+        assert!(accounts.locked.contains_key("b"));
+        assert_eq!(accounts.expiries.len(), 1);
+    }
+}
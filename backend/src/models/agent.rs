@@ -0,0 +1,223 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, RwLock},
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use anticheat_protocol::{agent::Agent, ingest::AgentHeartbeat, VersionStatus};
+
+/// Connectivity status derived from the time since an agent's last heartbeat
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AgentStatus {
+    Online,
+    Stale,
+    Offline,
+}
+
+impl fmt::Display for AgentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            AgentStatus::Online => "online",
+            AgentStatus::Stale => "stale",
+            AgentStatus::Offline => "offline",
+        };
+        f.write_str(s)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AgentRecord {
+    pub id: String,
+    pub org_id: String,
+    pub name: String,
+    pub platform: String,
+    pub version: String,
+    pub status: AgentStatus,
+    pub tags: Vec<String>,
+    pub cpu_usage: Option<f32>,
+    pub memory_usage: Option<f32>,
+    pub last_scan_at: Option<DateTime<Utc>>,
+    pub scan_count: u64,
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    /// Agent clock minus server clock at the last heartbeat that carried `sent_at`
+    pub clock_skew_ms: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AgentRecord {
+    /// Dashboard representation, with the version evaluated against the org policy
+    pub fn into_agent(self, version_status: VersionStatus) -> Agent {
+        Agent {
+            id: self.id,
+            org_id: self.org_id,
+            name: self.name,
+            platform: self.platform,
+            version: self.version,
+            status: self.status.to_string(),
+            version_status,
+            tags: self.tags,
+            cpu_usage: self.cpu_usage,
+            memory_usage: self.memory_usage,
+            last_scan_at: self.last_scan_at,
+            scan_count: self.scan_count,
+            last_heartbeat: self.last_heartbeat,
+            clock_skew_ms: self.clock_skew_ms,
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AgentStatusChange {
+    pub org_id: String,
+    pub agent_id: String,
+    /// `None` when the agent was seen for the first time
+    pub previous: Option<AgentStatus>,
+    pub current: AgentStatus,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// Silence thresholds after which an agent is considered stale or offline
+#[derive(Debug, Clone, Copy)]
+pub struct StatusThresholds {
+    pub stale_after: Duration,
+    pub offline_after: Duration,
+}
+
+impl StatusThresholds {
+    pub fn status_for(&self, last_heartbeat: Option<DateTime<Utc>>, now: DateTime<Utc>) -> AgentStatus {
+        match last_heartbeat {
+            Some(seen) if now - seen < self.stale_after => AgentStatus::Online,
+            Some(seen) if now - seen < self.offline_after => AgentStatus::Stale,
+            _ => AgentStatus::Offline,
+        }
+    }
+}
+
+type AgentKey = (String, String);
+
+/// In-memory agent registry keyed by (org_id, agent_id)
+#[derive(Clone, Default)]
+pub struct AgentStore {
+    agents: Arc<RwLock<HashMap<AgentKey, AgentRecord>>>,
+}
+
+impl AgentStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts a freshly enrolled agent; it stays offline until its first heartbeat
+    pub fn register(&self, record: AgentRecord) {
+        let mut agents = self.agents.write().expect("agent store lock poisoned");
+        agents.insert((record.org_id.clone(), record.id.clone()), record);
+    }
+
+    /// Upserts the agent from a heartbeat and marks it online.
+    /// Returns the status change if the agent was not already online.
+    pub fn record_heartbeat(
+        &self,
+        org_id: &str,
+        agent_id: &str,
+        heartbeat: &AgentHeartbeat,
+        now: DateTime<Utc>,
+    ) -> Option<AgentStatusChange> {
+        let mut agents = self.agents.write().expect("agent store lock poisoned");
+        let record = agents
+            .entry((org_id.to_string(), agent_id.to_string()))
+            .or_insert_with(|| AgentRecord {
+                id: agent_id.to_string(),
+                org_id: org_id.to_string(),
+                name: agent_id.to_string(),
+                platform: heartbeat.platform.clone(),
+                version: heartbeat.agent_version.clone(),
+                status: AgentStatus::Offline,
+                tags: Vec::new(),
+                cpu_usage: None,
+                memory_usage: None,
+                last_scan_at: None,
+                scan_count: 0,
+                last_heartbeat: None,
+                clock_skew_ms: None,
+                created_at: now,
+                updated_at: now,
+            });
+
+        let previous = record.last_heartbeat.map(|_| record.status);
+
+        record.platform = heartbeat.platform.clone();
+        record.version = heartbeat.agent_version.clone();
+        record.cpu_usage = heartbeat.cpu_usage;
+        record.memory_usage = heartbeat.memory_usage;
+        if heartbeat.last_scan_at.is_some() {
+            record.last_scan_at = heartbeat.last_scan_at;
+        }
+        if let Some(scan_count) = heartbeat.scan_count {
+            record.scan_count = scan_count;
+        }
+        if let Some(sent_at) = heartbeat.sent_at {
+            // Includes the network latency of the request, so a small negative skew is expected
+            record.clock_skew_ms = Some((sent_at - now).num_milliseconds());
+        }
+        record.last_heartbeat = Some(now);
+        record.status = AgentStatus::Online;
+        record.updated_at = now;
+
+        if previous == Some(AgentStatus::Online) {
+            return None;
+        }
+
+        Some(AgentStatusChange {
+            org_id: org_id.to_string(),
+            agent_id: agent_id.to_string(),
+            previous,
+            current: AgentStatus::Online,
+            changed_at: now,
+        })
+    }
+
+    /// Re-evaluates every agent against the thresholds, returning the transitions
+    pub fn sweep(&self, thresholds: &StatusThresholds, now: DateTime<Utc>) -> Vec<AgentStatusChange> {
+        let mut agents = self.agents.write().expect("agent store lock poisoned");
+        let mut changes = Vec::new();
+
+        for record in agents.values_mut() {
+            let status = thresholds.status_for(record.last_heartbeat, now);
+            if status != record.status {
+                changes.push(AgentStatusChange {
+                    org_id: record.org_id.clone(),
+                    agent_id: record.id.clone(),
+                    previous: Some(record.status),
+                    current: status,
+                    changed_at: now,
+                });
+                record.status = status;
+                record.updated_at = now;
+            }
+        }
+
+        changes
+    }
+
+    pub fn get(&self, org_id: &str, agent_id: &str) -> Option<AgentRecord> {
+        let agents = self.agents.read().expect("agent store lock poisoned");
+        agents.get(&(org_id.to_string(), agent_id.to_string())).cloned()
+    }
+
+    /// All agents of an org, oldest first
+    pub fn list(&self, org_id: &str) -> Vec<AgentRecord> {
+        let agents = self.agents.read().expect("agent store lock poisoned");
+        let mut records: Vec<AgentRecord> = agents
+            .values()
+            .filter(|record| record.org_id == org_id)
+            .cloned()
+            .collect();
+        records.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+        records
+    }
+}
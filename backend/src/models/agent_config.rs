@@ -0,0 +1,154 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub use anticheat_protocol::config::{AgentConfigBody, ResolvedConfig};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("config profile not found")]
+    ProfileNotFound,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ConfigProfile {
+    pub id: String,
+    pub org_id: String,
+    pub name: String,
+    /// Org-wide revision number, bumped on every profile change
+    pub version: u64,
+    pub body: AgentConfigBody,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct OrgConfigs {
+    revision: u64,
+    profiles: HashMap<String, ConfigProfile>,
+    agent_assignments: HashMap<String, String>,
+    /// tag -> (profile_id, revision at assignment); the latest assignment wins
+    tag_assignments: HashMap<String, (String, u64)>,
+}
+
+impl OrgConfigs {
+    fn next_revision(&mut self) -> u64 {
+        self.revision += 1;
+        self.revision
+    }
+}
+
+/// Versioned agent configuration profiles and their assignments, per org
+#[derive(Clone, Default)]
+pub struct ConfigStore {
+    orgs: Arc<RwLock<HashMap<String, OrgConfigs>>>,
+}
+
+impl ConfigStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn create(&self, org_id: &str, name: String, body: AgentConfigBody, now: DateTime<Utc>) -> ConfigProfile {
+        let mut orgs = self.orgs.write().expect("config store lock poisoned");
+        let org = orgs.entry(org_id.to_string()).or_default();
+        let profile = ConfigProfile {
+            id: Uuid::new_v4().to_string(),
+            org_id: org_id.to_string(),
+            name,
+            version: org.next_revision(),
+            body,
+            created_at: now,
+            updated_at: now,
+        };
+        org.profiles.insert(profile.id.clone(), profile.clone());
+        profile
+    }
+
+    /// Replaces the profile body and bumps its version
+    pub fn update(
+        &self,
+        org_id: &str,
+        profile_id: &str,
+        body: AgentConfigBody,
+        now: DateTime<Utc>,
+    ) -> Result<ConfigProfile, ConfigError> {
+        let mut orgs = self.orgs.write().expect("config store lock poisoned");
+        let org = orgs.get_mut(org_id).ok_or(ConfigError::ProfileNotFound)?;
+        if !org.profiles.contains_key(profile_id) {
+            return Err(ConfigError::ProfileNotFound);
+        }
+        let version = org.next_revision();
+        let profile = org.profiles.get_mut(profile_id).ok_or(ConfigError::ProfileNotFound)?;
+        profile.body = body;
+        profile.version = version;
+        profile.updated_at = now;
+        Ok(profile.clone())
+    }
+
+    pub fn list(&self, org_id: &str) -> Vec<ConfigProfile> {
+        let orgs = self.orgs.read().expect("config store lock poisoned");
+        let mut profiles: Vec<ConfigProfile> = orgs
+            .get(org_id)
+            .map(|org| org.profiles.values().cloned().collect())
+            .unwrap_or_default();
+        profiles.sort_by(|a, b| a.created_at.cmp(&b.created_at).then_with(|| a.id.cmp(&b.id)));
+        profiles
+    }
+
+    /// Assigns the profile to the given agents and tags, replacing earlier assignments
+    pub fn assign(
+        &self,
+        org_id: &str,
+        profile_id: &str,
+        agent_ids: &[String],
+        tags: &[String],
+    ) -> Result<(), ConfigError> {
+        let mut orgs = self.orgs.write().expect("config store lock poisoned");
+        let org = orgs.get_mut(org_id).ok_or(ConfigError::ProfileNotFound)?;
+        if !org.profiles.contains_key(profile_id) {
+            return Err(ConfigError::ProfileNotFound);
+        }
+        let revision = org.next_revision();
+        for agent_id in agent_ids {
+            org.agent_assignments.insert(agent_id.clone(), profile_id.to_string());
+        }
+        for tag in tags {
+            org.tag_assignments.insert(tag.clone(), (profile_id.to_string(), revision));
+        }
+        Ok(())
+    }
+
+    /// Direct agent assignments win over tag assignments; among tags the latest assignment wins
+    pub fn resolve(&self, org_id: &str, agent_id: &str, agent_tags: &[String]) -> ResolvedConfig {
+        let orgs = self.orgs.read().expect("config store lock poisoned");
+        let profile = orgs.get(org_id).and_then(|org| {
+            let profile_id = org.agent_assignments.get(agent_id).or_else(|| {
+                agent_tags
+                    .iter()
+                    .filter_map(|tag| org.tag_assignments.get(tag))
+                    .max_by_key(|(_, revision)| *revision)
+                    .map(|(profile_id, _)| profile_id)
+            })?;
+            org.profiles.get(profile_id)
+        });
+
+        match profile {
+            Some(profile) => ResolvedConfig {
+                version: profile.version,
+                profile_id: Some(profile.id.clone()),
+                body: Some(profile.body.clone()),
+            },
+            None => ResolvedConfig {
+                version: 0,
+                profile_id: None,
+                body: None,
+            },
+        }
+    }
+}
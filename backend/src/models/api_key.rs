@@ -0,0 +1,124 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, RwLock},
+};
+use chrono::{DateTime, Utc};
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+use anticheat_protocol::signing;
+
+/// Hex-encoded SHA-256 of a secret; only hashes are kept in memory
+pub fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+/// Random alphanumeric string suitable for tokens and keys
+pub fn generate_secret(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiKeyRecord {
+    pub org_id: String,
+    pub agent_id: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// What a signed request is checked against; derived from the key at issue time,
+/// since the plaintext is never kept
+#[derive(Debug, Clone)]
+struct SigningKey {
+    secret: String,
+    hash: String,
+}
+
+#[derive(Default)]
+struct Keys {
+    by_hash: HashMap<String, ApiKeyRecord>,
+    /// Key ID of signed requests to its signing secret and key hash
+    signing_by_key_id: HashMap<String, SigningKey>,
+    /// Orgs with at least one enrolled key
+    orgs: HashSet<String>,
+}
+
+/// Issued agent API keys, indexed by key hash and by signing key ID
+#[derive(Clone, Default)]
+pub struct ApiKeyStore {
+    keys: Arc<RwLock<Keys>>,
+}
+
+impl ApiKeyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Issues a new key `<prefix>_<random>` for the agent and returns the plaintext once
+    pub fn issue(&self, prefix: &str, org_id: &str, agent_id: &str, now: DateTime<Utc>) -> String {
+        let api_key = format!("{}_{}", prefix, generate_secret(40));
+        let record = ApiKeyRecord {
+            org_id: org_id.to_string(),
+            agent_id: agent_id.to_string(),
+            created_at: now,
+        };
+        let hash = hash_secret(&api_key);
+        let mut keys = self.keys.write().expect("api key store lock poisoned");
+        let signing_key = SigningKey { secret: signing::signing_secret(&api_key), hash: hash.clone() };
+        keys.signing_by_key_id.insert(signing::key_id(&api_key), signing_key);
+        keys.orgs.insert(record.org_id.clone());
+        keys.by_hash.insert(hash, record);
+        api_key
+    }
+
+    pub fn lookup(&self, api_key: &str) -> Option<ApiKeyRecord> {
+        self.keys
+            .read()
+            .expect("api key store lock poisoned")
+            .by_hash
+            .get(&hash_secret(api_key))
+            .cloned()
+    }
+
+    /// Whether the org has enrolled any agent, after which it no longer accepts legacy keys
+    pub fn org_has_keys(&self, org_id: &str) -> bool {
+        self.keys
+            .read()
+            .expect("api key store lock poisoned")
+            .orgs
+            .contains(org_id)
+    }
+
+    /// The signing secret and record of the key with this signing key ID
+    pub fn lookup_by_key_id(&self, key_id: &str) -> Option<(String, ApiKeyRecord)> {
+        let keys = self.keys.read().expect("api key store lock poisoned");
+        let signing_key = keys.signing_by_key_id.get(key_id)?;
+        keys.by_hash
+            .get(&signing_key.hash)
+            .map(|record| (signing_key.secret.clone(), record.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn issued_keys_are_found_by_key_and_signing_key_id() {
+        let store = ApiKeyStore::new();
+        assert!(!store.org_has_keys("org_1"));
+
+        let api_key = store.issue("org", "org_1", "agent_1", Utc::now());
+        assert!(store.org_has_keys("org_1"));
+        assert!(!store.org_has_keys("org_2"));
+        assert_eq!(store.lookup(&api_key).map(|record| record.agent_id), Some("agent_1".to_string()));
+        assert!(store.lookup("org_1_agent_1_forged").is_none());
+
+        let (secret, record) = store.lookup_by_key_id(&signing::key_id(&api_key)).expect("signing key");
+        assert_eq!(secret, signing::signing_secret(&api_key));
+        assert_ne!(secret, hash_secret(&api_key));
+        assert_eq!(record.org_id, "org_1");
+    }
+}
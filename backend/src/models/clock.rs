@@ -0,0 +1,85 @@
+use chrono::{DateTime, Duration, Utc};
+
+pub use anticheat_protocol::dashboard::TimestampFlag;
+
+/// What happens to events whose `detected_at` falls outside the bounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutOfBoundsAction {
+    /// Accept the event with a `timestamp_flag`
+    Flag,
+    /// Fail the event with a per-event error
+    Reject,
+}
+
+/// How far an agent-reported `detected_at` may stray from the server clock
+#[derive(Debug, Clone, Copy)]
+pub struct TimestampBounds {
+    pub max_future: Duration,
+    pub max_past: Duration,
+    pub action: OutOfBoundsAction,
+}
+
+impl Default for TimestampBounds {
+    fn default() -> Self {
+        Self {
+            max_future: Duration::minutes(5),
+            max_past: Duration::days(30),
+            action: OutOfBoundsAction::Flag,
+        }
+    }
+}
+
+impl TimestampBounds {
+    /// Reads `EVENT_MAX_FUTURE_SECS` (default 300), `EVENT_MAX_PAST_SECS` (default 30 days)
+    /// and `EVENT_TIMESTAMP_ACTION` (`flag` or `reject`, default `flag`)
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let secs = |name: &str, default: Duration| match std::env::var(name) {
+            Ok(value) => Duration::seconds(
+                value
+                    .parse::<u32>()
+                    .unwrap_or_else(|_| panic!("{} must be a valid number of seconds", name))
+                    .into(),
+            ),
+            Err(_) => default,
+        };
+        let action = match std::env::var("EVENT_TIMESTAMP_ACTION").as_deref() {
+            Err(_) | Ok("flag") => OutOfBoundsAction::Flag,
+            Ok("reject") => OutOfBoundsAction::Reject,
+            Ok(other) => panic!("EVENT_TIMESTAMP_ACTION must be 'flag' or 'reject', got '{}'", other),
+        };
+
+        Self {
+            max_future: secs("EVENT_MAX_FUTURE_SECS", defaults.max_future),
+            max_past: secs("EVENT_MAX_PAST_SECS", defaults.max_past),
+            action,
+        }
+    }
+
+    /// Flags a detection time too far ahead of or behind `received_at`
+    pub fn check(&self, detected_at: DateTime<Utc>, received_at: DateTime<Utc>) -> Option<TimestampFlag> {
+        if detected_at > received_at + self.max_future {
+            Some(TimestampFlag::Future)
+        } else if detected_at < received_at - self.max_past {
+            Some(TimestampFlag::Past)
+        } else {
+            None
+        }
+    }
+
+    /// Per-event error for a timestamp that is rejected
+    pub fn describe(&self, flag: TimestampFlag, detected_at: DateTime<Utc>) -> String {
+        match flag {
+            TimestampFlag::Future => format!(
+                "detected_at {} is more than {}s ahead of server time",
+                detected_at.to_rfc3339(),
+                self.max_future.num_seconds()
+            ),
+            TimestampFlag::Past => format!(
+                "detected_at {} is more than {}s in the past",
+                detected_at.to_rfc3339(),
+                self.max_past.num_seconds()
+            ),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+use chrono::{DateTime, Utc};
+use crate::models::api_key::{generate_secret, hash_secret};
+
+#[derive(Debug, thiserror::Error)]
+pub enum EnrollmentError {
+    #[error("enrollment token not found")]
+    NotFound,
+    #[error("enrollment token expired")]
+    Expired,
+    #[error("enrollment token has no uses left")]
+    Exhausted,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnrollmentToken {
+    pub id: String,
+    pub org_id: String,
+    /// Agent name template; `{hostname}` is replaced with the enrolling host's name
+    pub name_template: String,
+    pub tags: Vec<String>,
+    pub max_uses: u32,
+    pub uses: u32,
+    pub expires_at: DateTime<Utc>,
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl EnrollmentToken {
+    pub fn agent_name(&self, hostname: &str) -> String {
+        self.name_template.replace("{hostname}", hostname)
+    }
+}
+
+/// Short-lived, limited-use tokens that let fresh agents obtain their own API key
+#[derive(Clone, Default)]
+pub struct EnrollmentStore {
+    tokens: Arc<RwLock<HashMap<String, EnrollmentToken>>>,
+}
+
+impl EnrollmentStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores the token and returns its plaintext value, which is never retrievable again
+    pub fn create(&self, token: EnrollmentToken) -> String {
+        let secret = format!("enr_{}", generate_secret(32));
+        self.tokens
+            .write()
+            .expect("enrollment store lock poisoned")
+            .insert(hash_secret(&secret), token);
+        secret
+    }
+
+    /// Consumes one use of the token, removing it once expired or exhausted
+    pub fn redeem(&self, secret: &str, now: DateTime<Utc>) -> Result<EnrollmentToken, EnrollmentError> {
+        let mut tokens = self.tokens.write().expect("enrollment store lock poisoned");
+        let hash = hash_secret(secret);
+        let token = tokens.get_mut(&hash).ok_or(EnrollmentError::NotFound)?;
+
+        if token.expires_at <= now {
+            tokens.remove(&hash);
+            return Err(EnrollmentError::Expired);
+        }
+        if token.uses >= token.max_uses {
+            tokens.remove(&hash);
+            return Err(EnrollmentError::Exhausted);
+        }
+
+        token.uses += 1;
+        let redeemed = token.clone();
+        if redeemed.uses >= redeemed.max_uses {
+            tokens.remove(&hash);
+        }
+        Ok(redeemed)
+    }
+}
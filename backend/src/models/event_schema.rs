@@ -0,0 +1,190 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+use chrono::{DateTime, Utc};
+use jsonschema::Validator;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+pub use anticheat_protocol::severity::Severity;
+
+/// Metadata errors listed per event; the rest are summarised as a count
+const MAX_METADATA_ERRORS: usize = 5;
+
+#[derive(Debug, thiserror::Error)]
+pub enum EventSchemaError {
+    #[error("invalid metadata schema: {0}")]
+    InvalidSchema(String),
+    #[error("event type not found")]
+    NotFound,
+}
+
+/// What happens to events whose `event_type` has no registered schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum UnknownEventTypes {
+    /// Ingest them unchecked
+    #[default]
+    Accept,
+    /// Fail them with a per-event error
+    Reject,
+}
+
+/// Org-level handling of event types missing from the registry
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
+pub struct EventTypePolicy {
+    #[serde(default)]
+    pub unknown_event_types: UnknownEventTypes,
+
+    #[serde(skip_deserializing)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Declared shape of one event type
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct EventTypeSchema {
+    pub event_type: String,
+    pub description: Option<String>,
+    /// JSON Schema the event `metadata` must satisfy
+    #[schema(value_type = Object)]
+    pub metadata_schema: serde_json::Value,
+    /// Severities events of this type may carry; empty allows any
+    pub allowed_severities: Vec<Severity>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+struct CompiledSchema {
+    schema: EventTypeSchema,
+    validator: Arc<Validator>,
+}
+
+#[derive(Default)]
+struct OrgRegistry {
+    policy: EventTypePolicy,
+    types: HashMap<String, CompiledSchema>,
+}
+
+/// Event type registry per org; orgs without entries accept every event type
+#[derive(Clone, Default)]
+pub struct EventSchemaStore {
+    orgs: Arc<RwLock<HashMap<String, OrgRegistry>>>,
+}
+
+impl EventSchemaStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn list(&self, org_id: &str) -> Vec<EventTypeSchema> {
+        let orgs = self.orgs.read().expect("event schema lock poisoned");
+        let mut schemas: Vec<_> = orgs
+            .get(org_id)
+            .map(|org| org.types.values().map(|c| c.schema.clone()).collect())
+            .unwrap_or_default();
+        schemas.sort_by(|a, b| a.event_type.cmp(&b.event_type));
+        schemas
+    }
+
+    pub fn get(&self, org_id: &str, event_type: &str) -> Option<EventTypeSchema> {
+        let orgs = self.orgs.read().expect("event schema lock poisoned");
+        orgs.get(org_id)?.types.get(event_type).map(|c| c.schema.clone())
+    }
+
+    /// Compiles the metadata schema and creates or replaces the event type
+    pub fn upsert(
+        &self,
+        org_id: &str,
+        event_type: &str,
+        description: Option<String>,
+        metadata_schema: serde_json::Value,
+        allowed_severities: Vec<Severity>,
+        now: DateTime<Utc>,
+    ) -> Result<EventTypeSchema, EventSchemaError> {
+        let validator = jsonschema::validator_for(&metadata_schema)
+            .map_err(|e| EventSchemaError::InvalidSchema(e.to_string()))?;
+
+        let mut orgs = self.orgs.write().expect("event schema lock poisoned");
+        let org = orgs.entry(org_id.to_string()).or_default();
+        let created_at = org.types.get(event_type).map_or(now, |c| c.schema.created_at);
+        let schema = EventTypeSchema {
+            event_type: event_type.to_string(),
+            description,
+            metadata_schema,
+            allowed_severities,
+            created_at,
+            updated_at: now,
+        };
+        org.types.insert(event_type.to_string(), CompiledSchema {
+            schema: schema.clone(),
+            validator: Arc::new(validator),
+        });
+        Ok(schema)
+    }
+
+    pub fn delete(&self, org_id: &str, event_type: &str) -> Result<(), EventSchemaError> {
+        let mut orgs = self.orgs.write().expect("event schema lock poisoned");
+        orgs.get_mut(org_id)
+            .and_then(|org| org.types.remove(event_type))
+            .map(|_| ())
+            .ok_or(EventSchemaError::NotFound)
+    }
+
+    pub fn policy(&self, org_id: &str) -> EventTypePolicy {
+        let orgs = self.orgs.read().expect("event schema lock poisoned");
+        orgs.get(org_id).map(|org| org.policy.clone()).unwrap_or_default()
+    }
+
+    pub fn set_policy(&self, org_id: &str, mut policy: EventTypePolicy, now: DateTime<Utc>) -> EventTypePolicy {
+        policy.updated_at = Some(now);
+        let mut orgs = self.orgs.write().expect("event schema lock poisoned");
+        orgs.entry(org_id.to_string()).or_default().policy = policy.clone();
+        policy
+    }
+
+    /// Checks an event's severity and metadata against its registered type,
+    /// describing every violation found
+    pub fn check(&self, org_id: &str, event_type: &str, severity: Severity, metadata: &serde_json::Value) -> Result<(), String> {
+        let (allowed_severities, validator) = {
+            let orgs = self.orgs.read().expect("event schema lock poisoned");
+            let Some(org) = orgs.get(org_id) else {
+                return Ok(());
+            };
+            match org.types.get(event_type) {
+                Some(compiled) => (compiled.schema.allowed_severities.clone(), compiled.validator.clone()),
+                None if org.policy.unknown_event_types == UnknownEventTypes::Reject => {
+                    return Err(format!("event_type '{}' is not registered", event_type));
+                }
+                None => return Ok(()),
+            }
+        };
+
+        let mut problems = Vec::new();
+        if !allowed_severities.is_empty() && !allowed_severities.contains(&severity) {
+            problems.push(format!(
+                "severity '{}' is not allowed for event_type '{}' (allowed: {})",
+                severity,
+                event_type,
+                allowed_severities.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(", ")
+            ));
+        }
+
+        let mut metadata_errors = 0;
+        for error in validator.iter_errors(metadata) {
+            metadata_errors += 1;
+            if metadata_errors <= MAX_METADATA_ERRORS {
+                problems.push(format!("metadata{}: {}", error.instance_path, error));
+            }
+        }
+        if metadata_errors > MAX_METADATA_ERRORS {
+            problems.push(format!("{} more metadata errors", metadata_errors - MAX_METADATA_ERRORS));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems.join("; "))
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{Arc, RwLock},
+};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Width of the downsampled buckets kept after raw samples expire
+pub const ROLLUP_BUCKET_SECS: i64 = 60;
+
+/// Upper bound on raw samples kept per agent regardless of retention
+const MAX_RAW_SAMPLES: usize = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+pub struct HealthSample {
+    pub at: DateTime<Utc>,
+    pub cpu_usage: Option<f32>,
+    pub memory_usage: Option<f32>,
+}
+
+/// Running aggregate of samples falling into one bucket
+#[derive(Debug, Clone, Copy, Default)]
+struct Aggregate {
+    samples: u32,
+    cpu_sum: f64,
+    cpu_count: u32,
+    cpu_max: Option<f32>,
+    memory_sum: f64,
+    memory_count: u32,
+    memory_max: Option<f32>,
+}
+
+impl Aggregate {
+    fn add(&mut self, sample: &HealthSample) {
+        self.samples += 1;
+        if let Some(cpu) = sample.cpu_usage {
+            self.cpu_sum += cpu as f64;
+            self.cpu_count += 1;
+            self.cpu_max = Some(self.cpu_max.map_or(cpu, |max| max.max(cpu)));
+        }
+        if let Some(memory) = sample.memory_usage {
+            self.memory_sum += memory as f64;
+            self.memory_count += 1;
+            self.memory_max = Some(self.memory_max.map_or(memory, |max| max.max(memory)));
+        }
+    }
+
+    fn merge(&mut self, other: &Aggregate) {
+        self.samples += other.samples;
+        self.cpu_sum += other.cpu_sum;
+        self.cpu_count += other.cpu_count;
+        self.cpu_max = max_opt(self.cpu_max, other.cpu_max);
+        self.memory_sum += other.memory_sum;
+        self.memory_count += other.memory_count;
+        self.memory_max = max_opt(self.memory_max, other.memory_max);
+    }
+
+    fn to_point(self, timestamp: DateTime<Utc>) -> HealthPoint {
+        HealthPoint {
+            timestamp,
+            samples: self.samples,
+            cpu_avg: (self.cpu_count > 0).then(|| (self.cpu_sum / self.cpu_count as f64) as f32),
+            cpu_max: self.cpu_max,
+            memory_avg: (self.memory_count > 0).then(|| (self.memory_sum / self.memory_count as f64) as f32),
+            memory_max: self.memory_max,
+        }
+    }
+}
+
+fn max_opt(a: Option<f32>, b: Option<f32>) -> Option<f32> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.max(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// One step of an agent health time series
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct HealthPoint {
+    pub timestamp: DateTime<Utc>,
+    pub samples: u32,
+    pub cpu_avg: Option<f32>,
+    pub cpu_max: Option<f32>,
+    pub memory_avg: Option<f32>,
+    pub memory_max: Option<f32>,
+}
+
+#[derive(Default)]
+struct AgentSeries {
+    raw: VecDeque<HealthSample>,
+    rollups: BTreeMap<i64, Aggregate>,
+}
+
+type AgentKey = (String, String);
+
+/// Heartbeat samples per agent: recent raw samples plus per-minute rollups
+#[derive(Clone, Default)]
+pub struct HealthStore {
+    series: Arc<RwLock<HashMap<AgentKey, AgentSeries>>>,
+}
+
+impl HealthStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, org_id: &str, agent_id: &str, sample: HealthSample) {
+        let mut series = self.series.write().expect("health store lock poisoned");
+        let agent = series.entry((org_id.to_string(), agent_id.to_string())).or_default();
+
+        agent.raw.push_back(sample);
+        if agent.raw.len() > MAX_RAW_SAMPLES {
+            agent.raw.pop_front();
+        }
+
+        let bucket = sample.at.timestamp().div_euclid(ROLLUP_BUCKET_SECS) * ROLLUP_BUCKET_SECS;
+        agent.rollups.entry(bucket).or_default().add(&sample);
+    }
+
+    /// Aggregates samples in `[from, to)` into `step_secs` wide points.
+    /// Steps shorter than the rollup width are served from raw samples only.
+    pub fn query(
+        &self,
+        org_id: &str,
+        agent_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        step_secs: i64,
+    ) -> Vec<HealthPoint> {
+        let series = self.series.read().expect("health store lock poisoned");
+        let Some(agent) = series.get(&(org_id.to_string(), agent_id.to_string())) else {
+            return Vec::new();
+        };
+
+        let from_ts = from.timestamp();
+        let to_ts = to.timestamp();
+        let step_start = |ts: i64| from_ts + (ts - from_ts).div_euclid(step_secs) * step_secs;
+        let mut steps: BTreeMap<i64, Aggregate> = BTreeMap::new();
+
+        if step_secs < ROLLUP_BUCKET_SECS {
+            for sample in agent.raw.iter().filter(|s| s.at >= from && s.at < to) {
+                steps.entry(step_start(sample.at.timestamp())).or_default().add(sample);
+            }
+        } else {
+            for (bucket, aggregate) in agent.rollups.range(from_ts..to_ts) {
+                steps.entry(step_start(*bucket)).or_default().merge(aggregate);
+            }
+        }
+
+        steps
+            .into_iter()
+            .filter_map(|(ts, aggregate)| Utc.timestamp_opt(ts, 0).single().map(|t| aggregate.to_point(t)))
+            .collect()
+    }
+
+    /// Drops raw samples older than `raw_cutoff` and rollups older than `rollup_cutoff`
+    pub fn prune(&self, raw_cutoff: DateTime<Utc>, rollup_cutoff: DateTime<Utc>) {
+        let mut series = self.series.write().expect("health store lock poisoned");
+        let rollup_cutoff_ts = rollup_cutoff.timestamp();
+
+        for agent in series.values_mut() {
+            while agent.raw.front().is_some_and(|s| s.at < raw_cutoff) {
+                agent.raw.pop_front();
+            }
+            agent.rollups = agent.rollups.split_off(&rollup_cutoff_ts);
+        }
+        series.retain(|_, agent| !agent.raw.is_empty() || !agent.rollups.is_empty());
+    }
+}
@@ -0,0 +1,158 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Scope {
+    /// `Idempotency-Key` header of a whole batch
+    Batch,
+    /// `event_id` of a single event
+    Event,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SeenKey {
+    org_id: String,
+    agent_id: String,
+    scope: Scope,
+    id: String,
+}
+
+impl SeenKey {
+    fn new(org_id: &str, agent_id: &str, scope: Scope, id: &str) -> Self {
+        Self { org_id: org_id.to_string(), agent_id: agent_id.to_string(), scope, id: id.to_string() }
+    }
+}
+
+/// Batch keys and event IDs already ingested, per agent, until they fall out of the retention window
+#[derive(Clone, Default)]
+pub struct IdempotencyStore {
+    seen: Arc<RwLock<HashMap<SeenKey, DateTime<Utc>>>>,
+}
+
+impl IdempotencyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a batch key; returns `false` when the agent already sent a batch with it
+    pub fn claim_batch(&self, org_id: &str, agent_id: &str, key: &str, now: DateTime<Utc>) -> bool {
+        self.claim(org_id, agent_id, Scope::Batch, key, now)
+    }
+
+    /// Records an event ID; returns `false` when the agent already sent an event with it
+    pub fn claim_event(&self, org_id: &str, agent_id: &str, event_id: &str, now: DateTime<Utc>) -> bool {
+        self.claim(org_id, agent_id, Scope::Event, event_id, now)
+    }
+
+    /// Whether the agent already sent a batch with this key, without recording it
+    pub fn batch_seen(&self, org_id: &str, agent_id: &str, key: &str) -> bool {
+        self.seen_before(org_id, agent_id, Scope::Batch, key)
+    }
+
+    /// Whether the agent already sent an event with this ID, without recording it
+    pub fn event_seen(&self, org_id: &str, agent_id: &str, event_id: &str) -> bool {
+        self.seen_before(org_id, agent_id, Scope::Event, event_id)
+    }
+
+    /// Forgets a batch key claimed for a batch that was then refused, so it can be resent
+    pub fn release_batch(&self, org_id: &str, agent_id: &str, key: &str) {
+        self.release(org_id, agent_id, Scope::Batch, key)
+    }
+
+    /// Forgets an event ID claimed for an event that was then refused, so it can be resent
+    pub fn release_event(&self, org_id: &str, agent_id: &str, event_id: &str) {
+        self.release(org_id, agent_id, Scope::Event, event_id)
+    }
+
+    fn seen_before(&self, org_id: &str, agent_id: &str, scope: Scope, id: &str) -> bool {
+        let key = SeenKey::new(org_id, agent_id, scope, id);
+        self.seen.read().expect("idempotency store lock poisoned").contains_key(&key)
+    }
+
+    fn release(&self, org_id: &str, agent_id: &str, scope: Scope, id: &str) {
+        let key = SeenKey::new(org_id, agent_id, scope, id);
+        self.seen.write().expect("idempotency store lock poisoned").remove(&key);
+    }
+
+    fn claim(&self, org_id: &str, agent_id: &str, scope: Scope, id: &str, now: DateTime<Utc>) -> bool {
+        let key = SeenKey::new(org_id, agent_id, scope, id);
+        let mut seen = self.seen.write().expect("idempotency store lock poisoned");
+        if seen.contains_key(&key) {
+            return false;
+        }
+        seen.insert(key, now);
+        true
+    }
+
+    /// Drops keys first seen before `cutoff`
+    pub fn prune(&self, cutoff: DateTime<Utc>) {
+        self.seen
+            .write()
+            .expect("idempotency store lock poisoned")
+            .retain(|_, seen_at| *seen_at >= cutoff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn keys_are_claimed_once_per_agent_and_scope() {
+        let store = IdempotencyStore::new();
+        let now = Utc::now();
+
+        assert!(!store.batch_seen("org", "a1", "key"));
+        assert!(store.claim_batch("org", "a1", "key", now));
+        assert!(store.batch_seen("org", "a1", "key"));
+        assert!(!store.claim_batch("org", "a1", "key", now));
+
+        // The same ID in another scope, agent or org is unrelated
+        assert!(!store.event_seen("org", "a1", "key"));
+        assert!(store.claim_event("org", "a1", "key", now));
+        assert!(store.claim_batch("org", "a2", "key", now));
+        assert!(store.claim_batch("other", "a1", "key", now));
+    }
+
+    #[test]
+    fn peeking_does_not_claim() {
+        let store = IdempotencyStore::new();
+        assert!(!store.event_seen("org", "a1", "event"));
+        assert!(!store.event_seen("org", "a1", "event"));
+        assert!(store.claim_event("org", "a1", "event", Utc::now()));
+    }
+
+    #[test]
+    fn released_keys_can_be_claimed_again() {
+        let store = IdempotencyStore::new();
+        let now = Utc::now();
+        assert!(store.claim_batch("org", "a1", "key", now));
+        assert!(store.claim_event("org", "a1", "key", now));
+
+        store.release_batch("org", "a1", "key");
+        assert!(!store.batch_seen("org", "a1", "key"));
+        assert!(store.event_seen("org", "a1", "key"), "only the released scope is forgotten");
+        assert!(store.claim_batch("org", "a1", "key", now));
+
+        store.release_event("org", "a1", "key");
+        assert!(store.claim_event("org", "a1", "key", now));
+    }
+
+    #[test]
+    fn keys_older_than_the_cutoff_are_pruned() {
+        let store = IdempotencyStore::new();
+        let now = Utc::now();
+        store.claim_batch("org", "a1", "old", now - Duration::hours(25));
+        store.claim_batch("org", "a1", "recent", now - Duration::hours(1));
+
+        store.prune(now - Duration::hours(24));
+
+        assert!(!store.batch_seen("org", "a1", "old"));
+        assert!(store.batch_seen("org", "a1", "recent"));
+        assert!(store.claim_batch("org", "a1", "old", now));
+    }
+}
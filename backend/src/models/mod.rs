@@ -1 +1,12 @@
-pub mod organization;
\ No newline at end of file
+pub mod agent;
+pub mod agent_config;
+pub mod api_key;
+pub mod clock;
+pub mod enrollment;
+pub mod event_schema;
+pub mod health;
+pub mod idempotency;
+pub mod organization;
+pub mod quota;
+pub mod signature;
+pub mod version_policy;
@@ -0,0 +1,386 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc, RwLock,
+    },
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+const MINUTE_SECS: i64 = 60;
+const DAY_SECS: i64 = 86400;
+
+/// Ingest limits; `None` leaves that dimension unlimited
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct QuotaLimits {
+    pub agent_events_per_minute: Option<u64>,
+    pub agent_bytes_per_day: Option<u64>,
+    pub org_events_per_minute: Option<u64>,
+    pub org_bytes_per_day: Option<u64>,
+}
+
+impl QuotaLimits {
+    /// Reads `QUOTA_AGENT_EVENTS_PER_MINUTE`, `QUOTA_AGENT_BYTES_PER_DAY`,
+    /// `QUOTA_ORG_EVENTS_PER_MINUTE` and `QUOTA_ORG_BYTES_PER_DAY`; unset means unlimited
+    pub fn from_env() -> Self {
+        let limit = |name: &str| {
+            std::env::var(name).ok().map(|value| {
+                value
+                    .parse::<u64>()
+                    .unwrap_or_else(|_| panic!("{} must be a valid number", name))
+            })
+        };
+
+        Self {
+            agent_events_per_minute: limit("QUOTA_AGENT_EVENTS_PER_MINUTE"),
+            agent_bytes_per_day: limit("QUOTA_AGENT_BYTES_PER_DAY"),
+            org_events_per_minute: limit("QUOTA_ORG_EVENTS_PER_MINUTE"),
+            org_bytes_per_day: limit("QUOTA_ORG_BYTES_PER_DAY"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaKind {
+    AgentEventsPerMinute,
+    AgentBytesPerDay,
+    OrgEventsPerMinute,
+    OrgBytesPerDay,
+}
+
+impl fmt::Display for QuotaKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            QuotaKind::AgentEventsPerMinute => "agent events per minute",
+            QuotaKind::AgentBytesPerDay => "agent bytes per day",
+            QuotaKind::OrgEventsPerMinute => "org events per minute",
+            QuotaKind::OrgBytesPerDay => "org bytes per day",
+        };
+        f.write_str(s)
+    }
+}
+
+/// State of one quota, as reported in `X-RateLimit-*` headers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub kind: QuotaKind,
+    pub limit: u64,
+    pub remaining: u64,
+    /// Seconds until the window resets
+    pub reset_secs: u64,
+}
+
+/// Why a charge was refused
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaRefusal {
+    /// The window has no room left for the request; it fits once the window resets
+    Exceeded(RateLimit),
+    /// The request alone is larger than the limit, so it never fits
+    TooLarge(RateLimit),
+}
+
+impl QuotaRefusal {
+    pub fn rate_limit(&self) -> RateLimit {
+        match self {
+            QuotaRefusal::Exceeded(rate_limit) | QuotaRefusal::TooLarge(rate_limit) => *rate_limit,
+        }
+    }
+}
+
+/// A count over a fixed window, identified by its index since the epoch
+#[derive(Debug, Clone, Copy, Default)]
+struct Window {
+    index: i64,
+    count: u64,
+}
+
+impl Window {
+    fn current(&self, index: i64) -> u64 {
+        if self.index == index { self.count } else { 0 }
+    }
+
+    fn add(&mut self, index: i64, amount: u64) {
+        if self.index != index {
+            *self = Window { index, count: 0 };
+        }
+        self.count += amount;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Counters {
+    events_minute: Window,
+    events_day: Window,
+    bytes_day: Window,
+    throttled_day: Window,
+}
+
+/// Usage of the current windows
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct Usage {
+    pub events_this_minute: u64,
+    pub events_today: u64,
+    pub bytes_today: u64,
+    /// Events refused today for exceeding a quota
+    pub throttled_today: u64,
+}
+
+impl Counters {
+    fn usage(&self, now: DateTime<Utc>) -> Usage {
+        let (minute, day) = window_indexes(now);
+        Usage {
+            events_this_minute: self.events_minute.current(minute),
+            events_today: self.events_day.current(day),
+            bytes_today: self.bytes_day.current(day),
+            throttled_today: self.throttled_day.current(day),
+        }
+    }
+
+    /// Whether every window ended before `day`; the minute window always ends first
+    fn is_expired(&self, day: i64) -> bool {
+        [self.events_day, self.bytes_day, self.throttled_day]
+            .iter()
+            .all(|window| window.index < day)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AgentUsage {
+    pub agent_id: String,
+    #[serde(flatten)]
+    pub usage: Usage,
+}
+
+#[derive(Default)]
+struct OrgQuotas {
+    limits: Option<QuotaLimits>,
+    counters: Counters,
+    agents: HashMap<String, Counters>,
+}
+
+/// Drops agents with no usage today, and orgs with neither usage nor a limits override
+fn prune(orgs: &mut HashMap<String, OrgQuotas>, day: i64) {
+    orgs.retain(|_, org| {
+        org.agents.retain(|_, counters| !counters.is_expired(day));
+        org.limits.is_some() || !org.agents.is_empty() || !org.counters.is_expired(day)
+    });
+}
+
+fn window_indexes(now: DateTime<Utc>) -> (i64, i64) {
+    let secs = now.timestamp();
+    (secs.div_euclid(MINUTE_SECS), secs.div_euclid(DAY_SECS))
+}
+
+fn reset_secs(now: DateTime<Utc>, window_secs: i64) -> u64 {
+    (window_secs - now.timestamp().rem_euclid(window_secs)) as u64
+}
+
+/// Fixed-window ingest quotas and usage counters per org and agent
+#[derive(Clone, Default)]
+pub struct QuotaStore {
+    defaults: QuotaLimits,
+    orgs: Arc<RwLock<HashMap<String, OrgQuotas>>>,
+    /// Day index of the last charge, to prune counters when the day rolls over
+    day: Arc<AtomicI64>,
+}
+
+impl QuotaStore {
+    pub fn new(defaults: QuotaLimits) -> Self {
+        Self { defaults, ..Self::default() }
+    }
+
+    /// Limits in effect for the org: its override, or the server defaults
+    pub fn limits(&self, org_id: &str) -> QuotaLimits {
+        let orgs = self.orgs.read().expect("quota store lock poisoned");
+        orgs.get(org_id)
+            .and_then(|org| org.limits.clone())
+            .unwrap_or_else(|| self.defaults.clone())
+    }
+
+    pub fn set_limits(&self, org_id: &str, limits: QuotaLimits) -> QuotaLimits {
+        let mut orgs = self.orgs.write().expect("quota store lock poisoned");
+        orgs.entry(org_id.to_string()).or_default().limits = Some(limits.clone());
+        limits
+    }
+
+    /// Charges `events` and `bytes` against every quota of the agent and its org.
+    ///
+    /// Nothing is charged when any quota would be exceeded, and a request larger than
+    /// a limit on its own is refused as [`QuotaRefusal::TooLarge`] since no window
+    /// could ever take it. On success returns the quota closest to exhaustion, if any
+    /// is configured.
+    pub fn try_consume(
+        &self,
+        org_id: &str,
+        agent_id: &str,
+        events: u64,
+        bytes: u64,
+        now: DateTime<Utc>,
+    ) -> Result<Option<RateLimit>, QuotaRefusal> {
+        let (minute, day) = window_indexes(now);
+        let mut orgs = self.orgs.write().expect("quota store lock poisoned");
+        if self.day.swap(day, Ordering::Relaxed) != day {
+            prune(&mut orgs, day);
+        }
+        let org = orgs.entry(org_id.to_string()).or_default();
+        let limits = org.limits.clone().unwrap_or_else(|| self.defaults.clone());
+        let agent = org.agents.entry(agent_id.to_string()).or_default();
+
+        let checks = [
+            (QuotaKind::AgentEventsPerMinute, limits.agent_events_per_minute, agent.events_minute.current(minute), events, MINUTE_SECS),
+            (QuotaKind::AgentBytesPerDay, limits.agent_bytes_per_day, agent.bytes_day.current(day), bytes, DAY_SECS),
+            (QuotaKind::OrgEventsPerMinute, limits.org_events_per_minute, org.counters.events_minute.current(minute), events, MINUTE_SECS),
+            (QuotaKind::OrgBytesPerDay, limits.org_bytes_per_day, org.counters.bytes_day.current(day), bytes, DAY_SECS),
+        ];
+
+        let mut tightest: Option<RateLimit> = None;
+        for (kind, limit, used, amount, window_secs) in checks {
+            let Some(limit) = limit else {
+                continue;
+            };
+            let rate_limit = RateLimit {
+                kind,
+                limit,
+                remaining: limit.saturating_sub(used),
+                reset_secs: reset_secs(now, window_secs),
+            };
+            if used.saturating_add(amount) > limit {
+                agent.throttled_day.add(day, events);
+                org.counters.throttled_day.add(day, events);
+                // A limit of 0 blocks ingest outright rather than making every request too large
+                return Err(if limit > 0 && amount > limit {
+                    QuotaRefusal::TooLarge(rate_limit)
+                } else {
+                    QuotaRefusal::Exceeded(rate_limit)
+                });
+            }
+            let remaining = limit - used - amount;
+            let ratio = |r: &RateLimit| r.remaining as f64 / r.limit.max(1) as f64;
+            let after = RateLimit { remaining, ..rate_limit };
+            if tightest.as_ref().is_none_or(|t| ratio(&after) < ratio(t)) {
+                tightest = Some(after);
+            }
+        }
+
+        for counters in [agent, &mut org.counters] {
+            counters.events_minute.add(minute, events);
+            counters.events_day.add(day, events);
+            counters.bytes_day.add(day, bytes);
+        }
+        Ok(tightest)
+    }
+
+    pub fn org_usage(&self, org_id: &str, now: DateTime<Utc>) -> (Usage, Vec<AgentUsage>) {
+        let orgs = self.orgs.read().expect("quota store lock poisoned");
+        let Some(org) = orgs.get(org_id) else {
+            return (Usage::default(), Vec::new());
+        };
+        let mut agents: Vec<AgentUsage> = org.agents
+            .iter()
+            .map(|(agent_id, counters)| AgentUsage { agent_id: agent_id.clone(), usage: counters.usage(now) })
+            .collect();
+        agents.sort_by(|a, b| b.usage.bytes_today.cmp(&a.usage.bytes_today).then_with(|| a.agent_id.cmp(&b.agent_id)));
+        (org.counters.usage(now), agents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn start_of_day() -> DateTime<Utc> {
+        DateTime::from_timestamp(20_000 * DAY_SECS, 0).unwrap()
+    }
+
+    fn per_minute(agent: u64, org: u64) -> QuotaLimits {
+        QuotaLimits {
+            agent_events_per_minute: Some(agent),
+            org_events_per_minute: Some(org),
+            ..QuotaLimits::default()
+        }
+    }
+
+    #[test]
+    fn events_are_limited_per_minute_window() {
+        let store = QuotaStore::new(per_minute(10, 100));
+        let now = start_of_day();
+
+        let tightest = store.try_consume("org", "a1", 6, 0, now).unwrap().unwrap();
+        assert_eq!((tightest.kind, tightest.remaining, tightest.reset_secs), (QuotaKind::AgentEventsPerMinute, 4, 60));
+        let QuotaRefusal::Exceeded(refused) = store.try_consume("org", "a1", 5, 0, now + Duration::seconds(30)).unwrap_err() else {
+            panic!("expected the window to be exhausted");
+        };
+        assert_eq!((refused.kind, refused.remaining, refused.reset_secs), (QuotaKind::AgentEventsPerMinute, 4, 30));
+        assert!(store.try_consume("org", "a2", 5, 0, now).is_ok());
+
+        // A new window starts empty
+        assert!(store.try_consume("org", "a1", 10, 0, now + Duration::minutes(1)).is_ok());
+
+        let (org, agents) = store.org_usage("org", now + Duration::minutes(1));
+        assert_eq!((org.events_this_minute, org.events_today, org.throttled_today), (10, 21, 5));
+        assert_eq!(agents.len(), 2);
+    }
+
+    #[test]
+    fn requests_larger_than_a_limit_are_refused_even_in_an_empty_window() {
+        let store = QuotaStore::new(QuotaLimits { agent_bytes_per_day: Some(1024), ..per_minute(10, 100) });
+        let now = start_of_day();
+
+        let refused = store.try_consume("org", "a1", 50, 0, now).unwrap_err();
+        assert!(matches!(refused, QuotaRefusal::TooLarge(RateLimit { kind: QuotaKind::AgentEventsPerMinute, limit: 10, .. })));
+        let refused = store.try_consume("org", "a1", 1, 1025, now).unwrap_err();
+        assert!(matches!(refused, QuotaRefusal::TooLarge(RateLimit { kind: QuotaKind::AgentBytesPerDay, .. })));
+        assert!(store.try_consume("org", "a1", 10, 1024, now).is_ok());
+
+        let (org, _) = store.org_usage("org", now);
+        assert_eq!((org.events_today, org.bytes_today, org.throttled_today), (10, 1024, 51));
+    }
+
+    #[test]
+    fn a_zero_limit_blocks_ingest_as_exceeded() {
+        let store = QuotaStore::new(per_minute(0, 100));
+        let refused = store.try_consume("org", "a1", 1, 0, start_of_day()).unwrap_err();
+        assert!(matches!(refused, QuotaRefusal::Exceeded(_)));
+    }
+
+    #[test]
+    fn org_quota_covers_all_agents() {
+        let store = QuotaStore::new(per_minute(10, 15));
+        let now = start_of_day();
+        assert!(store.try_consume("org", "a1", 10, 0, now).is_ok());
+        let refused = store.try_consume("org", "a2", 10, 0, now).unwrap_err();
+        assert_eq!(refused, QuotaRefusal::Exceeded(RateLimit {
+            kind: QuotaKind::OrgEventsPerMinute,
+            limit: 15,
+            remaining: 5,
+            reset_secs: 60,
+        }));
+
+        store.set_limits("org", per_minute(10, 100));
+        assert!(store.try_consume("org", "a2", 10, 0, now).is_ok());
+        assert!(store.try_consume("other", "a1", 10, 0, now).is_ok());
+    }
+
+    #[test]
+    fn counters_idle_since_yesterday_are_pruned_on_rollover() {
+        let store = QuotaStore::new(QuotaLimits::default());
+        let today = start_of_day();
+        store.set_limits("custom", QuotaLimits::default());
+        store.try_consume("custom", "a1", 1, 10, today).unwrap();
+        store.try_consume("idle", "a1", 1, 10, today).unwrap();
+        store.try_consume("busy", "a1", 1, 10, today).unwrap();
+        store.try_consume("busy", "a2", 1, 10, today).unwrap();
+
+        store.try_consume("busy", "a2", 1, 10, today + Duration::days(1)).unwrap();
+
+        let orgs = store.orgs.read().unwrap();
+        assert!(!orgs.contains_key("idle"));
+        assert!(orgs["custom"].agents.is_empty());
+        assert_eq!(orgs["busy"].agents.keys().collect::<Vec<_>>(), ["a2"]);
+    }
+}
@@ -0,0 +1,116 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+use chrono::{DateTime, Duration, Utc};
+
+/// How signed agent requests are checked
+#[derive(Debug, Clone, Copy)]
+pub struct SignatureSettings {
+    /// Largest accepted distance between `X-Signature-Timestamp` and server time
+    pub max_skew: Duration,
+    /// Refuse requests that authenticate with a bare `X-API-Key`
+    pub required: bool,
+}
+
+impl Default for SignatureSettings {
+    fn default() -> Self {
+        Self { max_skew: Duration::minutes(5), required: false }
+    }
+}
+
+impl SignatureSettings {
+    /// Reads `SIGNATURE_MAX_SKEW_SECS` (default 300) and `REQUIRE_SIGNED_REQUESTS` (default false)
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let max_skew = match std::env::var("SIGNATURE_MAX_SKEW_SECS") {
+            Ok(value) => Duration::seconds(
+                value
+                    .parse::<u32>()
+                    .unwrap_or_else(|_| panic!("SIGNATURE_MAX_SKEW_SECS must be a valid number of seconds"))
+                    .into(),
+            ),
+            Err(_) => defaults.max_skew,
+        };
+        let required = match std::env::var("REQUIRE_SIGNED_REQUESTS").as_deref() {
+            Err(_) | Ok("false") | Ok("0") => false,
+            Ok("true") | Ok("1") => true,
+            Ok(other) => panic!("REQUIRE_SIGNED_REQUESTS must be 'true' or 'false', got '{}'", other),
+        };
+
+        Self { max_skew, required }
+    }
+
+    /// Whether a signature timestamp is close enough to `now`
+    pub fn is_fresh(&self, timestamp: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        (now - timestamp).abs() <= self.max_skew
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct SeenNonce {
+    key_id: String,
+    nonce: String,
+}
+
+/// Nonces of signed requests, per key, kept until their timestamp would be stale anyway
+#[derive(Clone, Default)]
+pub struct NonceStore {
+    seen: Arc<RwLock<HashMap<SeenNonce, DateTime<Utc>>>>,
+}
+
+impl NonceStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a nonce until `expires_at`; returns `false` when the key already used it
+    pub fn claim(&self, key_id: &str, nonce: &str, expires_at: DateTime<Utc>) -> bool {
+        let mut seen = self.seen.write().expect("nonce store lock poisoned");
+        let key = SeenNonce { key_id: key_id.to_string(), nonce: nonce.to_string() };
+        if seen.contains_key(&key) {
+            return false;
+        }
+        seen.insert(key, expires_at);
+        true
+    }
+
+    /// Drops nonces whose requests could no longer pass the timestamp check
+    pub fn prune(&self, now: DateTime<Utc>) {
+        self.seen
+            .write()
+            .expect("nonce store lock poisoned")
+            .retain(|_, expires_at| *expires_at > now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nonces_are_single_use_per_key_until_they_expire() {
+        let store = NonceStore::new();
+        let now = Utc::now();
+        let expires_at = now + Duration::minutes(5);
+
+        assert!(store.claim("key_1", "n1", expires_at));
+        assert!(!store.claim("key_1", "n1", expires_at));
+        assert!(store.claim("key_2", "n1", expires_at));
+
+        store.prune(now + Duration::minutes(4));
+        assert!(!store.claim("key_1", "n1", expires_at));
+        store.prune(expires_at);
+        assert!(store.claim("key_1", "n1", expires_at));
+    }
+
+    #[test]
+    fn timestamps_are_fresh_within_the_skew_either_way() {
+        let settings = SignatureSettings::default();
+        let now = Utc::now();
+        assert!(settings.is_fresh(now - Duration::minutes(5), now));
+        assert!(settings.is_fresh(now + Duration::minutes(5), now));
+        assert!(!settings.is_fresh(now - Duration::minutes(5) - Duration::seconds(1), now));
+        assert!(!settings.is_fresh(now + Duration::minutes(6), now));
+    }
+}
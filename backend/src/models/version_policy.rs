@@ -0,0 +1,220 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+};
+use chrono::{DateTime, Utc};
+use semver::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+pub use anticheat_protocol::agent::VersionStatus;
+
+#[derive(Debug, thiserror::Error)]
+pub enum VersionPolicyError {
+    #[error("invalid version '{0}'")]
+    InvalidVersion(String),
+    #[error("invalid version range '{0}'")]
+    InvalidRange(String),
+}
+
+/// What happens to ingest from agents running a blocked version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BlockedAction {
+    /// Accept the data but mark the agent as blocked
+    #[default]
+    Flag,
+    /// Refuse the batch with `AGENT_VERSION_BLOCKED`
+    Reject,
+}
+
+/// Org-level rules for which agent versions are supported
+#[derive(Debug, Clone, Default, Deserialize, Serialize, Validate, ToSchema)]
+pub struct VersionPolicy {
+    /// Agents below this version are reported as outdated
+    pub minimum_version: Option<String>,
+
+    /// Semver requirement agents should satisfy, e.g. `>=1.2, <3`
+    pub supported_range: Option<String>,
+
+    /// Exact versions that are blocked
+    #[validate(length(max = 100, message = "At most 100 blocked versions are allowed"))]
+    #[serde(default)]
+    pub blocked_versions: Vec<String>,
+
+    /// Semver requirements whose matches are blocked, e.g. `>=1.4.0, <1.4.3`
+    #[validate(length(max = 100, message = "At most 100 blocked ranges are allowed"))]
+    #[serde(default)]
+    pub blocked_ranges: Vec<String>,
+
+    #[serde(default)]
+    pub blocked_action: BlockedAction,
+
+    #[serde(skip_deserializing)]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Parses `1`, `1.2` and `v1.2.3` as well as strict semver
+pub fn parse_version(version: &str) -> Option<Version> {
+    let version = version.trim().trim_start_matches('v');
+    Version::parse(version).ok().or_else(|| {
+        let dots = version.matches('.').count();
+        let padded = match dots {
+            0 => format!("{}.0.0", version),
+            1 => format!("{}.0", version),
+            _ => return None,
+        };
+        Version::parse(&padded).ok()
+    })
+}
+
+/// A policy with its version strings parsed, ready for evaluation
+#[derive(Debug, Clone)]
+struct CompiledPolicy {
+    policy: VersionPolicy,
+    minimum_version: Option<Version>,
+    supported_range: Option<VersionReq>,
+    blocked_versions: Vec<Version>,
+    blocked_ranges: Vec<VersionReq>,
+}
+
+impl CompiledPolicy {
+    fn compile(policy: VersionPolicy) -> Result<Self, VersionPolicyError> {
+        let version = |v: &String| parse_version(v).ok_or_else(|| VersionPolicyError::InvalidVersion(v.clone()));
+        let range = |r: &String| VersionReq::parse(r).map_err(|_| VersionPolicyError::InvalidRange(r.clone()));
+
+        Ok(Self {
+            minimum_version: policy.minimum_version.as_ref().map(version).transpose()?,
+            supported_range: policy.supported_range.as_ref().map(range).transpose()?,
+            blocked_versions: policy.blocked_versions.iter().map(version).collect::<Result<_, _>>()?,
+            blocked_ranges: policy.blocked_ranges.iter().map(range).collect::<Result<_, _>>()?,
+            policy,
+        })
+    }
+
+    fn evaluate(&self, version: &str) -> VersionStatus {
+        let Some(version) = parse_version(version) else {
+            return VersionStatus::Unknown;
+        };
+
+        if self.blocked_versions.contains(&version)
+            || self.blocked_ranges.iter().any(|req| req.matches(&version))
+        {
+            return VersionStatus::Blocked;
+        }
+        if self.minimum_version.as_ref().is_some_and(|min| version < *min)
+            || self.supported_range.as_ref().is_some_and(|req| !req.matches(&version))
+        {
+            return VersionStatus::Outdated;
+        }
+        VersionStatus::Supported
+    }
+}
+
+/// Version policies keyed by org; orgs without a policy support every version
+#[derive(Clone, Default)]
+pub struct VersionPolicyStore {
+    policies: Arc<RwLock<HashMap<String, CompiledPolicy>>>,
+}
+
+impl VersionPolicyStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, org_id: &str) -> VersionPolicy {
+        let policies = self.policies.read().expect("version policy lock poisoned");
+        policies.get(org_id).map(|c| c.policy.clone()).unwrap_or_default()
+    }
+
+    /// Validates and replaces the org policy
+    pub fn set(&self, org_id: &str, mut policy: VersionPolicy, now: DateTime<Utc>) -> Result<VersionPolicy, VersionPolicyError> {
+        policy.updated_at = Some(now);
+        let compiled = CompiledPolicy::compile(policy)?;
+        let policy = compiled.policy.clone();
+        self.policies
+            .write()
+            .expect("version policy lock poisoned")
+            .insert(org_id.to_string(), compiled);
+        Ok(policy)
+    }
+
+    pub fn evaluate(&self, org_id: &str, version: &str) -> VersionStatus {
+        let policies = self.policies.read().expect("version policy lock poisoned");
+        match policies.get(org_id) {
+            Some(compiled) => compiled.evaluate(version),
+            None if parse_version(version).is_none() => VersionStatus::Unknown,
+            None => VersionStatus::Supported,
+        }
+    }
+
+    /// True when the org rejects ingest from agents running `version`
+    pub fn rejects(&self, org_id: &str, version: &str) -> bool {
+        let policies = self.policies.read().expect("version policy lock poisoned");
+        policies.get(org_id).is_some_and(|compiled| {
+            compiled.policy.blocked_action == BlockedAction::Reject
+                && compiled.evaluate(version) == VersionStatus::Blocked
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(blocked_action: BlockedAction) -> VersionPolicy {
+        VersionPolicy {
+            minimum_version: Some("1.2".to_string()),
+            supported_range: Some("<3".to_string()),
+            blocked_versions: vec!["2.0.1".to_string()],
+            blocked_ranges: vec![">=1.4.0, <1.4.3".to_string()],
+            blocked_action,
+            updated_at: None,
+        }
+    }
+
+    #[test]
+    fn loose_versions_are_parsed() {
+        assert_eq!(parse_version("v1.2.3"), Some(Version::new(1, 2, 3)));
+        assert_eq!(parse_version("1.2"), Some(Version::new(1, 2, 0)));
+        assert_eq!(parse_version("2"), Some(Version::new(2, 0, 0)));
+        assert_eq!(parse_version("1.2.3.4"), None);
+        assert_eq!(parse_version("latest"), None);
+    }
+
+    #[test]
+    fn blocked_versions_take_precedence_over_support() {
+        let store = VersionPolicyStore::new();
+        store.set("org", policy(BlockedAction::Flag), Utc::now()).unwrap();
+
+        assert_eq!(store.evaluate("org", "2.0.1"), VersionStatus::Blocked);
+        assert_eq!(store.evaluate("org", "1.4.2"), VersionStatus::Blocked);
+        assert_eq!(store.evaluate("org", "1.4.3"), VersionStatus::Supported);
+        assert_eq!(store.evaluate("org", "1.1"), VersionStatus::Outdated);
+        assert_eq!(store.evaluate("org", "3.0.0"), VersionStatus::Outdated);
+        assert_eq!(store.evaluate("org", "nightly"), VersionStatus::Unknown);
+        assert_eq!(store.evaluate("other", "0.1.0"), VersionStatus::Supported);
+    }
+
+    #[test]
+    fn only_the_reject_action_refuses_ingest() {
+        let store = VersionPolicyStore::new();
+        store.set("flag", policy(BlockedAction::Flag), Utc::now()).unwrap();
+        store.set("reject", policy(BlockedAction::Reject), Utc::now()).unwrap();
+
+        assert!(!store.rejects("flag", "2.0.1"));
+        assert!(store.rejects("reject", "2.0.1"));
+        assert!(!store.rejects("reject", "1.1.0"), "outdated versions are still accepted");
+        assert!(!store.rejects("other", "2.0.1"));
+    }
+
+    #[test]
+    fn invalid_policies_are_refused() {
+        let store = VersionPolicyStore::new();
+        let mut invalid = policy(BlockedAction::Reject);
+        invalid.blocked_ranges = vec!["not a range".to_string()];
+        assert!(matches!(store.set("org", invalid, Utc::now()), Err(VersionPolicyError::InvalidRange(_))));
+        assert!(!store.rejects("org", "2.0.1"), "the previous policy is kept");
+    }
+}
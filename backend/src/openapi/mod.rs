@@ -6,9 +6,30 @@ use utoipa::OpenApi;
     paths(
         crate::handlers::auth::login,
         crate::handlers::ingest::batch_ingest,
+        crate::handlers::ingest::stream_ingest,
+        crate::handlers::event_types::list_event_types,
+        crate::handlers::event_types::get_event_type,
+        crate::handlers::event_types::put_event_type,
+        crate::handlers::event_types::delete_event_type,
+        crate::handlers::event_types::get_event_type_policy,
+        crate::handlers::event_types::put_event_type_policy,
+        crate::handlers::usage::get_usage,
+        crate::handlers::usage::get_quotas,
+        crate::handlers::usage::put_quotas,
         crate::handlers::dashboard_api::list_detections,
         crate::handlers::dashboard_api::list_agents,
         crate::handlers::dashboard_api::list_alerts,
+        crate::handlers::agents::agent_health,
+        crate::handlers::agents::agent_versions,
+        crate::handlers::agents::get_version_policy,
+        crate::handlers::agents::put_version_policy,
+        crate::handlers::agent_config::create_profile,
+        crate::handlers::agent_config::list_profiles,
+        crate::handlers::agent_config::update_profile,
+        crate::handlers::agent_config::assign_profile,
+        crate::handlers::agent_config::agent_config,
+        crate::handlers::enrollment::create_enrollment_token,
+        crate::handlers::enrollment::enroll_agent,
         crate::handlers::healthz,
         crate::handlers::version,
     ),
@@ -24,6 +45,20 @@ use utoipa::OpenApi;
             crate::handlers::ingest::DetectionEvent,
             crate::handlers::ingest::AgentHeartbeat,
             crate::handlers::ingest::IngestResponse,
+            crate::handlers::ingest::StreamIngestResponse,
+            crate::handlers::ingest::LineError,
+            
+            // Event type registry schemas
+            crate::handlers::event_types::PutEventTypeRequest,
+            crate::models::event_schema::EventTypeSchema,
+            crate::models::event_schema::EventTypePolicy,
+            crate::models::event_schema::UnknownEventTypes,
+            
+            // Quota schemas
+            crate::handlers::usage::UsageResponse,
+            crate::models::quota::QuotaLimits,
+            crate::models::quota::Usage,
+            crate::models::quota::AgentUsage,
             
             // Dashboard schemas
             crate::handlers::dashboard_api::PaginationParams,
@@ -34,6 +69,34 @@ use utoipa::OpenApi;
             crate::handlers::dashboard_api::AgentFilters,
             crate::handlers::dashboard_api::Alert,
             crate::handlers::dashboard_api::AlertFilters,
+            crate::handlers::dashboard_api::Severity,
+            crate::models::clock::TimestampFlag,
+            
+            // Agent health schemas
+            crate::handlers::agents::HealthQuery,
+            crate::handlers::agents::AgentHealthResponse,
+            crate::models::health::HealthPoint,
+            
+            // Version policy schemas
+            crate::handlers::agents::VersionCount,
+            crate::handlers::agents::AgentVersionsResponse,
+            crate::models::version_policy::VersionPolicy,
+            crate::models::version_policy::BlockedAction,
+            crate::models::version_policy::VersionStatus,
+            
+            // Agent configuration schemas
+            crate::handlers::agent_config::CreateConfigProfileRequest,
+            crate::handlers::agent_config::UpdateConfigProfileRequest,
+            crate::handlers::agent_config::AssignConfigProfileRequest,
+            crate::models::agent_config::AgentConfigBody,
+            crate::models::agent_config::ConfigProfile,
+            crate::models::agent_config::ResolvedConfig,
+            
+            // Enrollment schemas
+            crate::handlers::enrollment::CreateEnrollmentTokenRequest,
+            crate::handlers::enrollment::EnrollmentTokenResponse,
+            crate::handlers::enrollment::EnrollAgentRequest,
+            crate::handlers::enrollment::EnrollAgentResponse,
             
             // Common schemas
             crate::handlers::HealthzResponse,
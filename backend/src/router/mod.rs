@@ -1,9 +1,14 @@
 use axum::{routing::get, Router, middleware};
 use tower_http::trace::TraceLayer;
-use crate::{handlers, openapi, config::AppState, auth::{jwt::jwt_middleware, api_key::api_key_middleware}};
+use crate::{handlers, openapi, config::AppState, auth::{jwt::jwt_middleware, api_key::api_key_middleware, signature::signature_middleware}, middleware::rate_limit::{enrollment_rate_limit_middleware, ip_rate_limit_middleware}};
 
 pub fn create_router(app_state: AppState) -> Router {
     let api_routes = handlers::dashboard_api::routes()
+        .merge(handlers::agents::routes())
+        .merge(handlers::event_types::routes())
+        .merge(handlers::usage::routes())
+        .merge(handlers::agent_config::admin_routes())
+        .merge(handlers::enrollment::admin_routes())
         .layer(middleware::from_fn_with_state(
             app_state.clone(),
             jwt_middleware,
@@ -13,13 +18,52 @@ pub fn create_router(app_state: AppState) -> Router {
         .layer(middleware::from_fn_with_state(
             app_state.clone(),
             api_key_middleware,
+        ))
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            signature_middleware,
+        ));
+
+    let agent_routes = handlers::agent_config::agent_routes()
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            api_key_middleware,
+        ))
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            signature_middleware,
+        ));
+
+    // The socket is scoped to the org of the connecting agent key
+    let realtime_routes = handlers::realtime::routes()
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            api_key_middleware,
+        ))
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            signature_middleware,
+        ));
+
+    // Unauthenticated endpoints are throttled per client IP
+    let auth_routes = handlers::auth::routes()
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            ip_rate_limit_middleware,
+        ));
+
+    let enrollment_routes = handlers::enrollment::routes()
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            enrollment_rate_limit_middleware,
         ));
 
     Router::new()
-        .nest("/auth", handlers::auth::routes())
+        .nest("/auth", auth_routes)
         .nest("/v1", api_routes)
         .nest("/ingest", ingest_routes)
-        .nest("/realtime", handlers::realtime::routes())
+        .nest("/agents", enrollment_routes.merge(agent_routes))
+        .nest("/realtime", realtime_routes)
 
         .route("/healthz", get(handlers::healthz))
         .route("/version", get(handlers::version))
@@ -0,0 +1,54 @@
+use std::time::Duration as StdDuration;
+use chrono::{Duration, Utc};
+use tokio::task::JoinHandle;
+use crate::{config::AppState, handlers::realtime::RealtimeEvent, models::agent::StatusThresholds};
+use super::env_secs;
+
+#[derive(Debug, Clone, Copy)]
+pub struct AgentStatusSettings {
+    pub thresholds: StatusThresholds,
+    pub sweep_interval: StdDuration,
+}
+
+impl AgentStatusSettings {
+    /// Reads `AGENT_STALE_AFTER_SECS` (default 60), `AGENT_OFFLINE_AFTER_SECS` (default 300)
+    /// and `AGENT_STATUS_SWEEP_SECS` (default 15)
+    pub fn from_env() -> Self {
+        let stale_after = env_secs("AGENT_STALE_AFTER_SECS", 60);
+        let offline_after = env_secs("AGENT_OFFLINE_AFTER_SECS", 300);
+        let sweep_interval = env_secs("AGENT_STATUS_SWEEP_SECS", 15);
+
+        if offline_after <= stale_after {
+            panic!("AGENT_OFFLINE_AFTER_SECS must be greater than AGENT_STALE_AFTER_SECS");
+        }
+
+        Self {
+            thresholds: StatusThresholds {
+                stale_after: Duration::seconds(stale_after as i64),
+                offline_after: Duration::seconds(offline_after as i64),
+            },
+            sweep_interval: StdDuration::from_secs(sweep_interval.max(1)),
+        }
+    }
+}
+
+/// Periodically downgrades silent agents to stale/offline and publishes each transition
+pub fn spawn(app_state: AppState, settings: AgentStatusSettings) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(settings.sweep_interval);
+        loop {
+            interval.tick().await;
+
+            for change in app_state.agents.sweep(&settings.thresholds, Utc::now()) {
+                tracing::info!(
+                    "Agent status changed: agent_id={}, org_id={}, {:?} -> {}",
+                    change.agent_id,
+                    change.org_id,
+                    change.previous,
+                    change.current
+                );
+                app_state.publish(RealtimeEvent::AgentStatusChanged(change));
+            }
+        }
+    })
+}
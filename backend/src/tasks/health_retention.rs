@@ -0,0 +1,41 @@
+use std::time::Duration as StdDuration;
+use chrono::{Duration, Utc};
+use tokio::task::JoinHandle;
+use crate::config::AppState;
+use super::env_secs;
+
+#[derive(Debug, Clone, Copy)]
+pub struct HealthRetentionSettings {
+    pub raw_retention: Duration,
+    pub rollup_retention: Duration,
+    pub prune_interval: StdDuration,
+}
+
+impl HealthRetentionSettings {
+    /// Reads `HEALTH_RAW_RETENTION_SECS` (default 6h), `HEALTH_ROLLUP_RETENTION_SECS`
+    /// (default 7d) and `HEALTH_PRUNE_INTERVAL_SECS` (default 60)
+    pub fn from_env() -> Self {
+        let raw_retention = env_secs("HEALTH_RAW_RETENTION_SECS", 6 * 3600);
+        let rollup_retention = env_secs("HEALTH_ROLLUP_RETENTION_SECS", 7 * 86400);
+        let prune_interval = env_secs("HEALTH_PRUNE_INTERVAL_SECS", 60);
+
+        Self {
+            raw_retention: Duration::seconds(raw_retention as i64),
+            rollup_retention: Duration::seconds(rollup_retention as i64),
+            prune_interval: StdDuration::from_secs(prune_interval.max(1)),
+        }
+    }
+}
+
+/// Periodically expires raw heartbeat samples and old rollups
+pub fn spawn(app_state: AppState, settings: HealthRetentionSettings) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(settings.prune_interval);
+        loop {
+            interval.tick().await;
+
+            let now = Utc::now();
+            app_state.health.prune(now - settings.raw_retention, now - settings.rollup_retention);
+        }
+    })
+}
@@ -0,0 +1,37 @@
+use std::time::Duration as StdDuration;
+use chrono::{Duration, Utc};
+use tokio::task::JoinHandle;
+use crate::config::AppState;
+use super::env_secs;
+
+#[derive(Debug, Clone, Copy)]
+pub struct IdempotencyRetentionSettings {
+    /// How long a batch key or event ID is remembered; retries after that are stored again
+    pub retention: Duration,
+    pub prune_interval: StdDuration,
+}
+
+impl IdempotencyRetentionSettings {
+    /// Reads `IDEMPOTENCY_RETENTION_SECS` (default 24h) and `IDEMPOTENCY_PRUNE_INTERVAL_SECS` (default 60)
+    pub fn from_env() -> Self {
+        let retention = env_secs("IDEMPOTENCY_RETENTION_SECS", 86400);
+        let prune_interval = env_secs("IDEMPOTENCY_PRUNE_INTERVAL_SECS", 60);
+
+        Self {
+            retention: Duration::seconds(retention as i64),
+            prune_interval: StdDuration::from_secs(prune_interval.max(1)),
+        }
+    }
+}
+
+/// Periodically forgets idempotency keys older than the retention window
+pub fn spawn(app_state: AppState, settings: IdempotencyRetentionSettings) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(settings.prune_interval);
+        loop {
+            interval.tick().await;
+
+            app_state.idempotency.prune(Utc::now() - settings.retention);
+        }
+    })
+}
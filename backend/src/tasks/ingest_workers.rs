@@ -0,0 +1,210 @@
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use tokio::{
+    sync::{mpsc, Mutex},
+    task::JoinHandle,
+};
+use crate::{
+    config::AppState,
+    handlers::{ingest::DetectionEvent, realtime::{DetectionReceived, RealtimeEvent}},
+    models::clock::TimestampFlag,
+};
+use super::env_secs;
+
+/// One accepted event waiting for processing
+#[derive(Debug)]
+pub struct IngestJob {
+    pub org_id: String,
+    pub agent_id: String,
+    pub event: DetectionEvent,
+    /// Server clock when the event arrived
+    pub received_at: DateTime<Utc>,
+    /// Set when `detected_at` was out of bounds but the event was accepted anyway
+    pub timestamp_flag: Option<TimestampFlag>,
+}
+
+impl IngestJob {
+    pub fn new(
+        org_id: &str,
+        agent_id: &str,
+        event: DetectionEvent,
+        received_at: DateTime<Utc>,
+        timestamp_flag: Option<TimestampFlag>,
+    ) -> Self {
+        Self { org_id: org_id.to_string(), agent_id: agent_id.to_string(), event, received_at, timestamp_flag }
+    }
+}
+
+/// Bounded queue between the ingest handlers and the processing workers, counted in events
+#[derive(Clone)]
+pub struct IngestQueue {
+    sender: mpsc::Sender<IngestJob>,
+    retry_after_secs: u64,
+}
+
+/// Receiving end of an [`IngestQueue`], handed to [`spawn`]
+pub struct IngestReceiver(mpsc::Receiver<IngestJob>);
+
+/// Queue slots held for the events of one batch; unused slots are released on drop
+pub struct Reservation<'a>(mpsc::PermitIterator<'a, IngestJob>);
+
+impl IngestQueue {
+    pub fn new(settings: &IngestWorkerSettings) -> (Self, IngestReceiver) {
+        let (sender, receiver) = mpsc::channel(settings.queue_capacity);
+        (Self { sender, retry_after_secs: settings.retry_after_secs }, IngestReceiver(receiver))
+    }
+
+    /// Reserves room for `count` events at once, or `None` when the queue cannot take them all
+    pub fn try_reserve(&self, count: usize) -> Option<Reservation<'_>> {
+        match self.sender.try_reserve_many(count) {
+            Ok(permits) => Some(Reservation(permits)),
+            Err(_) => None,
+        }
+    }
+
+    /// Waits for room for one event; `None` once the workers have stopped
+    pub async fn reserve(&self) -> Option<mpsc::Permit<'_, IngestJob>> {
+        self.sender.reserve().await.ok()
+    }
+
+    /// Seconds clients are asked to wait after the queue was full
+    pub fn retry_after_secs(&self) -> u64 {
+        self.retry_after_secs
+    }
+
+    /// Events waiting for a worker
+    pub fn depth(&self) -> usize {
+        self.sender.max_capacity() - self.sender.capacity()
+    }
+}
+
+impl Reservation<'_> {
+    pub fn send(&mut self, job: IngestJob) {
+        self.0
+            .next()
+            .expect("more events sent than reserved")
+            .send(job);
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct IngestWorkerSettings {
+    pub workers: usize,
+    pub queue_capacity: usize,
+    pub retry_after_secs: u64,
+}
+
+impl IngestWorkerSettings {
+    /// Reads `INGEST_WORKERS` (default 4), `INGEST_QUEUE_CAPACITY` (events, default 10000)
+    /// and `INGEST_RETRY_AFTER_SECS` (default 1)
+    pub fn from_env() -> Self {
+        let workers = std::env::var("INGEST_WORKERS")
+            .unwrap_or_else(|_| "4".to_string())
+            .parse::<usize>()
+            .expect("INGEST_WORKERS must be a valid number");
+        let queue_capacity = std::env::var("INGEST_QUEUE_CAPACITY")
+            .unwrap_or_else(|_| "10000".to_string())
+            .parse::<usize>()
+            .expect("INGEST_QUEUE_CAPACITY must be a valid number");
+
+        Self {
+            workers: workers.max(1),
+            // A batch reserves all of its events at once, so the queue must hold a full batch
+            queue_capacity: queue_capacity.max(1000),
+            retry_after_secs: env_secs("INGEST_RETRY_AFTER_SECS", 1).max(1),
+        }
+    }
+}
+
+/// Starts the workers that store, evaluate and publish queued events
+pub fn spawn(app_state: AppState, receiver: IngestReceiver, settings: IngestWorkerSettings) -> Vec<JoinHandle<()>> {
+    let receiver = Arc::new(Mutex::new(receiver.0));
+    (0..settings.workers)
+        .map(|_| {
+            let app_state = app_state.clone();
+            let receiver = receiver.clone();
+            tokio::spawn(async move {
+                loop {
+                    let Some(job) = receiver.lock().await.recv().await else {
+                        return;
+                    };
+                    process(&app_state, job);
+                }
+            })
+        })
+        .collect()
+}
+
+fn process(app_state: &AppState, job: IngestJob) {
+    let IngestJob { org_id, agent_id, event, received_at, timestamp_flag } = job;
+
+    // TODO: In production:
+    // 1. Store event in database with org_id and agent_id
+    // 2. Evaluate alert rules against it
+    tracing::info!(
+        "Event processed: type={}, severity={}, org_id={}, agent_id={}, queued_for_ms={}",
+        event.event_type,
+        event.severity,
+        org_id,
+        agent_id,
+        (Utc::now() - received_at).num_milliseconds()
+    );
+
+    app_state.publish(RealtimeEvent::DetectionReceived(DetectionReceived {
+        org_id,
+        agent_id,
+        event_type: event.event_type,
+        severity: event.severity,
+        title: event.title,
+        detected_at: event.detected_at,
+        received_at,
+        timestamp_flag,
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use anticheat_protocol::Severity;
+    use super::*;
+
+    fn queue(queue_capacity: usize) -> (IngestQueue, IngestReceiver) {
+        IngestQueue::new(&IngestWorkerSettings { workers: 1, queue_capacity, retry_after_secs: 1 })
+    }
+
+    fn job() -> IngestJob {
+        IngestJob::new("org_1", "agent_1", DetectionEvent::new("speed_hack", Severity::High), Utc::now(), None)
+    }
+
+    #[test]
+    fn batches_are_reserved_all_or_nothing() {
+        let (queue, _receiver) = queue(2);
+        assert!(queue.try_reserve(3).is_none());
+
+        let reservation = queue.try_reserve(2).expect("room for two events");
+        assert_eq!(queue.depth(), 2);
+        assert!(queue.try_reserve(1).is_none());
+        drop(reservation);
+        assert_eq!(queue.depth(), 0, "dropping a reservation frees its slots");
+    }
+
+    #[test]
+    fn unused_slots_are_released_and_sent_jobs_kept() {
+        let (queue, mut receiver) = queue(3);
+        let mut reservation = queue.try_reserve(3).unwrap();
+        reservation.send(job());
+        drop(reservation);
+
+        assert_eq!(queue.depth(), 1);
+        assert!(queue.try_reserve(2).is_some());
+        assert_eq!(receiver.0.try_recv().unwrap().agent_id, "agent_1");
+        assert_eq!(queue.depth(), 0);
+    }
+
+    #[tokio::test]
+    async fn waiting_for_room_fails_once_the_workers_are_gone() {
+        let (queue, receiver) = queue(1);
+        assert!(queue.reserve().await.is_some());
+        drop(receiver);
+        assert!(queue.reserve().await.is_none());
+    }
+}
@@ -0,0 +1,13 @@
+pub mod agent_status;
+pub mod health_retention;
+pub mod idempotency_retention;
+pub mod ingest_workers;
+pub mod nonce_retention;
+
+/// Reads a duration in seconds from the environment, panicking on malformed values
+fn env_secs(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .unwrap_or_else(|_| default.to_string())
+        .parse()
+        .unwrap_or_else(|_| panic!("{} must be a valid number of seconds", name))
+}
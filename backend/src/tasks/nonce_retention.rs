@@ -0,0 +1,33 @@
+use std::time::Duration as StdDuration;
+use chrono::Utc;
+use tokio::task::JoinHandle;
+use crate::config::AppState;
+use super::env_secs;
+
+#[derive(Debug, Clone, Copy)]
+pub struct NonceRetentionSettings {
+    pub prune_interval: StdDuration,
+}
+
+impl NonceRetentionSettings {
+    /// Reads `NONCE_PRUNE_INTERVAL_SECS` (default 60)
+    pub fn from_env() -> Self {
+        let prune_interval = env_secs("NONCE_PRUNE_INTERVAL_SECS", 60);
+
+        Self {
+            prune_interval: StdDuration::from_secs(prune_interval.max(1)),
+        }
+    }
+}
+
+/// Periodically forgets request nonces whose signatures have gone stale
+pub fn spawn(app_state: AppState, settings: NonceRetentionSettings) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(settings.prune_interval);
+        loop {
+            interval.tick().await;
+
+            app_state.nonces.prune(Utc::now());
+        }
+    })
+}
@@ -0,0 +1,606 @@
+use std::{sync::Arc, time::Duration};
+use tokio::{
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
+    time::Instant,
+};
+use validator::Validate;
+use crate::{
+    client::AnticheatClient,
+    error::{Error, Result},
+    heartbeat::HeartbeatSource,
+    outbox::Outbox,
+    types::{AgentHeartbeat, DetectionEvent, IngestBatchRequest, IngestResponse},
+};
+
+/// The backend rejects batches larger than this
+pub const MAX_BATCH_SIZE: usize = 1000;
+
+const DEFAULT_BATCH_SIZE: usize = 500;
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(5);
+const DEFAULT_QUEUE_CAPACITY: usize = 10_000;
+const DEFAULT_REPLAY_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Longest server-requested `Retry-After` honoured before replaying the outbox
+const MAX_REPLAY_DELAY: Duration = Duration::from_secs(3600);
+
+/// Outcome of one flushed batch, handed to the report callback
+#[derive(Debug)]
+pub struct BatchReport {
+    pub events: Vec<DetectionEvent>,
+    pub result: Result<IngestResponse>,
+}
+
+impl BatchReport {
+    /// Events the server refused, with its reason. Whole-batch failures list every event.
+    pub fn failed_events(&self) -> Vec<(&DetectionEvent, String)> {
+        match &self.result {
+            Ok(response) => response
+                .event_errors()
+                .filter_map(|(index, message)| self.events.get(index).map(|e| (e, message.to_string())))
+                .collect(),
+            Err(error) => self.events.iter().map(|e| (e, error.to_string())).collect(),
+        }
+    }
+}
+
+type ReportCallback = Arc<dyn Fn(BatchReport) + Send + Sync>;
+
+enum Command {
+    Event(DetectionEvent),
+    Heartbeat(AgentHeartbeat),
+    Flush(oneshot::Sender<()>),
+    /// Flush and exit even while [`EventSender`] clones are still alive
+    Shutdown,
+}
+
+/// Handle to a background task that buffers events and sends them in batches.
+///
+/// A batch is flushed when it reaches `max_batch_size`, when its oldest event is
+/// `max_batch_age` old, or on [`Batcher::flush`]. The latest heartbeat is attached
+/// to every batch, and a heartbeat-only batch is sent when nothing else went out
+/// for `heartbeat_interval`. With an [`Outbox`], batches are written to disk first
+/// and a report is only produced once a batch is delivered or refused for good, or
+/// when the server rejects the agent's credentials; those events stay stored and are
+/// replayed, and reported again, until they are accepted. Undelivered events evicted
+/// once the outbox reaches its size cap are reported with [`Error::OutboxFull`].
+pub struct Batcher {
+    commands: mpsc::Sender<Command>,
+    task: JoinHandle<()>,
+}
+
+impl Batcher {
+    pub fn builder(client: AnticheatClient) -> BatcherBuilder {
+        BatcherBuilder {
+            client,
+            max_batch_size: DEFAULT_BATCH_SIZE,
+            max_batch_age: DEFAULT_MAX_AGE,
+            queue_capacity: DEFAULT_QUEUE_CAPACITY,
+            on_report: None,
+            outbox: None,
+            replay_interval: DEFAULT_REPLAY_INTERVAL,
+            heartbeat_source: None,
+            heartbeat_interval: DEFAULT_HEARTBEAT_INTERVAL,
+        }
+    }
+
+    /// Validates and queues an event, waiting for queue space if the buffer is full
+    pub async fn send(&self, event: DetectionEvent) -> Result<()> {
+        self.sender().send(event).await
+    }
+
+    /// Like [`Batcher::send`] but fails with [`Error::QueueFull`] instead of waiting
+    pub fn try_send(&self, event: DetectionEvent) -> Result<()> {
+        self.sender().try_send(event)
+    }
+
+    /// Cloneable handle for queueing events from other tasks
+    pub fn sender(&self) -> EventSender {
+        EventSender { commands: self.commands.clone() }
+    }
+
+    /// Replaces the heartbeat attached to subsequent batches; ignored when a [`HeartbeatSource`] is set
+    pub async fn set_heartbeat(&self, heartbeat: AgentHeartbeat) -> Result<()> {
+        heartbeat.validate()?;
+        self.commands
+            .send(Command::Heartbeat(heartbeat))
+            .await
+            .map_err(|_| Error::Closed)
+    }
+
+    /// Sends everything queued so far and waits for that attempt to finish.
+    ///
+    /// With an [`Outbox`] the events are on disk when this returns, but while delivery
+    /// is backing off after a retryable failure they are only sent, and reported, once
+    /// the retry is due.
+    pub async fn flush(&self) -> Result<()> {
+        let (done, wait) = oneshot::channel();
+        self.commands
+            .send(Command::Flush(done))
+            .await
+            .map_err(|_| Error::Closed)?;
+        wait.await.map_err(|_| Error::Closed)
+    }
+
+    /// Flushes pending events and stops the background task
+    pub async fn shutdown(self) -> Result<()> {
+        self.commands
+            .send(Command::Shutdown)
+            .await
+            .map_err(|_| Error::Closed)?;
+        let _ = self.task.await;
+        Ok(())
+    }
+}
+
+/// Queues events into a [`Batcher`]; fails with [`Error::Closed`] once it has shut down.
+///
+/// Events without an `event_id` get a random one, so a batch replayed from the
+/// outbox after a crash is deduplicated by the server.
+#[derive(Clone)]
+pub struct EventSender {
+    commands: mpsc::Sender<Command>,
+}
+
+impl EventSender {
+    pub async fn send(&self, event: DetectionEvent) -> Result<()> {
+        let event = with_event_id(event);
+        event.validate()?;
+        self.commands
+            .send(Command::Event(event))
+            .await
+            .map_err(|_| Error::Closed)
+    }
+
+    pub fn try_send(&self, event: DetectionEvent) -> Result<()> {
+        let event = with_event_id(event);
+        event.validate()?;
+        self.commands.try_send(Command::Event(event)).map_err(|e| match e {
+            mpsc::error::TrySendError::Full(_) => Error::QueueFull,
+            mpsc::error::TrySendError::Closed(_) => Error::Closed,
+        })
+    }
+}
+
+fn with_event_id(event: DetectionEvent) -> DetectionEvent {
+    match event.event_id {
+        Some(_) => event,
+        None => event.event_id(uuid::Uuid::new_v4().to_string()),
+    }
+}
+
+pub struct BatcherBuilder {
+    client: AnticheatClient,
+    max_batch_size: usize,
+    max_batch_age: Duration,
+    queue_capacity: usize,
+    on_report: Option<ReportCallback>,
+    outbox: Option<Outbox>,
+    replay_interval: Duration,
+    heartbeat_source: Option<Arc<HeartbeatSource>>,
+    heartbeat_interval: Duration,
+}
+
+impl BatcherBuilder {
+    /// Events per request, capped at [`MAX_BATCH_SIZE`] (default 500)
+    pub fn max_batch_size(mut self, size: usize) -> Self {
+        self.max_batch_size = size.clamp(1, MAX_BATCH_SIZE);
+        self
+    }
+
+    /// Longest time an event waits in the buffer (default 5s)
+    pub fn max_batch_age(mut self, age: Duration) -> Self {
+        self.max_batch_age = age;
+        self
+    }
+
+    /// Events that may be queued before `send` starts waiting (default 10000)
+    pub fn queue_capacity(mut self, capacity: usize) -> Self {
+        self.queue_capacity = capacity.max(1);
+        self
+    }
+
+    /// Called from the background task after every flush, including failed ones
+    pub fn on_report(mut self, callback: impl Fn(BatchReport) + Send + Sync + 'static) -> Self {
+        self.on_report = Some(Arc::new(callback));
+        self
+    }
+
+    /// Persists every batch to the outbox before sending, replaying it in order
+    /// whenever the backend is unreachable, failing with 5xx/429 or refusing the credentials
+    pub fn outbox(mut self, outbox: Outbox) -> Self {
+        self.outbox = Some(outbox);
+        self
+    }
+
+    /// Delay before retrying outbox delivery after a retryable failure without `Retry-After` (default 5s)
+    pub fn replay_interval(mut self, interval: Duration) -> Self {
+        self.replay_interval = interval;
+        self
+    }
+
+    /// Collects a fresh heartbeat for every batch instead of using [`Batcher::set_heartbeat`]
+    pub fn heartbeat_source(mut self, source: Arc<HeartbeatSource>) -> Self {
+        self.heartbeat_source = Some(source);
+        self
+    }
+
+    /// Longest time without any request before a heartbeat-only batch is sent (default 30s)
+    pub fn heartbeat_interval(mut self, interval: Duration) -> Self {
+        self.heartbeat_interval = interval.max(Duration::from_secs(1));
+        self
+    }
+
+    /// Starts the background task; must be called within a tokio runtime
+    pub fn spawn(self) -> Batcher {
+        let (commands, receiver) = mpsc::channel(self.queue_capacity);
+        let worker = Worker {
+            client: self.client,
+            max_batch_size: self.max_batch_size,
+            max_batch_age: self.max_batch_age,
+            on_report: self.on_report,
+            buffer: Vec::with_capacity(self.max_batch_size),
+            heartbeat: None,
+            deadline: None,
+            outbox: self.outbox,
+            replay_interval: self.replay_interval,
+            retry_at: None,
+            // Announce the agent straight away when heartbeats are collected automatically
+            heartbeat_at: self.heartbeat_source.as_ref().map(|_| Instant::now()),
+            heartbeat_source: self.heartbeat_source,
+            heartbeat_interval: self.heartbeat_interval,
+        };
+        let task = tokio::spawn(worker.run(receiver));
+        Batcher { commands, task }
+    }
+}
+
+struct Worker {
+    client: AnticheatClient,
+    max_batch_size: usize,
+    max_batch_age: Duration,
+    on_report: Option<ReportCallback>,
+    buffer: Vec<DetectionEvent>,
+    heartbeat: Option<AgentHeartbeat>,
+    /// When the oldest buffered event must be flushed
+    deadline: Option<Instant>,
+    outbox: Option<Outbox>,
+    replay_interval: Duration,
+    /// When delivery from the outbox is retried after a retryable failure
+    retry_at: Option<Instant>,
+    heartbeat_source: Option<Arc<HeartbeatSource>>,
+    heartbeat_interval: Duration,
+    /// When an idle heartbeat-only batch is due
+    heartbeat_at: Option<Instant>,
+}
+
+impl Worker {
+    async fn run(mut self, mut commands: mpsc::Receiver<Command>) {
+        loop {
+            let wakeup = [self.deadline, self.retry_at, self.heartbeat_at].into_iter().flatten().min();
+            let command = match wakeup {
+                Some(wakeup) => tokio::select! {
+                    command = commands.recv() => command,
+                    _ = tokio::time::sleep_until(wakeup) => {
+                        self.on_timer().await;
+                        continue;
+                    }
+                },
+                None => commands.recv().await,
+            };
+
+            match command {
+                Some(Command::Event(event)) => {
+                    if self.buffer.is_empty() {
+                        self.deadline = Some(Instant::now() + self.max_batch_age);
+                    }
+                    self.buffer.push(event);
+                    if self.buffer.len() >= self.max_batch_size {
+                        self.flush().await;
+                    }
+                }
+                Some(Command::Heartbeat(heartbeat)) => {
+                    self.heartbeat = Some(heartbeat);
+                    if self.heartbeat_at.is_none() {
+                        self.heartbeat_at = Some(Instant::now() + self.heartbeat_interval);
+                    }
+                }
+                Some(Command::Flush(done)) => {
+                    self.flush().await;
+                    let _ = done.send(());
+                }
+                Some(Command::Shutdown) | None => {
+                    self.flush().await;
+                    return;
+                }
+            }
+        }
+    }
+
+    async fn on_timer(&mut self) {
+        let now = Instant::now();
+        if self.deadline.is_some_and(|deadline| deadline <= now) {
+            self.flush().await;
+        } else if self.retry_at.is_some_and(|retry_at| retry_at <= now) {
+            self.retry_at = None;
+            self.drain_outbox().await;
+        } else if self.heartbeat_at.is_some_and(|heartbeat_at| heartbeat_at <= now) {
+            self.send_heartbeat().await;
+        }
+    }
+
+    /// Sends a heartbeat-only batch; these bypass the outbox since a stale heartbeat is worthless
+    async fn send_heartbeat(&mut self) {
+        if !self.buffer.is_empty() {
+            self.flush().await;
+            return;
+        }
+        let Some(heartbeat) = self.current_heartbeat() else {
+            self.heartbeat_at = None;
+            return;
+        };
+
+        let result = self.client.ingest_batch(&IngestBatchRequest::heartbeat_only(heartbeat)).await;
+        self.heartbeat_sent();
+        self.report(Vec::new(), result);
+    }
+
+    fn current_heartbeat(&self) -> Option<AgentHeartbeat> {
+        match &self.heartbeat_source {
+            Some(source) => Some(source.collect()),
+            None => self.heartbeat.clone(),
+        }
+    }
+
+    /// Any request carries a heartbeat, so the idle timer restarts after each one
+    fn heartbeat_sent(&mut self) {
+        if self.heartbeat_at.is_some() {
+            self.heartbeat_at = Some(Instant::now() + self.heartbeat_interval);
+        }
+    }
+
+    async fn flush(&mut self) {
+        self.deadline = None;
+        let events = std::mem::take(&mut self.buffer);
+
+        let Some(outbox) = self.outbox.as_mut() else {
+            if !events.is_empty() {
+                self.send(events).await;
+            }
+            return;
+        };
+
+        // Events go to disk first so they survive a crash or outage, then the outbox is drained in order
+        match outbox.append(&events) {
+            Ok(evicted) if !evicted.is_empty() => {
+                tracing::warn!("Outbox is full: evicted {} undelivered events", evicted.len());
+                self.report(evicted, Err(Error::OutboxFull));
+            }
+            Ok(_) => {}
+            Err(e) => {
+                if !events.is_empty() {
+                    self.report(events, Err(Error::Outbox(e)));
+                }
+                return;
+            }
+        }
+        self.drain_outbox().await;
+    }
+
+    async fn send(&mut self, events: Vec<DetectionEvent>) {
+        let mut batch = IngestBatchRequest::new(events);
+        batch.heartbeat = self.current_heartbeat();
+
+        let result = self.client.ingest_batch(&batch).await;
+        self.heartbeat_sent();
+        self.report(batch.events, result);
+    }
+
+    /// Delivers stored events oldest first until the outbox is empty or the backend is unreachable
+    async fn drain_outbox(&mut self) {
+        if self.retry_at.is_some() {
+            return;
+        }
+
+        loop {
+            let Some(outbox) = self.outbox.as_mut() else {
+                return;
+            };
+            let pending = match outbox.peek(self.max_batch_size) {
+                Ok(Some(pending)) => pending,
+                Ok(None) => return,
+                Err(_) => {
+                    self.retry_at = Some(Instant::now() + self.replay_interval);
+                    return;
+                }
+            };
+
+            let mut batch = IngestBatchRequest::new(pending.events);
+            batch.heartbeat = self.current_heartbeat();
+            let result = self.client.ingest_batch(&batch).await;
+            self.heartbeat_sent();
+
+            if let Err(error) = &result {
+                if error.is_retryable() {
+                    let delay = error.retry_after().map_or(self.replay_interval, |delay| delay.min(MAX_REPLAY_DELAY));
+                    self.retry_at = Some(Instant::now() + delay);
+                    return;
+                }
+                // Every later batch would be refused the same way, and the events themselves are
+                // fine, so they are kept for when the credentials work again
+                if error.is_auth_failure() {
+                    self.retry_at = Some(Instant::now() + self.replay_interval);
+                    self.report(batch.events, result);
+                    return;
+                }
+            }
+
+            // Delivered, or refused for good: either way it must not be replayed
+            let acked = self.outbox.as_mut().map_or(Ok(()), |outbox| outbox.ack(pending.end));
+            if let Err(e) = acked {
+                self.report(batch.events, Err(Error::Outbox(e)));
+                self.retry_at = Some(Instant::now() + self.replay_interval);
+                return;
+            }
+            self.report(batch.events, result);
+        }
+    }
+
+    fn report(&self, events: Vec<DetectionEvent>, result: Result<IngestResponse>) {
+        if let Some(on_report) = &self.on_report {
+            on_report(BatchReport { events, result });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+    use super::*;
+    use crate::{outbox::OutboxConfig, retry::RetryPolicy, types::Severity};
+
+    const ACCEPTED: &str = r#"{"success":true,"processed":1,"failed":0,"errors":[]}"#;
+
+    /// Answers one request per scripted `(status line, extra headers, body)`, recording when each arrived
+    async fn serve(script: Vec<(&'static str, &'static str, &'static str)>) -> (String, Arc<Mutex<Vec<Instant>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let arrivals = Arc::new(Mutex::new(Vec::new()));
+        let recorded = arrivals.clone();
+        tokio::spawn(async move {
+            for (status, headers, body) in script {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 8192];
+                loop {
+                    let read = socket.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..read]);
+                    let text = String::from_utf8_lossy(&request);
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text[..end]
+                            .lines()
+                            .find_map(|line| line.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                            .unwrap_or(0);
+                        if request.len() >= end + 4 + length {
+                            break;
+                        }
+                    }
+                }
+                recorded.lock().unwrap().push(Instant::now());
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+                    status, body.len(), headers, body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+                socket.shutdown().await.unwrap();
+            }
+        });
+        (url, arrivals)
+    }
+
+    fn batcher(url: &str, outbox_dir: &std::path::Path, reports: Arc<Mutex<Vec<BatchReport>>>) -> Batcher {
+        let client = AnticheatClient::builder()
+            .base_url(url)
+            .api_key("org_test_agent_key")
+            .retry_policy(RetryPolicy::none())
+            .without_circuit_breaker()
+            .build()
+            .unwrap();
+        Batcher::builder(client)
+            .outbox(Outbox::open(OutboxConfig::new(outbox_dir)).unwrap())
+            .replay_interval(Duration::from_millis(50))
+            .on_report(move |report| reports.lock().unwrap().push(report))
+            .spawn()
+    }
+
+    fn outbox_dir() -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("batcher-test-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[tokio::test]
+    async fn auth_failures_keep_events_in_the_outbox() {
+        let (url, arrivals) = serve(vec![("401 Unauthorized", "", ""), ("202 Accepted", "", ACCEPTED)]).await;
+        let dir = outbox_dir();
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let batcher = batcher(&url, &dir, reports.clone());
+
+        batcher.send(DetectionEvent::new("speed_hack", Severity::High)).await.unwrap();
+        batcher.flush().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        batcher.shutdown().await.unwrap();
+
+        assert_eq!(arrivals.lock().unwrap().len(), 2);
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 2);
+        assert!(reports[0].result.as_ref().is_err_and(Error::is_auth_failure));
+        assert!(reports[1].result.is_ok());
+        assert_eq!(reports[0].events[0].event_id, reports[1].events[0].event_id);
+        assert!(Outbox::open(OutboxConfig::new(&dir)).unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn outbox_replay_waits_for_retry_after() {
+        let (url, arrivals) = serve(vec![
+            ("429 Too Many Requests", "Retry-After: 1\r\n", ""),
+            ("202 Accepted", "", ACCEPTED),
+        ]).await;
+        let dir = outbox_dir();
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let batcher = batcher(&url, &dir, reports.clone());
+
+        batcher.send(DetectionEvent::new("speed_hack", Severity::High)).await.unwrap();
+        batcher.flush().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert_eq!(arrivals.lock().unwrap().len(), 1, "the replay interval is shorter than Retry-After");
+        tokio::time::sleep(Duration::from_millis(1000)).await;
+        batcher.shutdown().await.unwrap();
+
+        let arrivals = arrivals.lock().unwrap();
+        assert_eq!(arrivals.len(), 2);
+        assert!(arrivals[1] - arrivals[0] >= Duration::from_secs(1));
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert!(reports[0].result.is_ok());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[tokio::test]
+    async fn events_evicted_from_a_full_outbox_are_reported() {
+        let (url, arrivals) = serve(vec![("503 Service Unavailable", "Retry-After: 60\r\n", "")]).await;
+        let dir = outbox_dir();
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let recorded = reports.clone();
+        let client = AnticheatClient::builder()
+            .base_url(&url)
+            .api_key("org_test_agent_key")
+            .retry_policy(RetryPolicy::none())
+            .without_circuit_breaker()
+            .build()
+            .unwrap();
+        // Each flush gets its own segment and only the newest one fits
+        let config = OutboxConfig { segment_max_bytes: 1, max_total_bytes: 1, ..OutboxConfig::new(&dir) };
+        let batcher = Batcher::builder(client)
+            .outbox(Outbox::open(config).unwrap())
+            .on_report(move |report| recorded.lock().unwrap().push(report))
+            .spawn();
+
+        batcher.send(DetectionEvent::new("speed_hack", Severity::High).event_id("first")).await.unwrap();
+        batcher.flush().await.unwrap();
+        batcher.send(DetectionEvent::new("speed_hack", Severity::High).event_id("second")).await.unwrap();
+        batcher.flush().await.unwrap();
+        batcher.shutdown().await.unwrap();
+
+        assert_eq!(arrivals.lock().unwrap().len(), 1, "delivery is backing off after the 503");
+        let reports = reports.lock().unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].events[0].event_id.as_deref(), Some("first"));
+        assert!(matches!(reports[0].result, Err(Error::OutboxFull)));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
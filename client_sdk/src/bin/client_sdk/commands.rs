@@ -0,0 +1,307 @@
+use std::{
+    error::Error,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use async_trait::async_trait;
+use clap::Args;
+use validator::Validate;
+use client_sdk::{
+    batcher::MAX_BATCH_SIZE, Batcher, DetectionEvent, Detector, Error as SdkError, HeartbeatSource,
+    IngestBatchRequest, IngestResponse, ScanError, ScanOutcome, ScanStats, Scheduler, Severity,
+};
+use crate::config::Connection;
+
+type CliResult = Result<(), Box<dyn Error>>;
+
+/// Stand-in for a real memory scanner: reports a routine scan every run
+struct SimulatedMemoryScan;
+
+#[async_trait]
+impl Detector for SimulatedMemoryScan {
+    fn name(&self) -> &str {
+        "memory_scan"
+    }
+
+    fn interval(&self) -> Duration {
+        Duration::from_secs(10)
+    }
+
+    async fn scan(&self) -> Result<Vec<DetectionEvent>, ScanError> {
+        Ok(vec![DetectionEvent::new("memory_scan", Severity::Low)
+            .title("Routine Scan Completed")
+            .description("No anomalies detected in process memory.")
+            .metadata(serde_json::json!({ "scanned_regions": 1024 }))])
+    }
+}
+
+pub async fn run(connection: &Connection, signatures: Option<PathBuf>, game_pid: Option<u32>) -> CliResult {
+    let client = connection.client()?;
+    println!("Starting Game Client Simulation");
+    println!("Connecting to Anticheat Server at {}...", client.base_url());
+
+    let scan_stats = ScanStats::default();
+    let heartbeat = HeartbeatSource::new(env!("CARGO_PKG_VERSION"), std::env::consts::OS).scan_stats(scan_stats.clone());
+
+    let batcher = Batcher::builder(client)
+        .max_batch_age(Duration::from_secs(1))
+        .heartbeat_source(Arc::new(heartbeat))
+        .heartbeat_interval(Duration::from_secs(10))
+        .on_report(|report| match report.result {
+            Ok(response) if report.events.is_empty() => println!("<-- heartbeat accepted, errors={:?}", response.errors),
+            Ok(response) => println!(
+                "<-- processed={}, failed={}, errors={:?}",
+                response.processed, response.failed, response.errors
+            ),
+            Err(SdkError::Unauthorized) => println!("<-- API key rejected"),
+            Err(e) => println!("<-- Error: {}", e),
+        })
+        .spawn();
+
+    let mut scheduler = Scheduler::builder(&batcher)
+        .scan_stats(scan_stats)
+        .register(SimulatedMemoryScan);
+
+    // Real process scanning on Linux when a signature file is given
+    #[cfg(target_os = "linux")]
+    if let Some(path) = signatures {
+        use client_sdk::detectors::process::{ProcessScanner, Signature};
+
+        let signatures = Signature::from_json(&std::fs::read_to_string(&path)?)?;
+        println!("Loaded {} signature(s) from {}", signatures.len(), path.display());
+        let mut scanner = ProcessScanner::new(signatures);
+        if let Some(pid) = game_pid {
+            scanner = scanner.target_pid(pid);
+        }
+        scheduler = scheduler.register(scanner);
+    }
+    #[cfg(not(target_os = "linux"))]
+    if signatures.is_some() || game_pid.is_some() {
+        return Err("the process scanner is only available on Linux".into());
+    }
+
+    let scheduler = scheduler
+        .on_scan(|report| match report.outcome {
+            ScanOutcome::Completed { events, .. } => println!("{}: {} event(s) in {:?}", report.detector, events, report.duration),
+            ScanOutcome::Failed(e) => println!("{}: scan failed: {}", report.detector, e),
+            ScanOutcome::TimedOut => println!("{}: scan timed out", report.detector),
+        })
+        .spawn();
+
+    tokio::signal::ctrl_c().await?;
+
+    println!("Shutting down...");
+    scheduler.shutdown().await;
+    batcher.shutdown().await?;
+    Ok(())
+}
+
+#[derive(Debug, Args)]
+pub struct SendArgs {
+    #[arg(long, required_unless_present = "file")]
+    event_type: Option<String>,
+
+    /// info, low, medium, high or critical
+    #[arg(long, default_value = "medium")]
+    severity: Severity,
+
+    #[arg(long)]
+    title: Option<String>,
+
+    #[arg(long)]
+    description: Option<String>,
+
+    /// Event metadata as a JSON object
+    #[arg(long, value_parser = parse_json)]
+    metadata: Option<serde_json::Value>,
+
+    /// JSON file holding one event or an array of events
+    #[arg(long, conflicts_with_all = ["event_type", "title", "description", "metadata"])]
+    file: Option<PathBuf>,
+}
+
+fn parse_json(value: &str) -> Result<serde_json::Value, String> {
+    serde_json::from_str(value).map_err(|e| format!("invalid JSON: {}", e))
+}
+
+pub async fn send(connection: &Connection, args: SendArgs) -> CliResult {
+    let events = match &args.file {
+        Some(path) => {
+            let contents = std::fs::read_to_string(path)?;
+            match serde_json::from_str::<serde_json::Value>(&contents)? {
+                serde_json::Value::Array(items) => items
+                    .into_iter()
+                    .map(serde_json::from_value)
+                    .collect::<Result<Vec<DetectionEvent>, _>>()?,
+                single => vec![serde_json::from_value(single)?],
+            }
+        }
+        None => {
+            let mut event = DetectionEvent::new(args.event_type.unwrap_or_default(), args.severity);
+            event.title = args.title;
+            event.description = args.description;
+            if let Some(metadata) = args.metadata {
+                event.metadata = metadata;
+            }
+            vec![event]
+        }
+    };
+    if events.is_empty() {
+        return Err("no events to send".into());
+    }
+
+    let client = connection.client()?;
+    let mut failed = 0;
+    for chunk in events.chunks(MAX_BATCH_SIZE) {
+        let response = client.ingest_batch(&IngestBatchRequest::new(chunk.to_vec())).await?;
+        println!("{}", serde_json::to_string_pretty(&response)?);
+        failed += response.failed;
+    }
+    match failed {
+        0 => Ok(()),
+        n => Err(format!("{} event(s) rejected", n).into()),
+    }
+}
+
+pub async fn replay(connection: &Connection, file: &Path, batch_size: usize) -> CliResult {
+    let reader: Box<dyn BufRead> = if file == Path::new("-") {
+        Box::new(BufReader::new(std::io::stdin()))
+    } else {
+        Box::new(BufReader::new(std::fs::File::open(file)?))
+    };
+    let client = connection.client()?;
+    let batch_size = batch_size.clamp(1, MAX_BATCH_SIZE);
+
+    let mut totals = ReplayTotals::default();
+    // (line number, event); line numbers let server errors point back into the file
+    let mut pending: Vec<(usize, DetectionEvent)> = Vec::with_capacity(batch_size);
+    for (index, line) in reader.lines().enumerate() {
+        let line_no = index + 1;
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<DetectionEvent>(&line) {
+            Ok(event) if event.validate().is_ok() => pending.push((line_no, event)),
+            Ok(_) => totals.invalid(line_no, "event fails validation"),
+            Err(e) => totals.invalid(line_no, &e.to_string()),
+        }
+        if pending.len() >= batch_size {
+            totals.send(&client, std::mem::take(&mut pending)).await;
+        }
+    }
+    if !pending.is_empty() {
+        totals.send(&client, pending).await;
+    }
+
+    println!(
+        "replayed: {} accepted, {} duplicate(s), {} rejected by the server, {} invalid line(s)",
+        totals.accepted, totals.duplicates, totals.rejected, totals.invalid
+    );
+    if totals.rejected + totals.invalid > 0 {
+        return Err("some events were not ingested".into());
+    }
+    Ok(())
+}
+
+/// Uploads an NDJSON file unparsed; the server validates each line and reports failures by line number
+pub async fn replay_stream(connection: &Connection, file: &Path) -> CliResult {
+    let client = connection.client()?;
+    let response = if file == Path::new("-") {
+        client.ingest_ndjson(tokio::io::stdin()).await?
+    } else {
+        client.ingest_ndjson(tokio::fs::File::open(file).await?).await?
+    };
+
+    for error in &response.errors {
+        eprintln!("line {}: {}", error.line, error.error);
+    }
+    if response.errors_truncated {
+        eprintln!("... {} more failed line(s)", response.failed - response.errors.len() as u64);
+    }
+    println!(
+        "streamed {} line(s): {} accepted, {} duplicate(s), {} rejected by the server",
+        response.lines, response.processed, response.duplicates, response.failed
+    );
+    if response.failed > 0 {
+        return Err("some events were not ingested".into());
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+struct ReplayTotals {
+    accepted: u64,
+    duplicates: u64,
+    rejected: u64,
+    invalid: u64,
+}
+
+impl ReplayTotals {
+    fn invalid(&mut self, line_no: usize, message: &str) {
+        eprintln!("line {}: {}", line_no, message);
+        self.invalid += 1;
+    }
+
+    async fn send(&mut self, client: &client_sdk::AnticheatClient, batch: Vec<(usize, DetectionEvent)>) {
+        let (lines, events): (Vec<usize>, Vec<DetectionEvent>) = batch.into_iter().unzip();
+        let first = lines.first().copied().unwrap_or_default();
+        let last = lines.last().copied().unwrap_or_default();
+
+        let response: IngestResponse = match client.ingest_batch(&IngestBatchRequest::new(events)).await {
+            Ok(response) => response,
+            Err(SdkError::Rejected { response, .. }) => *response,
+            Err(e) => {
+                eprintln!("lines {}-{}: {}", first, last, e);
+                self.rejected += lines.len() as u64;
+                return;
+            }
+        };
+        for (index, message) in response.event_errors() {
+            if let Some(line_no) = lines.get(index) {
+                eprintln!("line {}: {}", line_no, message);
+            }
+        }
+        println!(
+            "lines {}-{}: processed={}, failed={}, duplicates={}",
+            first, last, response.processed, response.failed, response.duplicates
+        );
+        self.accepted += response.processed as u64;
+        self.duplicates += response.duplicates as u64;
+        self.rejected += (lines.len() as u64).saturating_sub((response.processed + response.duplicates) as u64);
+    }
+}
+
+pub async fn heartbeat(
+    connection: &Connection,
+    agent_version: String,
+    platform: String,
+    config_version: Option<u64>,
+) -> CliResult {
+    let source = HeartbeatSource::new(agent_version, platform);
+    if let Some(version) = config_version {
+        source.set_config_version(version);
+    }
+    let heartbeat = source.collect();
+    heartbeat.validate()?;
+
+    let response = connection
+        .client()?
+        .ingest_batch(&IngestBatchRequest::heartbeat_only(heartbeat))
+        .await?;
+    println!("{}", serde_json::to_string_pretty(&response)?);
+    Ok(())
+}
+
+pub async fn verify_key(connection: &Connection) -> CliResult {
+    match connection.client()?.agent_config().await {
+        Ok(config) => {
+            println!("API key accepted by {} (config version {})", connection.server_url, config.version);
+            Ok(())
+        }
+        Err(SdkError::Unauthorized) => Err("API key rejected by the server".into()),
+        Err(e) => Err(e.into()),
+    }
+}
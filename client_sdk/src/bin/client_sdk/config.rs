@@ -0,0 +1,148 @@
+//! Connection settings: flags win over environment variables, which win over the config file.
+
+use std::{
+    error::Error,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use clap::{Args, ValueEnum};
+use serde::Deserialize;
+use client_sdk::{AnticheatClient, BodyFormat, Compression};
+
+const DEFAULT_SERVER_URL: &str = "http://localhost:3000";
+const DEFAULT_TIMEOUT_SECS: u64 = 10;
+
+#[derive(Debug, Args)]
+pub struct ConnectionArgs {
+    /// Server root URL [default: http://localhost:3000]
+    #[arg(long, env = "ANTICHEAT_URL", global = true)]
+    pub server_url: Option<String>,
+
+    /// Agent API key
+    #[arg(long, env = "ANTICHEAT_API_KEY", hide_env_values = true, global = true)]
+    pub api_key: Option<String>,
+
+    /// Sign requests with the API key instead of sending it
+    #[arg(long, env = "ANTICHEAT_SIGN_REQUESTS", global = true)]
+    pub sign_requests: bool,
+
+    /// TOML config file [default: $XDG_CONFIG_HOME/anticheat/client.toml]
+    #[arg(long, env = "ANTICHEAT_CONFIG", global = true)]
+    pub config: Option<PathBuf>,
+
+    /// Request timeout in seconds [default: 10]
+    #[arg(long, global = true)]
+    pub timeout: Option<u64>,
+
+    /// Ingest body encoding [default: json]
+    #[arg(long, value_enum, global = true)]
+    pub format: Option<FormatArg>,
+
+    /// Ingest body compression [default: none]
+    #[arg(long, value_enum, global = true)]
+    pub compression: Option<CompressionArg>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FormatArg {
+    Json,
+    Msgpack,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompressionArg {
+    None,
+    Gzip,
+    Zstd,
+}
+
+/// Contents of the config file; every key is optional
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    server_url: Option<String>,
+    api_key: Option<String>,
+    sign_requests: Option<bool>,
+    timeout_secs: Option<u64>,
+    format: Option<FormatArg>,
+    compression: Option<CompressionArg>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Connection {
+    pub server_url: String,
+    pub api_key: String,
+    pub sign_requests: bool,
+    pub timeout: Duration,
+    pub body_format: BodyFormat,
+    pub compression: Compression,
+}
+
+impl ConnectionArgs {
+    pub fn resolve(&self) -> Result<Connection, Box<dyn Error>> {
+        let (file, path) = match &self.config {
+            Some(path) => (read_config(path)?, Some(path.clone())),
+            None => match default_config_path().filter(|path| path.exists()) {
+                Some(path) => (read_config(&path)?, Some(path)),
+                None => (FileConfig::default(), None),
+            },
+        };
+
+        let api_key = self.api_key.clone().or(file.api_key).ok_or_else(|| {
+            let hint = path.map_or_else(String::new, |p| format!(" or add api_key to {}", p.display()));
+            format!("an API key is required: pass --api-key, set ANTICHEAT_API_KEY{}", hint)
+        })?;
+
+        Ok(Connection {
+            server_url: self
+                .server_url
+                .clone()
+                .or(file.server_url)
+                .unwrap_or_else(|| DEFAULT_SERVER_URL.to_string()),
+            api_key,
+            sign_requests: self.sign_requests || file.sign_requests.unwrap_or(false),
+            timeout: Duration::from_secs(self.timeout.or(file.timeout_secs).unwrap_or(DEFAULT_TIMEOUT_SECS)),
+            body_format: match self.format.or(file.format) {
+                None | Some(FormatArg::Json) => BodyFormat::Json,
+                Some(FormatArg::Msgpack) => BodyFormat::MessagePack,
+            },
+            compression: match self.compression.or(file.compression) {
+                None | Some(CompressionArg::None) => Compression::None,
+                Some(CompressionArg::Gzip) => Compression::Gzip,
+                Some(CompressionArg::Zstd) => Compression::Zstd,
+            },
+        })
+    }
+}
+
+impl Connection {
+    pub fn client(&self) -> client_sdk::error::Result<AnticheatClient> {
+        self.client_with_key(&self.api_key)
+    }
+
+    pub fn client_with_key(&self, api_key: &str) -> client_sdk::error::Result<AnticheatClient> {
+        AnticheatClient::builder()
+            .base_url(&self.server_url)
+            .api_key(api_key)
+            .sign_requests(self.sign_requests)
+            .timeout(self.timeout)
+            .body_format(self.body_format)
+            .compression(self.compression)
+            .build()
+    }
+}
+
+fn read_config(path: &Path) -> Result<FileConfig, Box<dyn Error>> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read config file {}: {}", path.display(), e))?;
+    Ok(toml::from_str(&contents).map_err(|e| format!("invalid config file {}: {}", path.display(), e))?)
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("anticheat").join("client.toml"))
+}
@@ -0,0 +1,92 @@
+mod commands;
+mod config;
+mod simulate;
+
+use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+use config::ConnectionArgs;
+
+/// Command-line agent and test client for the Anticheat ingest API
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Cli {
+    #[command(flatten)]
+    connection: ConnectionArgs,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run the agent: built-in detectors, batching and heartbeats until Ctrl-C
+    Run {
+        /// JSON signature list enabling the Linux process scanner
+        #[arg(long, env = "ANTICHEAT_SIGNATURES")]
+        signatures: Option<PathBuf>,
+
+        /// Game process whose loaded modules are scanned as well
+        #[arg(long, env = "ANTICHEAT_GAME_PID")]
+        game_pid: Option<u32>,
+    },
+
+    /// Send one detection from flags, or the events of a JSON file
+    Send(commands::SendArgs),
+
+    /// Send every event of an NDJSON file (`-` for stdin) in batches
+    Replay {
+        file: PathBuf,
+
+        /// Events per request
+        #[arg(long, default_value_t = 500, conflicts_with = "stream")]
+        batch_size: usize,
+
+        /// Upload the file as-is in one streaming request to /ingest/stream instead of batching
+        #[arg(long)]
+        stream: bool,
+    },
+
+    /// Send a heartbeat-only batch with this machine's metrics
+    Heartbeat {
+        #[arg(long, default_value = env!("CARGO_PKG_VERSION"))]
+        agent_version: String,
+
+        #[arg(long, default_value = std::env::consts::OS)]
+        platform: String,
+
+        /// Config version to report as applied
+        #[arg(long)]
+        config_version: Option<u64>,
+    },
+
+    /// Generate load from many virtual agents
+    Simulate(simulate::SimulateArgs),
+
+    /// Check that the API key is accepted by the server
+    VerifyKey,
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+    if let Err(e) = dispatch(cli).await {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn dispatch(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
+    let connection = cli.connection.resolve()?;
+
+    match cli.command {
+        Command::Run { signatures, game_pid } => commands::run(&connection, signatures, game_pid).await,
+        Command::Send(args) => commands::send(&connection, args).await,
+        Command::Replay { file, stream: true, .. } => commands::replay_stream(&connection, &file).await,
+        Command::Replay { file, batch_size, .. } => commands::replay(&connection, &file, batch_size).await,
+        Command::Heartbeat { agent_version, platform, config_version } => {
+            commands::heartbeat(&connection, agent_version, platform, config_version).await
+        }
+        Command::Simulate(args) => simulate::run(&connection, args).await,
+        Command::VerifyKey => commands::verify_key(&connection).await,
+    }
+}
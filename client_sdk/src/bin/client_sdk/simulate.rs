@@ -0,0 +1,173 @@
+//! Load generation: many virtual agents, each with its own batcher, sending at a fixed rate.
+
+use std::{
+    error::Error,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use clap::Args;
+use rand::{seq::SliceRandom, Rng};
+use tokio::time::{Instant, MissedTickBehavior};
+use client_sdk::{Batcher, DetectionEvent, Error as SdkError, HeartbeatSource, Severity};
+use crate::config::Connection;
+
+const EVENT_TYPES: &[&str] = &["memory_scan", "suspicious_process", "module_injection", "speed_hack", "aim_assist"];
+const SEVERITIES: &[Severity] = &[Severity::Low, Severity::Medium, Severity::High, Severity::Critical];
+
+#[derive(Debug, Args)]
+pub struct SimulateArgs {
+    /// Number of virtual agents
+    #[arg(long, default_value_t = 10)]
+    agents: usize,
+
+    /// Events per second, per agent
+    #[arg(long, default_value_t = 1.0)]
+    rate: f64,
+
+    /// Stop after this many seconds instead of waiting for Ctrl-C
+    #[arg(long)]
+    duration: Option<u64>,
+
+    /// File with one API key per line, assigned to agents round-robin; defaults to the configured key
+    #[arg(long)]
+    keys_file: Option<PathBuf>,
+
+    /// Seconds between progress lines
+    #[arg(long, default_value_t = 5)]
+    report_every: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    generated: AtomicU64,
+    dropped: AtomicU64,
+    accepted: AtomicU64,
+    rejected: AtomicU64,
+    request_errors: AtomicU64,
+}
+
+pub async fn run(connection: &Connection, args: SimulateArgs) -> Result<(), Box<dyn Error>> {
+    if args.agents == 0 || args.rate <= 0.0 {
+        return Err("--agents and --rate must be positive".into());
+    }
+    let keys = match &args.keys_file {
+        Some(path) => {
+            let keys: Vec<String> = std::fs::read_to_string(path)?
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect();
+            if keys.is_empty() {
+                return Err(format!("no API keys in {}", path.display()).into());
+            }
+            keys
+        }
+        None => vec![connection.api_key.clone()],
+    };
+
+    println!(
+        "Simulating {} agent(s) at {} event(s)/s each against {}",
+        args.agents, args.rate, connection.server_url
+    );
+
+    let counters = Arc::new(Counters::default());
+    let mut agents = Vec::with_capacity(args.agents);
+    for index in 0..args.agents {
+        let client = connection.client_with_key(&keys[index % keys.len()])?;
+        let reports = counters.clone();
+        let batcher = Batcher::builder(client)
+            .max_batch_age(Duration::from_secs(1))
+            .heartbeat_source(Arc::new(HeartbeatSource::new(env!("CARGO_PKG_VERSION"), "simulator")))
+            .on_report(move |report| match report.result {
+                Ok(response) => {
+                    reports.accepted.fetch_add(response.processed as u64, Ordering::Relaxed);
+                    reports.rejected.fetch_add(response.failed as u64, Ordering::Relaxed);
+                }
+                Err(SdkError::Rejected { .. }) => {
+                    reports.rejected.fetch_add(report.events.len() as u64, Ordering::Relaxed);
+                }
+                Err(_) => {
+                    reports.request_errors.fetch_add(1, Ordering::Relaxed);
+                    reports.rejected.fetch_add(report.events.len() as u64, Ordering::Relaxed);
+                }
+            })
+            .spawn();
+        let generator = tokio::spawn(generate(batcher.sender(), args.rate, counters.clone()));
+        agents.push((batcher, generator));
+    }
+
+    let started = Instant::now();
+    let deadline = args.duration.map(|secs| started + Duration::from_secs(secs));
+    let mut progress = tokio::time::interval(Duration::from_secs(args.report_every.max(1)));
+    progress.tick().await;
+    loop {
+        tokio::select! {
+            _ = progress.tick() => print_progress(&counters, started.elapsed()),
+            _ = sleep_until(deadline) => break,
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    println!("Stopping agents and flushing...");
+    for (_, generator) in &agents {
+        generator.abort();
+    }
+    for (batcher, _) in agents {
+        let _ = batcher.shutdown().await;
+    }
+    print_progress(&counters, started.elapsed());
+    Ok(())
+}
+
+async fn generate(sender: client_sdk::EventSender, rate: f64, counters: Arc<Counters>) {
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / rate));
+    // Falling behind means the machine is saturated; skip rather than burst
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        ticker.tick().await;
+        let event = random_event();
+        counters.generated.fetch_add(1, Ordering::Relaxed);
+        match sender.try_send(event) {
+            Ok(()) => {}
+            Err(SdkError::Closed) => return,
+            Err(_) => {
+                counters.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+fn random_event() -> DetectionEvent {
+    let mut rng = rand::thread_rng();
+    let event_type = EVENT_TYPES.choose(&mut rng).copied().unwrap_or("memory_scan");
+    let severity = SEVERITIES.choose(&mut rng).copied().unwrap_or(Severity::Low);
+    DetectionEvent::new(event_type, severity)
+        .title(format!("Simulated {}", event_type))
+        .metadata(serde_json::json!({ "simulated": true, "confidence": rng.gen_range(0.5..1.0) }))
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+fn print_progress(counters: &Counters, elapsed: Duration) {
+    let accepted = counters.accepted.load(Ordering::Relaxed);
+    println!(
+        "[{:>6.1}s] generated={} accepted={} ({:.1}/s) rejected={} dropped={} request_errors={}",
+        elapsed.as_secs_f64(),
+        counters.generated.load(Ordering::Relaxed),
+        accepted,
+        accepted as f64 / elapsed.as_secs_f64().max(0.001),
+        counters.rejected.load(Ordering::Relaxed),
+        counters.dropped.load(Ordering::Relaxed),
+        counters.request_errors.load(Ordering::Relaxed),
+    );
+}
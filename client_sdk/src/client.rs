@@ -0,0 +1,332 @@
+use std::{sync::Arc, time::Duration};
+use chrono::{DateTime, Utc};
+use anticheat_protocol::signing::{
+    self, CONTENT_SHA256_HEADER, KEY_ID_HEADER, SIGNATURE_HEADER, SIGNATURE_NONCE_HEADER,
+    SIGNATURE_TIMESTAMP_HEADER, UNSIGNED_PAYLOAD,
+};
+use reqwest::{header::{HeaderMap, CONTENT_ENCODING, CONTENT_TYPE, RETRY_AFTER}, RequestBuilder, StatusCode, Url};
+use tokio::io::AsyncRead;
+use tokio_util::io::ReaderStream;
+use crate::{
+    error::{Error, Result},
+    encoding::{self, BodyFormat, Compression, EncodedBody},
+    retry::{CircuitBreakerConfig, Retrier, RetryMetrics, RetryPolicy},
+    types::{IngestBatchRequest, IngestResponse, ResolvedConfig, StreamIngestResponse, IDEMPOTENCY_KEY_HEADER},
+};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Typed client for the agent-facing endpoints, authenticated with an `X-API-Key`
+/// or, with [`AnticheatClientBuilder::sign_requests`], an HMAC request signature.
+///
+/// Requests are retried according to the client's [`RetryPolicy`]; clones share
+/// one circuit breaker.
+#[derive(Debug, Clone)]
+pub struct AnticheatClient {
+    http: reqwest::Client,
+    base_url: Url,
+    api_key: String,
+    signing_key: Option<SigningKey>,
+    body_format: BodyFormat,
+    compression: Compression,
+    retrier: Arc<Retrier>,
+}
+
+/// Key ID and HMAC secret derived from the API key
+#[derive(Debug, Clone)]
+struct SigningKey {
+    key_id: String,
+    secret: String,
+}
+
+impl AnticheatClient {
+    pub fn builder() -> AnticheatClientBuilder {
+        AnticheatClientBuilder::default()
+    }
+
+    pub fn base_url(&self) -> &Url {
+        &self.base_url
+    }
+
+    /// Sends a batch to `POST /ingest/batch`.
+    ///
+    /// Partial failures (206) are returned as `Ok` with `failed > 0`; whole-batch
+    /// rejections carrying an ingest response surface as [`Error::Rejected`].
+    /// Every retry carries the same `Idempotency-Key`, so a batch that reached the
+    /// server before a timeout is reported back as duplicates rather than stored twice.
+    /// An attached heartbeat gets `sent_at` set to the local clock on every attempt,
+    /// so the server can measure clock skew without counting time spent retrying.
+    pub async fn ingest_batch(&self, batch: &IngestBatchRequest) -> Result<IngestResponse> {
+        let idempotency_key = uuid::Uuid::new_v4().to_string();
+        if batch.heartbeat.is_none() {
+            let body = encoding::encode(batch, self.body_format, self.compression)?;
+            return self.retrier.run(|| self.send_ingest_batch(&body, &idempotency_key)).await;
+        }
+
+        self.retrier
+            .run(|| async {
+                let mut stamped = batch.clone();
+                if let Some(heartbeat) = &mut stamped.heartbeat {
+                    heartbeat.sent_at = Some(Utc::now());
+                }
+                let body = encoding::encode(&stamped, self.body_format, self.compression)?;
+                self.send_ingest_batch(&body, &idempotency_key).await
+            })
+            .await
+    }
+
+    /// Streams newline-delimited [`DetectionEvent`](crate::DetectionEvent) JSON from `reader`
+    /// to `POST /ingest/stream`, for backfills too large for a batch.
+    ///
+    /// The body is read lazily, so the upload may be of any size; the client timeout
+    /// still applies to the whole request. Lines the server refused are listed by
+    /// line number in the response. A consumed reader cannot be replayed, so this
+    /// request is never retried: events with an `event_id` can safely be re-sent.
+    pub async fn ingest_ndjson<R>(&self, reader: R) -> Result<StreamIngestResponse>
+    where
+        R: AsyncRead + Send + Sync + 'static,
+    {
+        let url = self.url("ingest/stream")?;
+        // The stream cannot be hashed before it is sent, so only the request line is signed
+        let response = self
+            .authenticate(self.http.post(url.clone()), "POST", &url, None)
+            .header(CONTENT_TYPE, "application/x-ndjson")
+            .body(reqwest::Body::wrap_stream(ReaderStream::new(reader)))
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status == StatusCode::UNAUTHORIZED {
+            return Err(Error::Unauthorized);
+        }
+        if !status.is_success() {
+            let retry_after = retry_after(status, response.headers());
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::Status { status, body, retry_after });
+        }
+        Ok(response.json().await?)
+    }
+
+    /// Fetches the configuration currently assigned to this agent from `GET /agents/config`
+    pub async fn agent_config(&self) -> Result<ResolvedConfig> {
+        self.retrier.run(|| self.fetch_agent_config()).await
+    }
+
+    async fn send_ingest_batch(&self, body: &EncodedBody, idempotency_key: &str) -> Result<IngestResponse> {
+        let url = self.url("ingest/batch")?;
+        let mut request = self
+            .authenticate(self.http.post(url.clone()), "POST", &url, Some(&body.bytes))
+            .header(IDEMPOTENCY_KEY_HEADER, idempotency_key)
+            .header(CONTENT_TYPE, body.content_type);
+        if let Some(content_encoding) = body.content_encoding {
+            request = request.header(CONTENT_ENCODING, content_encoding);
+        }
+        let response = request.body(body.bytes.clone()).send().await?;
+
+        let status = response.status();
+        if status == StatusCode::UNAUTHORIZED {
+            return Err(Error::Unauthorized);
+        }
+
+        let retry_after = retry_after(status, response.headers());
+        let body = response.text().await?;
+        match serde_json::from_str::<IngestResponse>(&body) {
+            Ok(ingest) if status.is_success() => Ok(ingest),
+            Ok(ingest) => Err(Error::Rejected { status, response: Box::new(ingest), retry_after }),
+            Err(_) => Err(Error::Status { status, body, retry_after }),
+        }
+    }
+
+    async fn fetch_agent_config(&self) -> Result<ResolvedConfig> {
+        let url = self.url("agents/config")?;
+        let response = self
+            .authenticate(self.http.get(url.clone()), "GET", &url, Some(&[]))
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status == StatusCode::UNAUTHORIZED {
+            return Err(Error::Unauthorized);
+        }
+        if !status.is_success() {
+            let retry_after = retry_after(status, response.headers());
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::Status { status, body, retry_after });
+        }
+        Ok(response.json().await?)
+    }
+
+    /// Adds the `X-API-Key`, or when signing, a signature over the request with a fresh
+    /// timestamp and nonce; `body` is `None` for streamed bodies, which are left unsigned
+    fn authenticate(&self, request: RequestBuilder, method: &str, url: &Url, body: Option<&[u8]>) -> RequestBuilder {
+        let Some(signing_key) = &self.signing_key else {
+            return request.header("X-API-Key", &self.api_key);
+        };
+
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        let timestamp = Utc::now().timestamp();
+        let nonce = uuid::Uuid::new_v4().simple().to_string();
+        let content_sha256 = body.map_or_else(|| UNSIGNED_PAYLOAD.to_string(), signing::content_sha256);
+        let canonical = signing::canonical_request(method, &path, timestamp, &nonce, &content_sha256);
+
+        request
+            .header(KEY_ID_HEADER, &signing_key.key_id)
+            .header(SIGNATURE_TIMESTAMP_HEADER, timestamp.to_string())
+            .header(SIGNATURE_NONCE_HEADER, nonce)
+            .header(CONTENT_SHA256_HEADER, content_sha256)
+            .header(SIGNATURE_HEADER, signing::sign(&signing_key.secret, &canonical))
+    }
+
+    fn url(&self, path: &str) -> Result<Url> {
+        self.base_url
+            .join(path)
+            .map_err(|e| Error::Config(format!("invalid endpoint path {}: {}", path, e)))
+    }
+}
+
+/// `Retry-After` is only honoured on 429 and 503; both delta-seconds and HTTP dates are accepted
+fn retry_after(status: StatusCode, headers: &HeaderMap) -> Option<Duration> {
+    if status != StatusCode::TOO_MANY_REQUESTS && status != StatusCode::SERVICE_UNAVAILABLE {
+        return None;
+    }
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+    Some((at - Utc::now()).to_std().unwrap_or(Duration::ZERO))
+}
+
+#[derive(Default)]
+pub struct AnticheatClientBuilder {
+    base_url: Option<String>,
+    api_key: Option<String>,
+    sign_requests: bool,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    user_agent: Option<String>,
+    body_format: BodyFormat,
+    compression: Compression,
+    retry_policy: Option<RetryPolicy>,
+    circuit_breaker: Option<Option<CircuitBreakerConfig>>,
+    retry_metrics: Option<Arc<dyn RetryMetrics>>,
+}
+
+impl AnticheatClientBuilder {
+    /// Server root, e.g. `https://anticheat.example.com`
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Sign each request with an HMAC of the API key instead of sending the key itself
+    /// (default off). A captured request cannot be replayed or altered, but the server
+    /// must see the same URL path as the client, and clocks must agree within its window.
+    pub fn sign_requests(mut self, sign: bool) -> Self {
+        self.sign_requests = sign;
+        self
+    }
+
+    /// Total time allowed per request (default 30s)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Time allowed to establish a connection (default 10s)
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Serialization of ingest batches (default JSON)
+    pub fn body_format(mut self, format: BodyFormat) -> Self {
+        self.body_format = format;
+        self
+    }
+
+    /// Compression of ingest batches (default none); bodies under 1 KiB are always sent uncompressed
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Retry behaviour for every request (default [`RetryPolicy::default`])
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    /// Circuit breaker settings (default [`CircuitBreakerConfig::default`])
+    pub fn circuit_breaker(mut self, config: CircuitBreakerConfig) -> Self {
+        self.circuit_breaker = Some(Some(config));
+        self
+    }
+
+    pub fn without_circuit_breaker(mut self) -> Self {
+        self.circuit_breaker = Some(None);
+        self
+    }
+
+    /// Receives retry, drop and circuit breaker notifications, e.g. a shared [`crate::RetryCounters`]
+    pub fn retry_metrics(mut self, metrics: Arc<dyn RetryMetrics>) -> Self {
+        self.retry_metrics = Some(metrics);
+        self
+    }
+
+    pub fn build(self) -> Result<AnticheatClient> {
+        let base_url = self.base_url.ok_or_else(|| Error::Config("base URL is required".to_string()))?;
+        let api_key = self.api_key.ok_or_else(|| Error::Config("API key is required".to_string()))?;
+        if api_key.is_empty() {
+            return Err(Error::Config("API key must not be empty".to_string()));
+        }
+
+        // A trailing slash makes `Url::join` append instead of replacing the last segment
+        let base_url = if base_url.ends_with('/') { base_url } else { format!("{}/", base_url) };
+        let base_url = Url::parse(&base_url)
+            .map_err(|e| Error::Config(format!("invalid base URL: {}", e)))?;
+
+        let http = reqwest::Client::builder()
+            .timeout(self.timeout.unwrap_or(DEFAULT_TIMEOUT))
+            .connect_timeout(self.connect_timeout.unwrap_or(DEFAULT_CONNECT_TIMEOUT))
+            .user_agent(self.user_agent.unwrap_or_else(|| {
+                format!("anticheat-client-sdk/{}", env!("CARGO_PKG_VERSION"))
+            }))
+            .build()?;
+
+        let retrier = Retrier::new(
+            self.retry_policy.unwrap_or_default(),
+            self.circuit_breaker.unwrap_or_else(|| Some(CircuitBreakerConfig::default())),
+            self.retry_metrics,
+        );
+
+        let signing_key = self.sign_requests.then(|| SigningKey {
+            key_id: signing::key_id(&api_key),
+            secret: signing::signing_secret(&api_key),
+        });
+
+        Ok(AnticheatClient {
+            http,
+            base_url,
+            api_key,
+            signing_key,
+            body_format: self.body_format,
+            compression: self.compression,
+            retrier: Arc::new(retrier),
+        })
+    }
+}
@@ -0,0 +1,274 @@
+//! Pluggable detectors and the scheduler that runs them.
+//!
+//! Each registered [`Detector`] runs on its own task every [`Detector::interval`].
+//! A scan that outlives [`Detector::timeout`] is cancelled and reported as timed
+//! out; the events of completed scans are queued into a [`Batcher`].
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::{
+    task::JoinHandle,
+    time::{Instant, MissedTickBehavior},
+};
+use crate::{
+    batcher::{Batcher, EventSender},
+    error::Error,
+    types::DetectionEvent,
+};
+
+const DEFAULT_SCAN_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub type ScanError = Box<dyn std::error::Error + Send + Sync>;
+
+/// A game-specific check run periodically by the [`Scheduler`]
+#[async_trait]
+pub trait Detector: Send + Sync + 'static {
+    /// Stable identifier, also used to enable detectors from remote config
+    fn name(&self) -> &str;
+
+    /// Time between the start of consecutive scans
+    fn interval(&self) -> Duration;
+
+    /// Longest a single scan may run before it is cancelled (default 30s)
+    fn timeout(&self) -> Duration {
+        DEFAULT_SCAN_TIMEOUT
+    }
+
+    /// Performs one scan, returning whatever it detected
+    async fn scan(&self) -> Result<Vec<DetectionEvent>, ScanError>;
+}
+
+#[derive(Debug)]
+pub enum ScanOutcome {
+    /// Events produced by the scan; `rejected` were refused by the batcher (invalid or queue closed)
+    Completed { events: usize, rejected: Vec<Error> },
+    Failed(ScanError),
+    TimedOut,
+}
+
+/// Result of one scan, handed to the report callback
+#[derive(Debug)]
+pub struct ScanReport {
+    pub detector: String,
+    pub duration: Duration,
+    pub outcome: ScanOutcome,
+}
+
+type ScanCallback = Arc<dyn Fn(ScanReport) + Send + Sync>;
+
+/// Completed-scan counters shared between the scheduler and heartbeat collection
+#[derive(Debug, Clone, Default)]
+pub struct ScanStats {
+    inner: Arc<ScanStatsInner>,
+}
+
+#[derive(Debug, Default)]
+struct ScanStatsInner {
+    scan_count: AtomicU64,
+    last_scan_at: Mutex<Option<DateTime<Utc>>>,
+}
+
+impl ScanStats {
+    /// Scans that finished without error or timeout, across all detectors
+    pub fn scan_count(&self) -> u64 {
+        self.inner.scan_count.load(Ordering::Relaxed)
+    }
+
+    pub fn last_scan_at(&self) -> Option<DateTime<Utc>> {
+        *self.inner.last_scan_at.lock().expect("scan stats lock poisoned")
+    }
+
+    fn record(&self, at: DateTime<Utc>) {
+        self.inner.scan_count.fetch_add(1, Ordering::Relaxed);
+        *self.inner.last_scan_at.lock().expect("scan stats lock poisoned") = Some(at);
+    }
+}
+
+pub struct SchedulerBuilder {
+    sender: EventSender,
+    detectors: Vec<Arc<dyn Detector>>,
+    on_scan: Option<ScanCallback>,
+    stats: ScanStats,
+}
+
+impl SchedulerBuilder {
+    pub fn register(mut self, detector: impl Detector) -> Self {
+        self.detectors.push(Arc::new(detector));
+        self
+    }
+
+    /// Called from the detector's task after every scan
+    pub fn on_scan(mut self, callback: impl Fn(ScanReport) + Send + Sync + 'static) -> Self {
+        self.on_scan = Some(Arc::new(callback));
+        self
+    }
+
+    /// Records scans into existing stats, e.g. ones already handed to a [`crate::HeartbeatSource`]
+    pub fn scan_stats(mut self, stats: ScanStats) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Starts one task per detector; must be called within a tokio runtime
+    pub fn spawn(self) -> Scheduler {
+        let tasks = self
+            .detectors
+            .into_iter()
+            .map(|detector| {
+                tokio::spawn(run_detector(detector, self.sender.clone(), self.on_scan.clone(), self.stats.clone()))
+            })
+            .collect();
+        Scheduler { tasks, stats: self.stats }
+    }
+}
+
+/// Handle to the running detector tasks
+pub struct Scheduler {
+    tasks: Vec<JoinHandle<()>>,
+    stats: ScanStats,
+}
+
+impl Scheduler {
+    /// Detectors feed the given batcher, which keeps running after the scheduler stops
+    pub fn builder(batcher: &Batcher) -> SchedulerBuilder {
+        SchedulerBuilder {
+            sender: batcher.sender(),
+            detectors: Vec::new(),
+            on_scan: None,
+            stats: ScanStats::default(),
+        }
+    }
+
+    pub fn scan_stats(&self) -> &ScanStats {
+        &self.stats
+    }
+
+    /// Stops every detector, cancelling scans in progress
+    pub async fn shutdown(self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+        for task in self.tasks {
+            let _ = task.await;
+        }
+    }
+}
+
+async fn run_detector(
+    detector: Arc<dyn Detector>,
+    sender: EventSender,
+    on_scan: Option<ScanCallback>,
+    stats: ScanStats,
+) {
+    let mut ticker = tokio::time::interval(detector.interval().max(Duration::from_millis(1)));
+    // A slow scan pushes the schedule back instead of triggering a burst of catch-up scans
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        ticker.tick().await;
+
+        let started = Instant::now();
+        let outcome = match tokio::time::timeout(detector.timeout(), detector.scan()).await {
+            Ok(Ok(events)) => {
+                stats.record(Utc::now());
+                let count = events.len();
+                let mut rejected = Vec::new();
+                for event in events {
+                    if let Err(e) = sender.send(event).await {
+                        rejected.push(e);
+                    }
+                }
+                ScanOutcome::Completed { events: count, rejected }
+            }
+            Ok(Err(e)) => ScanOutcome::Failed(e),
+            Err(_) => ScanOutcome::TimedOut,
+        };
+
+        let closed = matches!(&outcome, ScanOutcome::Completed { rejected, .. }
+            if rejected.iter().any(|e| matches!(e, Error::Closed)));
+        if let Some(on_scan) = &on_scan {
+            on_scan(ScanReport {
+                detector: detector.name().to_string(),
+                duration: started.elapsed(),
+                outcome,
+            });
+        }
+        if closed {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{client::AnticheatClient, types::Severity};
+
+    struct Sleepy {
+        scan_time: Duration,
+    }
+
+    #[async_trait]
+    impl Detector for Sleepy {
+        fn name(&self) -> &str {
+            "sleepy"
+        }
+
+        fn interval(&self) -> Duration {
+            Duration::from_secs(10)
+        }
+
+        fn timeout(&self) -> Duration {
+            Duration::from_secs(1)
+        }
+
+        async fn scan(&self) -> Result<Vec<DetectionEvent>, ScanError> {
+            tokio::time::sleep(self.scan_time).await;
+            Ok(vec![DetectionEvent::new("sleepy", Severity::Low)])
+        }
+    }
+
+    async fn run_once(scan_time: Duration) -> (ScanReport, u64) {
+        let client = AnticheatClient::builder()
+            .base_url("http://127.0.0.1:9")
+            .api_key("org_test_agent_key")
+            .build()
+            .unwrap();
+        let batcher = Batcher::builder(client).max_batch_age(Duration::from_secs(3600)).spawn();
+        let (reports, mut received) = tokio::sync::mpsc::unbounded_channel();
+        let scheduler = Scheduler::builder(&batcher)
+            .register(Sleepy { scan_time })
+            .on_scan(move |report| {
+                let _ = reports.send(report);
+            })
+            .spawn();
+
+        let report = received.recv().await.expect("scan report");
+        let scans = scheduler.scan_stats().scan_count();
+        scheduler.shutdown().await;
+        (report, scans)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn scans_past_their_timeout_are_cancelled() {
+        let (report, scans) = run_once(Duration::from_secs(5)).await;
+        assert!(matches!(report.outcome, ScanOutcome::TimedOut));
+        assert_eq!(report.detector, "sleepy");
+        assert_eq!(report.duration, Duration::from_secs(1));
+        assert_eq!(scans, 0, "a timed out scan is not counted as completed");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn scans_within_their_timeout_queue_their_events() {
+        let (report, scans) = run_once(Duration::from_millis(500)).await;
+        assert!(matches!(report.outcome, ScanOutcome::Completed { events: 1, ref rejected } if rejected.is_empty()));
+        assert_eq!(scans, 1);
+    }
+}
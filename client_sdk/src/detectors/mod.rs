@@ -0,0 +1,4 @@
+//! First-party [`Detector`](crate::Detector) implementations.
+
+#[cfg(target_os = "linux")]
+pub mod process;
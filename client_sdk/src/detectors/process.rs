@@ -0,0 +1,622 @@
+//! Linux process and module scanner.
+//!
+//! Walks `/proc` to list running processes (name, executable, command line) and,
+//! when a game PID is set, the shared objects mapped into it. Each is matched
+//! against a [`Signature`] list by name, path glob or SHA-256 of the binary, and
+//! every hit becomes a `suspicious_process` event. A hit is reported once per
+//! process and signature rather than on every scan.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Read},
+    os::unix::fs::MetadataExt,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use crate::{
+    detector::{Detector, ScanError},
+    types::{DetectionEvent, Severity},
+};
+
+const EVENT_TYPE: &str = "suspicious_process";
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(30);
+const DEFAULT_MAX_HASH_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Something known to be a cheat, matched by any of its names, globs or hashes
+#[derive(Debug, Clone, Deserialize)]
+pub struct Signature {
+    /// Human-readable label, e.g. "Cheat Engine"
+    pub name: String,
+    /// Process names or binary file names, compared case-insensitively
+    #[serde(default)]
+    pub process_names: Vec<String>,
+    /// Globs over the full path of executables and mapped objects; `*` and `?` also match `/`
+    #[serde(default)]
+    pub path_globs: Vec<String>,
+    /// Lowercase hex SHA-256 of the binary
+    #[serde(default)]
+    pub sha256: Vec<String>,
+    #[serde(default = "default_severity")]
+    pub severity: Severity,
+}
+
+fn default_severity() -> Severity {
+    Severity::High
+}
+
+impl Signature {
+    /// Parses a JSON array of signatures
+    pub fn from_json(json: &str) -> serde_json::Result<Vec<Signature>> {
+        serde_json::from_str(json)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum MatchKind {
+    Sha256,
+    Path,
+    Name,
+}
+
+impl MatchKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            MatchKind::Sha256 => "sha256",
+            MatchKind::Path => "path",
+            MatchKind::Name => "name",
+        }
+    }
+
+    /// A hash match is conclusive; names are the easiest to spoof
+    fn confidence(self) -> f64 {
+        match self {
+            MatchKind::Sha256 => 1.0,
+            MatchKind::Path => 0.9,
+            MatchKind::Name => 0.75,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ProcessInfo {
+    pid: u32,
+    name: String,
+    exe: Option<PathBuf>,
+    cmdline: String,
+}
+
+/// Detector scanning `/proc` for processes and game modules matching known signatures
+pub struct ProcessScanner {
+    inner: Arc<Inner>,
+    options: ScanOptions,
+    interval: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ScanOptions {
+    target_pid: Option<u32>,
+    max_hash_bytes: u64,
+}
+
+struct Inner {
+    signatures: Vec<Signature>,
+    /// Where the procfs is mounted, `/proc` outside of tests
+    proc_root: PathBuf,
+    hashes: Mutex<HashCache>,
+    /// (pid, module path or empty for the process itself, signature index) already reported
+    reported: Mutex<HashSet<(u32, String, usize)>>,
+}
+
+/// Identifies a binary by (device, inode, mtime, size) so unchanged files are hashed once
+type FileKey = (u64, u64, i64, u64);
+
+/// Binary hashes, kept only for files seen in the latest scan
+type HashCache = HashMap<FileKey, String>;
+
+impl ProcessScanner {
+    pub fn new(signatures: Vec<Signature>) -> Self {
+        Self::with_proc_root(signatures, "/proc")
+    }
+
+    fn with_proc_root(signatures: Vec<Signature>, proc_root: impl Into<PathBuf>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                signatures,
+                proc_root: proc_root.into(),
+                hashes: Mutex::new(HashMap::new()),
+                reported: Mutex::new(HashSet::new()),
+            }),
+            options: ScanOptions {
+                target_pid: None,
+                max_hash_bytes: DEFAULT_MAX_HASH_BYTES,
+            },
+            interval: DEFAULT_INTERVAL,
+        }
+    }
+
+    /// Also scans the shared objects mapped into the game process
+    pub fn target_pid(mut self, pid: u32) -> Self {
+        self.options.target_pid = Some(pid);
+        self
+    }
+
+    /// Binaries larger than this are never hashed (default 64 MiB)
+    pub fn max_hash_bytes(mut self, bytes: u64) -> Self {
+        self.options.max_hash_bytes = bytes;
+        self
+    }
+
+    /// Time between scans (default 30s)
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+#[async_trait]
+impl Detector for ProcessScanner {
+    fn name(&self) -> &str {
+        "process_scan"
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    async fn scan(&self) -> Result<Vec<DetectionEvent>, ScanError> {
+        let inner = self.inner.clone();
+        let options = self.options;
+        // Reading /proc and hashing binaries is blocking file I/O
+        Ok(tokio::task::spawn_blocking(move || inner.scan(options)).await??)
+    }
+}
+
+impl Inner {
+    fn scan(&self, options: ScanOptions) -> io::Result<Vec<DetectionEvent>> {
+        let processes = list_processes(&self.proc_root)?;
+        let mut events = Vec::new();
+        let mut seen = HashSet::new();
+        let mut hashed = HashSet::new();
+
+        for process in &processes {
+            let exe = self.proc_root.join(process.pid.to_string()).join("exe");
+            let hash = self.hash_if_needed(&exe, options.max_hash_bytes, &mut hashed);
+            for (index, kind) in self.match_binary(&process.name, process.exe.as_deref(), hash.as_deref()) {
+                let key = (process.pid, String::new(), index);
+                seen.insert(key.clone());
+                if self.newly_reported(key) {
+                    events.push(self.process_event(process, index, kind, hash.as_deref()));
+                }
+            }
+        }
+
+        let mut unscanned_pid = None;
+        if let Some(target) = options.target_pid.and_then(|pid| processes.iter().find(|p| p.pid == pid)) {
+            // Like an inaccessible process, a game whose maps cannot be read is skipped
+            // rather than failing the process scan
+            let modules = mapped_modules(&self.proc_root, target.pid).unwrap_or_else(|e| {
+                tracing::warn!("Skipping module scan: pid={}, error={}", target.pid, e);
+                unscanned_pid = Some(target.pid);
+                Vec::new()
+            });
+            for module in modules {
+                let file_name = module.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                let hash = self.hash_if_needed(&module, options.max_hash_bytes, &mut hashed);
+                for (index, kind) in self.match_binary(file_name, Some(&module), hash.as_deref()) {
+                    let key = (target.pid, module.display().to_string(), index);
+                    seen.insert(key.clone());
+                    if self.newly_reported(key) {
+                        events.push(self.module_event(target, &module, index, kind, hash.as_deref()));
+                    }
+                }
+            }
+        }
+
+        // Forget hits that are gone so they are reported again if they come back; module
+        // hits of a game whose maps could not be read are kept, as nothing is known about them
+        self.reported
+            .lock()
+            .expect("reported set lock poisoned")
+            .retain(|key| seen.contains(key) || (Some(key.0) == unscanned_pid && !key.1.is_empty()));
+        self.hashes.lock().expect("hash cache lock poisoned").retain(|key, _| hashed.contains(key));
+        Ok(events)
+    }
+
+    /// Strongest match per signature
+    fn match_binary(&self, name: &str, path: Option<&Path>, hash: Option<&str>) -> Vec<(usize, MatchKind)> {
+        let path = path.map(|p| p.to_string_lossy());
+        let file_name = path.as_deref().and_then(|p| p.rsplit('/').next());
+
+        self.signatures
+            .iter()
+            .enumerate()
+            .filter_map(|(index, signature)| {
+                let kind = if hash.is_some_and(|h| signature.sha256.iter().any(|s| s.eq_ignore_ascii_case(h))) {
+                    MatchKind::Sha256
+                } else if path.as_deref().is_some_and(|p| signature.path_globs.iter().any(|g| glob_match(g, p))) {
+                    MatchKind::Path
+                } else if signature.process_names.iter().any(|n| {
+                    n.eq_ignore_ascii_case(name) || file_name.is_some_and(|f| n.eq_ignore_ascii_case(f))
+                }) {
+                    MatchKind::Name
+                } else {
+                    return None;
+                };
+                Some((index, kind))
+            })
+            .collect()
+    }
+
+    fn newly_reported(&self, key: (u32, String, usize)) -> bool {
+        self.reported.lock().expect("reported set lock poisoned").insert(key)
+    }
+
+    /// Hashes the file only when some signature carries hashes; unreadable files yield `None`.
+    /// The file's cache key is added to `hashed`.
+    fn hash_if_needed(&self, path: &Path, max_bytes: u64, hashed: &mut HashSet<FileKey>) -> Option<String> {
+        if self.signatures.iter().all(|s| s.sha256.is_empty()) {
+            return None;
+        }
+        let meta = fs::metadata(path).ok()?;
+        if meta.len() > max_bytes {
+            return None;
+        }
+        let key = (meta.dev(), meta.ino(), meta.mtime(), meta.len());
+        hashed.insert(key);
+        if let Some(hash) = self.hashes.lock().expect("hash cache lock poisoned").get(&key) {
+            return Some(hash.clone());
+        }
+
+        let hash = sha256_file(path).ok()?;
+        self.hashes
+            .lock()
+            .expect("hash cache lock poisoned")
+            .insert(key, hash.clone());
+        Some(hash)
+    }
+
+    fn process_event(&self, process: &ProcessInfo, index: usize, kind: MatchKind, hash: Option<&str>) -> DetectionEvent {
+        let signature = &self.signatures[index];
+        DetectionEvent::new(EVENT_TYPE, signature.severity)
+            .title(format!("Suspicious Process Detected: {}", signature.name))
+            .description(format!(
+                "Process {} (pid {}) matched signature \"{}\" by {}",
+                process.name, process.pid, signature.name, kind.as_str()
+            ))
+            .metadata(json!({
+                "process": process.name,
+                "confidence": kind.confidence(),
+                "pid": process.pid,
+                "exe": process.exe,
+                "cmdline": process.cmdline,
+                "sha256": hash,
+                "signature": signature.name,
+                "matched_by": kind.as_str(),
+            }))
+    }
+
+    fn module_event(
+        &self,
+        process: &ProcessInfo,
+        module: &Path,
+        index: usize,
+        kind: MatchKind,
+        hash: Option<&str>,
+    ) -> DetectionEvent {
+        let signature = &self.signatures[index];
+        DetectionEvent::new(EVENT_TYPE, signature.severity)
+            .title(format!("Suspicious Module Loaded: {}", signature.name))
+            .description(format!(
+                "{} loaded into {} (pid {}) matched signature \"{}\" by {}",
+                module.display(), process.name, process.pid, signature.name, kind.as_str()
+            ))
+            .metadata(json!({
+                "process": process.name,
+                "confidence": kind.confidence(),
+                "pid": process.pid,
+                "module": module,
+                "sha256": hash,
+                "signature": signature.name,
+                "matched_by": kind.as_str(),
+            }))
+    }
+}
+
+/// Processes that vanish or deny access mid-scan are skipped
+fn list_processes(proc_root: &Path) -> io::Result<Vec<ProcessInfo>> {
+    let mut processes = Vec::new();
+    for entry in fs::read_dir(proc_root)? {
+        let entry = entry?;
+        let Some(pid) = entry.file_name().to_str().and_then(|n| n.parse::<u32>().ok()) else {
+            continue;
+        };
+        let dir = entry.path();
+        let Ok(name) = fs::read_to_string(dir.join("comm")) else {
+            continue;
+        };
+        let cmdline = fs::read(dir.join("cmdline"))
+            .map(|raw| {
+                raw.split(|b| *b == 0)
+                    .filter(|arg| !arg.is_empty())
+                    .map(String::from_utf8_lossy)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .unwrap_or_default();
+
+        processes.push(ProcessInfo {
+            pid,
+            name: name.trim_end().to_string(),
+            exe: fs::read_link(dir.join("exe")).ok(),
+            cmdline,
+        });
+    }
+    Ok(processes)
+}
+
+/// Distinct file-backed mappings from `/proc/<pid>/maps`
+fn mapped_modules(proc_root: &Path, pid: u32) -> io::Result<Vec<PathBuf>> {
+    let maps = match fs::read_to_string(proc_root.join(pid.to_string()).join("maps")) {
+        Ok(maps) => maps,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut modules: Vec<PathBuf> = maps
+        .lines()
+        // address perms offset dev inode pathname; the pathname may contain spaces
+        .filter_map(|line| line.splitn(6, char::is_whitespace).nth(5))
+        .map(str::trim)
+        .filter(|path| path.starts_with('/'))
+        .map(|path| PathBuf::from(path.trim_end_matches(" (deleted)")))
+        .collect();
+    modules.sort();
+    modules.dedup();
+    Ok(modules)
+}
+
+fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Wildcard match where `*` is any run of characters and `?` a single one
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                // Let the last `*` swallow one more character and retry
+                Some((star, star_t)) => {
+                    p = star + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHEAT_BYTES: &[u8] = b"cheat binary";
+
+    fn signature(name: &str) -> Signature {
+        Signature {
+            name: name.to_string(),
+            process_names: Vec::new(),
+            path_globs: Vec::new(),
+            sha256: Vec::new(),
+            severity: Severity::High,
+        }
+    }
+
+    fn sha256_of(bytes: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(bytes))
+    }
+
+    /// A fake procfs plus a directory for the binaries its processes run
+    struct FakeProc {
+        root: PathBuf,
+    }
+
+    impl FakeProc {
+        fn new() -> Self {
+            let root = std::env::temp_dir().join(format!("process-scan-test-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(root.join("proc")).unwrap();
+            fs::create_dir_all(root.join("bin")).unwrap();
+            Self { root }
+        }
+
+        fn proc_root(&self) -> PathBuf {
+            self.root.join("proc")
+        }
+
+        fn binary(&self, name: &str, contents: &[u8]) -> PathBuf {
+            let path = self.root.join("bin").join(name);
+            fs::write(&path, contents).unwrap();
+            path
+        }
+
+        fn spawn(&self, pid: u32, comm: &str, exe: &Path, args: &[&str]) {
+            let dir = self.proc_root().join(pid.to_string());
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("comm"), format!("{}\n", comm)).unwrap();
+            fs::write(dir.join("cmdline"), args.iter().map(|arg| format!("{}\0", arg)).collect::<String>()).unwrap();
+            std::os::unix::fs::symlink(exe, dir.join("exe")).unwrap();
+        }
+
+        fn map(&self, pid: u32, modules: &[&Path]) {
+            let maps: String = modules
+                .iter()
+                .map(|module| format!("7f0000000000-7f0000001000 r-xp 00000000 08:01 1234 {}\n", module.display()))
+                .collect();
+            fs::write(self.proc_root().join(pid.to_string()).join("maps"), maps).unwrap();
+        }
+
+        fn kill(&self, pid: u32) {
+            fs::remove_dir_all(self.proc_root().join(pid.to_string())).unwrap();
+        }
+    }
+
+    impl Drop for FakeProc {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    fn file_key(path: &Path) -> FileKey {
+        let meta = fs::metadata(path).unwrap();
+        (meta.dev(), meta.ino(), meta.mtime(), meta.len())
+    }
+
+    #[test]
+    fn scan_drops_hashes_of_files_no_longer_running() {
+        let proc = FakeProc::new();
+        let first = proc.binary("first", b"first");
+        let second = proc.binary("second", b"second binary");
+        let mut hashed = signature("Test");
+        hashed.sha256 = vec!["0".repeat(64)];
+        let scanner = ProcessScanner::with_proc_root(vec![hashed], proc.proc_root());
+
+        proc.spawn(100, "first", &first, &["first"]);
+        scanner.inner.scan(scanner.options).expect("scan");
+        assert_eq!(
+            scanner.inner.hashes.lock().unwrap().get(&file_key(&first)),
+            Some(&sha256_of(b"first"))
+        );
+
+        proc.kill(100);
+        proc.spawn(200, "second", &second, &["second"]);
+        scanner.inner.scan(scanner.options).expect("scan");
+        let hashes = scanner.inner.hashes.lock().unwrap();
+        assert_eq!(hashes.len(), 1);
+        assert!(hashes.contains_key(&file_key(&second)));
+    }
+
+    #[test]
+    fn match_binary_prefers_hash_over_path_over_name() {
+        let mut by_hash = signature("Hash");
+        by_hash.sha256 = vec![sha256_of(CHEAT_BYTES).to_uppercase()];
+        by_hash.process_names = vec!["cheat".to_string()];
+        let mut by_path = signature("Path");
+        by_path.path_globs = vec!["*/tools/*".to_string()];
+        by_path.process_names = vec!["cheat".to_string()];
+        let mut by_name = signature("Name");
+        by_name.process_names = vec!["CheatEngine".to_string()];
+        let scanner = ProcessScanner::new(vec![by_hash, by_path, by_name]);
+        let inner = &scanner.inner;
+
+        let kinds = |name, path: Option<&str>, hash: Option<&str>| -> Vec<(usize, &'static str)> {
+            inner
+                .match_binary(name, path.map(Path::new), hash)
+                .into_iter()
+                .map(|(index, kind)| (index, kind.as_str()))
+                .collect()
+        };
+        let hash = sha256_of(CHEAT_BYTES);
+
+        assert_eq!(
+            kinds("cheat", Some("/opt/tools/cheat"), Some(&hash)),
+            vec![(0, "sha256"), (1, "path")]
+        );
+        assert_eq!(kinds("cheat", Some("/usr/bin/cheat"), None), vec![(0, "name"), (1, "name")]);
+        // Names compare case-insensitively, against the process name or the binary's file name
+        assert_eq!(kinds("cheatengine", None, None), vec![(2, "name")]);
+        assert_eq!(kinds("renamed", Some("/home/user/CHEATENGINE"), None), vec![(2, "name")]);
+        assert!(kinds("game", Some("/usr/bin/game"), Some(&"0".repeat(64))).is_empty());
+    }
+
+    #[test]
+    fn process_hits_become_suspicious_process_events_once() {
+        let proc = FakeProc::new();
+        let exe = proc.binary("ce", CHEAT_BYTES);
+        proc.spawn(42, "CheatEngine", &exe, &["ce", "--attach", "game"]);
+        let mut cheat = signature("Cheat Engine");
+        cheat.sha256 = vec![sha256_of(CHEAT_BYTES)];
+        cheat.severity = Severity::Critical;
+        let scanner = ProcessScanner::with_proc_root(vec![cheat], proc.proc_root());
+
+        let events = scanner.inner.scan(scanner.options).expect("scan");
+        assert_eq!(events.len(), 1);
+        let event = &events[0];
+        assert_eq!(event.event_type, "suspicious_process");
+        assert_eq!(event.severity, Severity::Critical);
+        assert_eq!(
+            event.metadata,
+            json!({
+                "process": "CheatEngine",
+                "confidence": 1.0,
+                "pid": 42,
+                "exe": exe,
+                "cmdline": "ce --attach game",
+                "sha256": sha256_of(CHEAT_BYTES),
+                "signature": "Cheat Engine",
+                "matched_by": "sha256",
+            })
+        );
+
+        assert!(scanner.inner.scan(scanner.options).expect("scan").is_empty(), "a hit is reported once");
+        proc.kill(42);
+        scanner.inner.scan(scanner.options).expect("scan");
+        proc.spawn(42, "CheatEngine", &exe, &["ce"]);
+        assert_eq!(scanner.inner.scan(scanner.options).expect("scan").len(), 1, "a hit that comes back is reported again");
+    }
+
+    #[test]
+    fn modules_mapped_into_the_game_are_matched() {
+        let proc = FakeProc::new();
+        let game = proc.binary("game", b"game");
+        let overlay = proc.binary("libhook.so", b"hook");
+        proc.spawn(7, "game", &game, &["game"]);
+        proc.map(7, &[&game, &overlay]);
+        let mut hook = signature("Hook");
+        hook.path_globs = vec!["*/libhook.so".to_string()];
+        let scanner = ProcessScanner::with_proc_root(vec![hook], proc.proc_root()).target_pid(7);
+
+        let events = scanner.inner.scan(scanner.options).expect("scan");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event_type, "suspicious_process");
+        assert_eq!(events[0].metadata["module"], json!(overlay));
+        assert_eq!(events[0].metadata["matched_by"], "path");
+        assert_eq!(events[0].metadata["pid"], 7);
+    }
+
+    #[test]
+    fn glob_wildcards_span_path_separators() {
+        assert!(glob_match("*/cheatengine*", "/opt/tools/cheatengine-x86_64"));
+        assert!(glob_match("/tmp/?.so", "/tmp/a.so"));
+        assert!(!glob_match("/tmp/?.so", "/tmp/ab.so"));
+    }
+}
@@ -0,0 +1,66 @@
+//! Request body encodings for `POST /ingest/batch`.
+
+use std::io::Write;
+use serde::Serialize;
+use crate::error::{Error, Result};
+
+/// Bodies smaller than this are sent uncompressed; compression would not pay for itself
+const MIN_COMPRESS_SIZE: usize = 1024;
+
+/// Serialization of ingest batches, sent as `Content-Type`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BodyFormat {
+    #[default]
+    Json,
+    /// MessagePack with struct fields as maps; typically a third smaller than JSON
+    MessagePack,
+}
+
+/// Compression of ingest batches, sent as `Content-Encoding`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl BodyFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            BodyFormat::Json => "application/json",
+            BodyFormat::MessagePack => "application/msgpack",
+        }
+    }
+}
+
+/// A serialized and possibly compressed body with the headers describing it
+#[derive(Debug, Clone)]
+pub(crate) struct EncodedBody {
+    pub bytes: Vec<u8>,
+    pub content_type: &'static str,
+    pub content_encoding: Option<&'static str>,
+}
+
+pub(crate) fn encode<T: Serialize>(value: &T, format: BodyFormat, compression: Compression) -> Result<EncodedBody> {
+    let bytes = match format {
+        BodyFormat::Json => serde_json::to_vec(value).map_err(|e| Error::Encode(e.to_string()))?,
+        BodyFormat::MessagePack => rmp_serde::to_vec_named(value).map_err(|e| Error::Encode(e.to_string()))?,
+    };
+    let compression = if bytes.len() < MIN_COMPRESS_SIZE { Compression::None } else { compression };
+
+    let (bytes, content_encoding) = match compression {
+        Compression::None => (bytes, None),
+        Compression::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&bytes).map_err(|e| Error::Encode(e.to_string()))?;
+            (encoder.finish().map_err(|e| Error::Encode(e.to_string()))?, Some("gzip"))
+        }
+        Compression::Zstd => (
+            zstd::encode_all(&bytes[..], zstd::DEFAULT_COMPRESSION_LEVEL).map_err(|e| Error::Encode(e.to_string()))?,
+            Some("zstd"),
+        ),
+    };
+
+    Ok(EncodedBody { bytes, content_type: format.content_type(), content_encoding })
+}
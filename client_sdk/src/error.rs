@@ -0,0 +1,108 @@
+use std::time::Duration;
+use reqwest::StatusCode;
+use crate::types::IngestResponse;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("invalid client configuration: {0}")]
+    Config(String),
+
+    #[error("request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("API key rejected by the server")]
+    Unauthorized,
+
+    /// The server refused the batch with a structured ingest response
+    #[error("batch rejected with {status}: {}", .response.errors.join("; "))]
+    Rejected {
+        status: StatusCode,
+        response: Box<IngestResponse>,
+        /// Server-requested delay from `Retry-After` on 429/503
+        retry_after: Option<Duration>,
+    },
+
+    #[error("unexpected status {status}: {body}")]
+    Status {
+        status: StatusCode,
+        body: String,
+        retry_after: Option<Duration>,
+    },
+
+    /// Too many consecutive failures; requests are refused locally until the circuit closes
+    #[error("circuit breaker open, retrying in {retry_in:?}")]
+    CircuitOpen {
+        retry_in: Duration,
+    },
+
+    #[error("failed to encode request body: {0}")]
+    Encode(String),
+
+    #[error("invalid payload: {0}")]
+    Invalid(#[from] validator::ValidationErrors),
+
+    #[error("outbox I/O failed: {0}")]
+    Outbox(#[from] std::io::Error),
+
+    /// The outbox reached its size cap and dropped these undelivered events to make room
+    #[error("outbox is full; oldest undelivered events were evicted")]
+    OutboxFull,
+
+    #[error("event queue is full")]
+    QueueFull,
+
+    #[error("batcher has shut down")]
+    Closed,
+}
+
+impl Error {
+    /// Whether the same request may succeed later: network failures, 429, 5xx and an open circuit
+    pub fn is_retryable(&self) -> bool {
+        let retryable_status = |status: &StatusCode| {
+            status.is_server_error() || *status == StatusCode::TOO_MANY_REQUESTS
+        };
+        match self {
+            // Without a status only transport failures are worth repeating; a body that
+            // cannot be built or decoded fails the same way every time
+            Error::Http(e) => match e.status() {
+                Some(status) => retryable_status(&status),
+                None => e.is_connect() || e.is_timeout() || e.is_request() || e.is_body(),
+            },
+            Error::Rejected { status, .. } | Error::Status { status, .. } => retryable_status(status),
+            Error::CircuitOpen { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// The server is up but throttling this client (429)
+    pub fn is_throttled(&self) -> bool {
+        let status = match self {
+            Error::Http(e) => e.status(),
+            Error::Rejected { status, .. } | Error::Status { status, .. } => Some(*status),
+            _ => None,
+        };
+        status == Some(StatusCode::TOO_MANY_REQUESTS)
+    }
+
+    /// The server refused this client's credentials (401/403); resending fails until they are fixed
+    pub fn is_auth_failure(&self) -> bool {
+        let status = match self {
+            Error::Unauthorized => return true,
+            Error::Http(e) => e.status(),
+            Error::Rejected { status, .. } | Error::Status { status, .. } => Some(*status),
+            _ => None,
+        };
+        status.is_some_and(|status| status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN)
+    }
+
+    /// How long the server asked the client to wait before retrying
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::Rejected { retry_after, .. } | Error::Status { retry_after, .. } => *retry_after,
+            Error::CircuitOpen { retry_in } => Some(*retry_in),
+            _ => None,
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
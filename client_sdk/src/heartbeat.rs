@@ -0,0 +1,126 @@
+//! Heartbeat collection: the agent's own resource usage plus scan progress.
+//!
+//! CPU and memory come from `/proc/self` on Linux and are left empty elsewhere.
+//! CPU usage is averaged since the previous heartbeat and normalised over all
+//! cores, so it stays within the 0-100 range the backend validates.
+
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Mutex,
+};
+use crate::{detector::ScanStats, types::AgentHeartbeat};
+
+/// Builds a fresh [`AgentHeartbeat`] each time the batcher sends
+pub struct HeartbeatSource {
+    agent_version: String,
+    platform: String,
+    scan_stats: Option<ScanStats>,
+    /// 0 means no config has been applied yet
+    config_version: AtomicU64,
+    cpu: Mutex<Option<CpuSample>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct CpuSample {
+    at: std::time::Instant,
+    cpu_secs: f64,
+}
+
+impl HeartbeatSource {
+    pub fn new(agent_version: impl Into<String>, platform: impl Into<String>) -> Self {
+        Self {
+            agent_version: agent_version.into(),
+            platform: platform.into(),
+            scan_stats: None,
+            config_version: AtomicU64::new(0),
+            cpu: Mutex::new(None),
+        }
+    }
+
+    /// Reports `scan_count` and `last_scan_at` from the scheduler sharing these stats
+    pub fn scan_stats(mut self, stats: ScanStats) -> Self {
+        self.scan_stats = Some(stats);
+        self
+    }
+
+    /// Records the config version the agent applied, reported so the server can push updates
+    pub fn set_config_version(&self, version: u64) {
+        self.config_version.store(version, Ordering::Relaxed);
+    }
+
+    pub fn collect(&self) -> AgentHeartbeat {
+        let mut heartbeat = AgentHeartbeat::new(self.agent_version.clone(), self.platform.clone());
+        heartbeat.cpu_usage = self.cpu_usage();
+        heartbeat.memory_usage = memory_usage();
+        if let Some(stats) = &self.scan_stats {
+            heartbeat.scan_count = Some(stats.scan_count());
+            heartbeat.last_scan_at = stats.last_scan_at();
+        }
+        heartbeat.config_version = match self.config_version.load(Ordering::Relaxed) {
+            0 => None,
+            version => Some(version),
+        };
+        heartbeat
+    }
+
+    /// `None` on the first call, which only establishes the baseline
+    fn cpu_usage(&self) -> Option<f32> {
+        let now = CpuSample {
+            at: std::time::Instant::now(),
+            cpu_secs: process_cpu_secs()?,
+        };
+        let previous = self.cpu.lock().expect("cpu sample lock poisoned").replace(now)?;
+
+        let wall = now.at.duration_since(previous.at).as_secs_f64();
+        if wall <= 0.0 {
+            return None;
+        }
+        let cores = std::thread::available_parallelism().map_or(1, |n| n.get()) as f64;
+        let percent = (now.cpu_secs - previous.cpu_secs) / wall / cores * 100.0;
+        Some(percent.clamp(0.0, 100.0) as f32)
+    }
+}
+
+/// User plus system CPU time of this process from `/proc/self/stat`
+#[cfg(target_os = "linux")]
+fn process_cpu_secs() -> Option<f64> {
+    // USER_HZ, fixed at 100 by the kernel ABI on every mainstream architecture
+    const TICKS_PER_SEC: f64 = 100.0;
+
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    // The command name may contain spaces, so fields are counted after its closing paren
+    let fields: Vec<&str> = stat.rsplit_once(')')?.1.split_whitespace().collect();
+    let utime: f64 = fields.get(11)?.parse().ok()?;
+    let stime: f64 = fields.get(12)?.parse().ok()?;
+    Some((utime + stime) / TICKS_PER_SEC)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn process_cpu_secs() -> Option<f64> {
+    None
+}
+
+/// Resident set size as a percentage of total memory
+#[cfg(target_os = "linux")]
+fn memory_usage() -> Option<f32> {
+    let kib = |contents: &str, key: &str| -> Option<f64> {
+        contents
+            .lines()
+            .find_map(|line| line.strip_prefix(key))?
+            .split_whitespace()
+            .next()?
+            .parse()
+            .ok()
+    };
+    let rss = kib(&std::fs::read_to_string("/proc/self/status").ok()?, "VmRSS:")?;
+    let total = kib(&std::fs::read_to_string("/proc/meminfo").ok()?, "MemTotal:")?;
+    if total <= 0.0 {
+        return None;
+    }
+    Some((rss / total * 100.0).clamp(0.0, 100.0) as f32)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn memory_usage() -> Option<f32> {
+    None
+}
@@ -0,0 +1,42 @@
+//! Client SDK for the Anticheat ingest API.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), client_sdk::Error> {
+//! use client_sdk::{AnticheatClient, DetectionEvent, IngestBatchRequest, Severity};
+//!
+//! let client = AnticheatClient::builder()
+//!     .base_url("http://localhost:3000")
+//!     .api_key("org_...")
+//!     .build()?;
+//!
+//! let response = client
+//!     .ingest_batch(&IngestBatchRequest::new(vec![DetectionEvent::new("memory_scan", Severity::Low)]))
+//!     .await?;
+//! println!("processed {}", response.processed);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod batcher;
+pub mod client;
+pub mod detector;
+pub mod detectors;
+pub mod encoding;
+pub mod error;
+pub mod heartbeat;
+pub mod outbox;
+pub mod retry;
+pub mod types;
+
+pub use batcher::{BatchReport, Batcher, BatcherBuilder, EventSender};
+pub use client::{AnticheatClient, AnticheatClientBuilder};
+pub use detector::{Detector, ScanError, ScanOutcome, ScanReport, ScanStats, Scheduler, SchedulerBuilder};
+pub use encoding::{BodyFormat, Compression};
+pub use error::Error;
+pub use heartbeat::HeartbeatSource;
+pub use outbox::{Outbox, OutboxConfig};
+pub use retry::{CircuitBreakerConfig, RetryCounters, RetryMetrics, RetryPolicy};
+pub use types::{
+    AgentConfigBody, AgentHeartbeat, DetectionEvent, IngestBatchRequest, IngestResponse, LineError, ResolvedConfig,
+    Severity, StreamIngestResponse,
+};
@@ -0,0 +1,409 @@
+//! Disk-backed queue of events waiting to be delivered.
+//!
+//! Events are appended as NDJSON lines to numbered segment files
+//! (`segment-<seq>.ndjson`). A `cursor` file records how far the oldest
+//! segment has been delivered, so replay resumes in order after a restart.
+//! When the queue outgrows its size cap, whole segments are evicted oldest first.
+
+use std::{
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+use crate::types::DetectionEvent;
+
+const SEGMENT_PREFIX: &str = "segment-";
+const SEGMENT_SUFFIX: &str = ".ndjson";
+const CURSOR_FILE: &str = "cursor";
+
+#[derive(Debug, Clone)]
+pub struct OutboxConfig {
+    pub dir: PathBuf,
+    /// A new segment is started once the current one reaches this size (default 4 MiB)
+    pub segment_max_bytes: u64,
+    /// Oldest segments are evicted beyond this total size (default 256 MiB)
+    pub max_total_bytes: u64,
+}
+
+impl OutboxConfig {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            segment_max_bytes: 4 * 1024 * 1024,
+            max_total_bytes: 256 * 1024 * 1024,
+        }
+    }
+}
+
+/// Position just past the last delivered event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutboxPosition {
+    segment: u64,
+    offset: u64,
+}
+
+/// Events read from the head of the outbox, to be acknowledged once delivered
+#[derive(Debug)]
+pub struct OutboxBatch {
+    pub events: Vec<DetectionEvent>,
+    pub end: OutboxPosition,
+}
+
+#[derive(Debug)]
+struct Segment {
+    seq: u64,
+    len: u64,
+}
+
+pub struct Outbox {
+    config: OutboxConfig,
+    segments: VecDeque<Segment>,
+    cursor: OutboxPosition,
+    writer: Option<File>,
+}
+
+impl Outbox {
+    /// Opens (or creates) the outbox directory and resumes from the persisted cursor
+    pub fn open(config: OutboxConfig) -> io::Result<Self> {
+        fs::create_dir_all(&config.dir)?;
+
+        let mut segments = Vec::new();
+        for entry in fs::read_dir(&config.dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let Some(seq) = name
+                .to_str()
+                .and_then(|n| n.strip_prefix(SEGMENT_PREFIX))
+                .and_then(|n| n.strip_suffix(SEGMENT_SUFFIX))
+                .and_then(|n| n.parse().ok())
+            else {
+                continue;
+            };
+            segments.push(Segment { seq, len: entry.metadata()?.len() });
+        }
+        segments.sort_by_key(|s| s.seq);
+
+        let cursor = read_cursor(&config.dir)?.unwrap_or(OutboxPosition {
+            segment: segments.first().map_or(0, |s| s.seq),
+            offset: 0,
+        });
+
+        let mut outbox = Self {
+            config,
+            segments: segments.into(),
+            cursor,
+            writer: None,
+        };
+        // Segments the cursor has moved past were delivered before a crash
+        while outbox.segments.front().is_some_and(|s| s.seq < outbox.cursor.segment) {
+            outbox.remove_head()?;
+        }
+        Ok(outbox)
+    }
+
+    /// True when every stored event has been delivered
+    pub fn is_empty(&self) -> bool {
+        match self.segments.back() {
+            None => true,
+            Some(last) => self.cursor.segment > last.seq
+                || (self.cursor.segment == last.seq && self.cursor.offset >= last.len),
+        }
+    }
+
+    /// Bytes on disk, including delivered events of the head segment
+    pub fn size_bytes(&self) -> u64 {
+        self.segments.iter().map(|s| s.len).sum()
+    }
+
+    /// Durably appends events, evicting the oldest segments when over the size cap.
+    /// Returns the undelivered events that were evicted to make room.
+    pub fn append(&mut self, events: &[DetectionEvent]) -> io::Result<Vec<DetectionEvent>> {
+        if events.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut buf = Vec::new();
+        for event in events {
+            serde_json::to_writer(&mut buf, event)?;
+            buf.push(b'\n');
+        }
+
+        let rotate = match (&self.writer, self.segments.back()) {
+            (Some(_), Some(last)) => last.len >= self.config.segment_max_bytes,
+            _ => true,
+        };
+        if rotate {
+            self.start_segment()?;
+        }
+
+        let writer = self.writer.as_mut().expect("writer opened by start_segment");
+        writer.write_all(&buf)?;
+        writer.sync_data()?;
+        if let Some(last) = self.segments.back_mut() {
+            last.len += buf.len() as u64;
+        }
+
+        self.evict()
+    }
+
+    /// Reads up to `max` undelivered events from the head segment without consuming them
+    pub fn peek(&mut self, max: usize) -> io::Result<Option<OutboxBatch>> {
+        loop {
+            let Some(head) = self.segments.front() else {
+                return Ok(None);
+            };
+            let head_seq = head.seq;
+            let is_last = self.segments.len() == 1;
+
+            if self.cursor.segment < head_seq {
+                self.cursor = OutboxPosition { segment: head_seq, offset: 0 };
+            }
+
+            let (events, offset) = read_events(&self.segment_path(head_seq), self.cursor.offset, max)?;
+            let end = OutboxPosition { segment: head_seq, offset };
+            if !events.is_empty() {
+                return Ok(Some(OutboxBatch { events, end }));
+            }
+            if is_last {
+                return Ok(None);
+            }
+            // Head segment fully consumed (or only unreadable lines left): move on
+            self.remove_head()?;
+        }
+    }
+
+    /// Marks everything up to `end` as delivered
+    pub fn ack(&mut self, end: OutboxPosition) -> io::Result<()> {
+        self.cursor = end;
+        write_cursor(&self.config.dir, end)?;
+
+        let head_done = self.segments.front().is_some_and(|head| {
+            head.seq == end.segment && end.offset >= head.len && self.segments.len() > 1
+        });
+        if head_done {
+            self.remove_head()?;
+        }
+        Ok(())
+    }
+
+    fn start_segment(&mut self) -> io::Result<()> {
+        let seq = match self.segments.back() {
+            Some(last) => last.seq + 1,
+            None => {
+                // Nothing pending: restart the cursor at the fresh segment
+                let seq = self.cursor.segment + 1;
+                self.cursor = OutboxPosition { segment: seq, offset: 0 };
+                write_cursor(&self.config.dir, self.cursor)?;
+                seq
+            }
+        };
+        let file = OpenOptions::new()
+            .create_new(true)
+            .append(true)
+            .open(self.segment_path(seq))?;
+        self.segments.push_back(Segment { seq, len: 0 });
+        self.writer = Some(file);
+        Ok(())
+    }
+
+    fn evict(&mut self) -> io::Result<Vec<DetectionEvent>> {
+        let mut evicted = Vec::new();
+        while self.size_bytes() > self.config.max_total_bytes && self.segments.len() > 1 {
+            if let Some(head) = self.segments.front().filter(|head| head.seq >= self.cursor.segment) {
+                let offset = if head.seq == self.cursor.segment { self.cursor.offset } else { 0 };
+                // An unreadable segment cannot be listed, only dropped
+                if let Ok((events, _)) = read_events(&self.segment_path(head.seq), offset, usize::MAX) {
+                    evicted.extend(events);
+                }
+            }
+            self.remove_head()?;
+        }
+        Ok(evicted)
+    }
+
+    fn remove_head(&mut self) -> io::Result<()> {
+        if let Some(head) = self.segments.pop_front() {
+            match fs::remove_file(self.segment_path(head.seq)) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+            if self.cursor.segment <= head.seq {
+                let next = self.segments.front().map_or(head.seq + 1, |s| s.seq);
+                self.cursor = OutboxPosition { segment: next, offset: 0 };
+                write_cursor(&self.config.dir, self.cursor)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn segment_path(&self, seq: u64) -> PathBuf {
+        self.config.dir.join(format!("{}{:020}{}", SEGMENT_PREFIX, seq, SEGMENT_SUFFIX))
+    }
+}
+
+/// Up to `max` complete events from `offset` on, and the offset just past the last line read
+fn read_events(path: &Path, offset: u64, max: usize) -> io::Result<(Vec<DetectionEvent>, u64)> {
+    let mut reader = BufReader::new(File::open(path)?);
+    reader.seek(SeekFrom::Start(offset))?;
+
+    let mut events = Vec::new();
+    let mut offset = offset;
+    let mut line = String::new();
+    while events.len() < max {
+        line.clear();
+        let read = reader.read_line(&mut line)?;
+        // A missing newline means the write was cut short; never replay it
+        if read == 0 || !line.ends_with('\n') {
+            break;
+        }
+        offset += read as u64;
+        // Lines that no longer parse are skipped rather than blocking the queue
+        if let Ok(event) = serde_json::from_str(line.trim_end()) {
+            events.push(event);
+        }
+    }
+    Ok((events, offset))
+}
+
+fn read_cursor(dir: &Path) -> io::Result<Option<OutboxPosition>> {
+    let contents = match fs::read_to_string(dir.join(CURSOR_FILE)) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut parts = contents.split_whitespace().map(str::parse::<u64>);
+    match (parts.next(), parts.next()) {
+        (Some(Ok(segment)), Some(Ok(offset))) => Ok(Some(OutboxPosition { segment, offset })),
+        _ => Ok(None),
+    }
+}
+
+/// Writes the cursor via a temp file and rename so a crash never leaves it half-written
+fn write_cursor(dir: &Path, cursor: OutboxPosition) -> io::Result<()> {
+    let tmp = dir.join(format!("{}.tmp", CURSOR_FILE));
+    let mut file = File::create(&tmp)?;
+    writeln!(file, "{} {}", cursor.segment, cursor.offset)?;
+    file.sync_data()?;
+    fs::rename(tmp, dir.join(CURSOR_FILE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Severity;
+
+    fn event(id: &str) -> DetectionEvent {
+        DetectionEvent::new("speed_hack", Severity::High).event_id(id)
+    }
+
+    fn ids(events: &[DetectionEvent]) -> Vec<&str> {
+        events.iter().filter_map(|e| e.event_id.as_deref()).collect()
+    }
+
+    fn config() -> OutboxConfig {
+        OutboxConfig::new(std::env::temp_dir().join(format!("outbox-test-{}", uuid::Uuid::new_v4())))
+    }
+
+    /// Every append starts a new segment, and only the newest segment fits under the cap
+    fn tiny_config() -> OutboxConfig {
+        OutboxConfig { segment_max_bytes: 1, max_total_bytes: 1, ..config() }
+    }
+
+    fn segment_files(dir: &Path) -> usize {
+        fs::read_dir(dir)
+            .unwrap()
+            .filter(|entry| entry.as_ref().unwrap().file_name().to_string_lossy().starts_with(SEGMENT_PREFIX))
+            .count()
+    }
+
+    #[test]
+    fn reopening_resumes_after_the_last_acked_event() {
+        let config = config();
+        let mut outbox = Outbox::open(config.clone()).unwrap();
+        outbox.append(&[event("a"), event("b"), event("c")]).unwrap();
+        let batch = outbox.peek(2).unwrap().unwrap();
+        assert_eq!(ids(&batch.events), ["a", "b"]);
+        outbox.ack(batch.end).unwrap();
+        drop(outbox);
+
+        let mut outbox = Outbox::open(config.clone()).unwrap();
+        assert!(!outbox.is_empty());
+        let batch = outbox.peek(10).unwrap().unwrap();
+        assert_eq!(ids(&batch.events), ["c"]);
+        outbox.ack(batch.end).unwrap();
+        assert!(outbox.is_empty());
+        fs::remove_dir_all(config.dir).unwrap();
+    }
+
+    #[test]
+    fn a_torn_last_record_is_never_replayed() {
+        let config = config();
+        let mut outbox = Outbox::open(config.clone()).unwrap();
+        outbox.append(&[event("a"), event("b")]).unwrap();
+        let segment = outbox.segment_path(outbox.segments[0].seq);
+        drop(outbox);
+        // A crash mid-write leaves a record without its newline
+        let mut file = OpenOptions::new().append(true).open(&segment).unwrap();
+        file.write_all(br#"{"event_id":"torn","event_type":"speed"#).unwrap();
+        drop(file);
+
+        let mut outbox = Outbox::open(config.clone()).unwrap();
+        let batch = outbox.peek(10).unwrap().unwrap();
+        assert_eq!(ids(&batch.events), ["a", "b"]);
+        outbox.ack(batch.end).unwrap();
+        assert!(outbox.peek(10).unwrap().is_none());
+
+        // Later events go to a new segment and are delivered past the torn one
+        outbox.append(&[event("c")]).unwrap();
+        let batch = outbox.peek(10).unwrap().unwrap();
+        assert_eq!(ids(&batch.events), ["c"]);
+        fs::remove_dir_all(config.dir).unwrap();
+    }
+
+    #[test]
+    fn oldest_segments_are_evicted_at_the_size_cap() {
+        let config = tiny_config();
+        let mut outbox = Outbox::open(config.clone()).unwrap();
+        assert!(outbox.append(&[event("a"), event("b")]).unwrap().is_empty());
+        let batch = outbox.peek(1).unwrap().unwrap();
+        outbox.ack(batch.end).unwrap();
+
+        // Only the undelivered part of an evicted segment is returned
+        let evicted = outbox.append(&[event("c")]).unwrap();
+        assert_eq!(ids(&evicted), ["b"]);
+        let evicted = outbox.append(&[event("d")]).unwrap();
+        assert_eq!(ids(&evicted), ["c"]);
+
+        assert_eq!(segment_files(&config.dir), 1);
+        let batch = outbox.peek(10).unwrap().unwrap();
+        assert_eq!(ids(&batch.events), ["d"]);
+        fs::remove_dir_all(config.dir).unwrap();
+    }
+
+    #[test]
+    fn fully_acked_segments_are_deleted() {
+        let config = OutboxConfig { segment_max_bytes: 1, ..config() };
+        let mut outbox = Outbox::open(config.clone()).unwrap();
+        outbox.append(&[event("a")]).unwrap();
+        outbox.append(&[event("b")]).unwrap();
+        assert_eq!(segment_files(&config.dir), 2);
+
+        let batch = outbox.peek(10).unwrap().unwrap();
+        assert_eq!(ids(&batch.events), ["a"]);
+        outbox.ack(batch.end).unwrap();
+        assert_eq!(segment_files(&config.dir), 1);
+
+        let batch = outbox.peek(10).unwrap().unwrap();
+        assert_eq!(ids(&batch.events), ["b"]);
+        outbox.ack(batch.end).unwrap();
+        assert!(outbox.is_empty());
+        drop(outbox);
+
+        // Nothing delivered is replayed after a restart
+        let mut outbox = Outbox::open(config.clone()).unwrap();
+        assert!(outbox.is_empty());
+        assert!(outbox.peek(10).unwrap().is_none());
+        fs::remove_dir_all(config.dir).unwrap();
+    }
+}
@@ -0,0 +1,403 @@
+//! Retries with exponential backoff, server-driven throttling and a circuit breaker.
+//!
+//! Network failures and 5xx responses are retried with jittered exponential backoff.
+//! 429 and 503 responses carrying `Retry-After` wait as long as the server asks.
+//! Other 4xx responses are never retried. After `failure_threshold` consecutive
+//! network failures or 5xx responses the circuit opens and requests fail fast with
+//! [`Error::CircuitOpen`] until `open_for` has passed; a single trial request then
+//! decides whether it closes again.
+
+use std::{
+    fmt,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+use rand::Rng;
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (default 3)
+    pub max_retries: u32,
+    /// Delay before the first retry (default 200ms)
+    pub initial_backoff: Duration,
+    /// Upper bound for the computed backoff (default 30s)
+    pub max_backoff: Duration,
+    /// Growth factor between retries (default 2.0)
+    pub multiplier: f64,
+    /// Fraction of each delay that is randomised, 0.0 to 1.0 (default 0.5)
+    pub jitter: f64,
+    /// Longest server-requested `Retry-After` that is honoured (default 5 minutes)
+    pub max_retry_after: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.5,
+            max_retry_after: Duration::from_secs(300),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Every request is attempted exactly once
+    pub fn none() -> Self {
+        Self { max_retries: 0, ..Self::default() }
+    }
+
+    /// Jittered delay before retry number `retry` (starting at 0)
+    fn backoff(&self, retry: u32) -> Duration {
+        let base = self.initial_backoff.as_secs_f64() * self.multiplier.max(1.0).powi(retry as i32);
+        let base = base.min(self.max_backoff.as_secs_f64());
+        let jitter = self.jitter.clamp(0.0, 1.0) * rand::thread_rng().gen_range(0.0..=1.0);
+        Duration::from_secs_f64(base * (1.0 - jitter))
+    }
+
+    fn delay(&self, retry: u32, error: &Error) -> Duration {
+        match error.retry_after() {
+            Some(retry_after) => retry_after.min(self.max_retry_after),
+            None => self.backoff(retry),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive retryable failures that open the circuit (default 5)
+    pub failure_threshold: u32,
+    /// How long the circuit stays open before a trial request (default 30s)
+    pub open_for: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_for: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Hooks for exporting retry behaviour to the operator's metrics system.
+/// Every method defaults to doing nothing.
+pub trait RetryMetrics: Send + Sync {
+    /// A failed attempt will be retried after `delay`; `attempt` counts retries from 1
+    fn on_retry(&self, _attempt: u32, _delay: Duration, _error: &Error) {}
+    /// A request was given up: non-retryable error, retries exhausted or circuit open
+    fn on_dropped(&self, _error: &Error) {}
+    fn on_circuit_open(&self) {}
+    fn on_circuit_close(&self) {}
+}
+
+/// Ready-made [`RetryMetrics`] that only counts
+#[derive(Debug, Default)]
+pub struct RetryCounters {
+    retried: AtomicU64,
+    dropped: AtomicU64,
+    circuit_opened: AtomicU64,
+}
+
+impl RetryCounters {
+    pub fn retried(&self) -> u64 {
+        self.retried.load(Ordering::Relaxed)
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn circuit_opened(&self) -> u64 {
+        self.circuit_opened.load(Ordering::Relaxed)
+    }
+}
+
+impl RetryMetrics for RetryCounters {
+    fn on_retry(&self, _attempt: u32, _delay: Duration, _error: &Error) {
+        self.retried.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_dropped(&self, _error: &Error) {
+        self.dropped.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_circuit_open(&self) {
+        self.circuit_opened.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug)]
+enum CircuitState {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    /// A trial request is in flight
+    HalfOpen,
+}
+
+#[derive(Debug)]
+struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: Mutex<CircuitState>,
+}
+
+impl CircuitBreaker {
+    fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(CircuitState::Closed { failures: 0 }),
+        }
+    }
+
+    /// Lets a request through, or returns how long the circuit stays open
+    fn acquire(&self) -> std::result::Result<Permit<'_>, Duration> {
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+        match *state {
+            CircuitState::Closed { .. } => Ok(Permit { breaker: self, trial: false }),
+            CircuitState::Open { until } => {
+                let now = Instant::now();
+                if now >= until {
+                    *state = CircuitState::HalfOpen;
+                    Ok(Permit { breaker: self, trial: true })
+                } else {
+                    Err(until - now)
+                }
+            }
+            CircuitState::HalfOpen => Err(Duration::ZERO),
+        }
+    }
+
+    /// A trial that ended without a result says nothing about the backend; try again later
+    fn abandon_trial(&self) {
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+        if matches!(*state, CircuitState::HalfOpen) {
+            *state = CircuitState::Open { until: Instant::now() + self.config.open_for };
+        }
+    }
+
+    /// Returns true when this closes an open circuit
+    fn record_success(&self) -> bool {
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+        let was_open = !matches!(*state, CircuitState::Closed { .. });
+        *state = CircuitState::Closed { failures: 0 };
+        was_open
+    }
+
+    /// Returns true when this opens the circuit
+    fn record_failure(&self) -> bool {
+        let mut state = self.state.lock().expect("circuit breaker lock poisoned");
+        let failures = match *state {
+            CircuitState::Closed { failures } => failures + 1,
+            // A failed trial reopens straight away
+            CircuitState::HalfOpen => self.config.failure_threshold,
+            CircuitState::Open { .. } => return false,
+        };
+        if failures < self.config.failure_threshold {
+            *state = CircuitState::Closed { failures };
+            return false;
+        }
+        *state = CircuitState::Open { until: Instant::now() + self.config.open_for };
+        true
+    }
+}
+
+/// Permission to send one request. A trial permit dropped before its result is
+/// recorded, e.g. because the request future was cancelled, reopens the circuit.
+struct Permit<'a> {
+    breaker: &'a CircuitBreaker,
+    trial: bool,
+}
+
+impl Permit<'_> {
+    /// Returns true when this closes an open circuit
+    fn success(mut self) -> bool {
+        self.trial = false;
+        self.breaker.record_success()
+    }
+
+    /// Returns true when this opens the circuit
+    fn failure(mut self) -> bool {
+        self.trial = false;
+        self.breaker.record_failure()
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.trial {
+            self.breaker.abandon_trial();
+        }
+    }
+}
+
+/// Retry machinery shared by all clones of one client
+pub(crate) struct Retrier {
+    policy: RetryPolicy,
+    breaker: Option<CircuitBreaker>,
+    metrics: Option<Arc<dyn RetryMetrics>>,
+}
+
+impl fmt::Debug for Retrier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Retrier")
+            .field("policy", &self.policy)
+            .field("breaker", &self.breaker)
+            .field("metrics", &self.metrics.is_some())
+            .finish()
+    }
+}
+
+impl Retrier {
+    pub(crate) fn new(
+        policy: RetryPolicy,
+        breaker: Option<CircuitBreakerConfig>,
+        metrics: Option<Arc<dyn RetryMetrics>>,
+    ) -> Self {
+        Self {
+            policy,
+            breaker: breaker.map(CircuitBreaker::new),
+            metrics,
+        }
+    }
+
+    /// Runs `request` until it succeeds, fails for good or runs out of retries
+    pub(crate) async fn run<T, F, Fut>(&self, mut request: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut retries = 0;
+        loop {
+            let permit = match self.breaker.as_ref().map(CircuitBreaker::acquire) {
+                Some(Err(retry_in)) => return Err(self.dropped(Error::CircuitOpen { retry_in })),
+                Some(Ok(permit)) => Some(permit),
+                None => None,
+            };
+
+            let error = match request().await {
+                Ok(value) => {
+                    self.record_success(permit);
+                    return Ok(value);
+                }
+                Err(error) => error,
+            };
+
+            // The backend answered, so a non-retryable error or throttling says nothing
+            // about its health; a 429 only waits for `Retry-After`
+            if !error.is_retryable() || error.is_throttled() {
+                self.record_success(permit);
+            } else {
+                self.record_failure(permit);
+            }
+            if !error.is_retryable() || retries >= self.policy.max_retries {
+                return Err(self.dropped(error));
+            }
+
+            let delay = self.policy.delay(retries, &error);
+            retries += 1;
+            if let Some(metrics) = &self.metrics {
+                metrics.on_retry(retries, delay, &error);
+            }
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    fn record_success(&self, permit: Option<Permit<'_>>) {
+        if permit.is_some_and(Permit::success) {
+            if let Some(metrics) = &self.metrics {
+                metrics.on_circuit_close();
+            }
+        }
+    }
+
+    fn record_failure(&self, permit: Option<Permit<'_>>) {
+        if permit.is_some_and(Permit::failure) {
+            if let Some(metrics) = &self.metrics {
+                metrics.on_circuit_open();
+            }
+        }
+    }
+
+    fn dropped(&self, error: Error) -> Error {
+        if let Some(metrics) = &self.metrics {
+            metrics.on_dropped(&error);
+        }
+        error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+
+    fn status_error(status: StatusCode, retry_after: Option<Duration>) -> Error {
+        Error::Status { status, body: String::new(), retry_after }
+    }
+
+    fn retrier(max_retries: u32, open_for: Duration) -> Retrier {
+        let policy = RetryPolicy {
+            max_retries,
+            initial_backoff: Duration::ZERO,
+            ..RetryPolicy::default()
+        };
+        Retrier::new(policy, Some(CircuitBreakerConfig { failure_threshold: 1, open_for }), None)
+    }
+
+    #[test]
+    fn dropped_trial_permit_reopens_the_circuit() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig { failure_threshold: 1, open_for: Duration::ZERO });
+        assert!(breaker.acquire().expect("closed").failure());
+
+        let trial = breaker.acquire().expect("open_for has passed");
+        assert_eq!(breaker.acquire().err(), Some(Duration::ZERO), "only one trial at a time");
+        drop(trial);
+
+        // Without a recorded result the next request gets a new trial instead of staying blocked
+        assert!(breaker.acquire().expect("trial again").success());
+        assert!(matches!(*breaker.state.lock().unwrap(), CircuitState::Closed { failures: 0 }));
+    }
+
+    #[tokio::test]
+    async fn cancelled_trial_does_not_block_the_client() {
+        let retrier = retrier(0, Duration::ZERO);
+        let failed = retrier.run(|| async { Err::<(), _>(status_error(StatusCode::BAD_GATEWAY, None)) }).await;
+        assert!(matches!(failed, Err(Error::Status { .. })));
+
+        let trial = retrier.run(std::future::pending::<Result<()>>);
+        assert!(tokio::time::timeout(Duration::from_millis(10), trial).await.is_err());
+
+        let next = retrier.run(|| async { Ok(7) }).await;
+        assert_eq!(next.expect("circuit allows a new trial"), 7);
+    }
+
+    #[tokio::test]
+    async fn throttling_waits_without_tripping_the_breaker() {
+        let retrier = retrier(2, Duration::from_secs(60));
+        let attempts = AtomicU64::new(0);
+        let result = retrier
+            .run(|| async {
+                attempts.fetch_add(1, Ordering::Relaxed);
+                Err::<(), _>(status_error(StatusCode::TOO_MANY_REQUESTS, Some(Duration::from_millis(1))))
+            })
+            .await;
+
+        assert!(matches!(result, Err(Error::Status { status: StatusCode::TOO_MANY_REQUESTS, .. })));
+        assert_eq!(attempts.load(Ordering::Relaxed), 3, "429 is retried after Retry-After");
+        assert!(retrier.run(|| async { Ok(()) }).await.is_ok(), "circuit stays closed");
+    }
+
+    #[tokio::test]
+    async fn server_errors_open_the_circuit() {
+        let retrier = retrier(3, Duration::from_secs(60));
+        let result = retrier.run(|| async { Err::<(), _>(status_error(StatusCode::SERVICE_UNAVAILABLE, None)) }).await;
+        assert!(matches!(result, Err(Error::CircuitOpen { .. })));
+    }
+}
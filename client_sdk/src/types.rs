@@ -0,0 +1,10 @@
+//! Wire types for the ingest API, shared with the backend through `anticheat_protocol`.
+
+pub use anticheat_protocol::{
+    config::{AgentConfigBody, ResolvedConfig},
+    ingest::{
+        AgentHeartbeat, DetectionEvent, IngestBatchRequest, IngestResponse, LineError, StreamIngestResponse,
+        IDEMPOTENCY_KEY_HEADER,
+    },
+    severity::Severity,
+};
@@ -0,0 +1,136 @@
+//! One virtual agent: flushes a Poisson-distributed number of events every flush
+//! interval and a heartbeat on its own cadence, timing every request.
+
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+use chrono::Utc;
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng, SeedableRng};
+use tokio::time::{Instant, MissedTickBehavior};
+use client_sdk::{
+    batcher::MAX_BATCH_SIZE, AgentHeartbeat, AnticheatClient, DetectionEvent, IngestBatchRequest, Severity,
+};
+use crate::stats::Stats;
+
+/// Rough shape of production traffic: routine scans dominate, critical hits are rare
+const EVENT_TYPES: &[(&str, u32)] = &[
+    ("memory_scan", 50),
+    ("suspicious_process", 20),
+    ("module_injection", 10),
+    ("speed_hack", 10),
+    ("aim_assist", 7),
+    ("debugger_attached", 3),
+];
+const SEVERITIES: &[(Severity, u32)] = &[
+    (Severity::Low, 55),
+    (Severity::Medium, 25),
+    (Severity::High, 15),
+    (Severity::Critical, 5),
+];
+
+#[derive(Debug, Clone)]
+pub struct AgentProfile {
+    /// Mean events per second
+    pub event_rate: f64,
+    pub flush_interval: Duration,
+    pub heartbeat_interval: Duration,
+    /// Agents start at a random point within this window
+    pub ramp_up: Duration,
+}
+
+pub async fn run(client: AnticheatClient, profile: AgentProfile, stats: Arc<Stats>) {
+    let mut rng = rand::rngs::StdRng::from_entropy();
+    let event_types = WeightedIndex::new(EVENT_TYPES.iter().map(|(_, w)| *w)).expect("event type weights");
+    let severities = WeightedIndex::new(SEVERITIES.iter().map(|(_, w)| *w)).expect("severity weights");
+
+    let ramp_up = profile.ramp_up.as_secs_f64();
+    if ramp_up > 0.0 {
+        tokio::time::sleep(Duration::from_secs_f64(rng.gen_range(0.0..ramp_up))).await;
+    }
+
+    let mut ticker = tokio::time::interval(profile.flush_interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // The first flush always carries a heartbeat so the agent registers as online
+    let mut next_heartbeat = Instant::now();
+    let mut scan_count = 0u64;
+
+    loop {
+        ticker.tick().await;
+
+        let lambda = profile.event_rate * profile.flush_interval.as_secs_f64();
+        let count = poisson(&mut rng, lambda).min(MAX_BATCH_SIZE);
+        let events: Vec<DetectionEvent> = (0..count)
+            .map(|_| {
+                let event_type = EVENT_TYPES[event_types.sample(&mut rng)].0;
+                DetectionEvent::new(event_type, SEVERITIES[severities.sample(&mut rng)].0)
+                    .title(format!("Simulated {}", event_type))
+                    .metadata(serde_json::json!({ "simulated": true, "confidence": rng.gen_range(0.5..1.0) }))
+            })
+            .collect();
+        scan_count += 1;
+
+        let now = Instant::now();
+        let heartbeat = (now >= next_heartbeat).then(|| {
+            next_heartbeat = now + profile.heartbeat_interval;
+            let mut heartbeat = AgentHeartbeat::new(env!("CARGO_PKG_VERSION"), "simulator");
+            heartbeat.cpu_usage = Some(rng.gen_range(1.0..15.0));
+            heartbeat.memory_usage = Some(rng.gen_range(1.0..5.0));
+            heartbeat.scan_count = Some(scan_count);
+            heartbeat.last_scan_at = Some(Utc::now());
+            heartbeat
+        });
+        if events.is_empty() && heartbeat.is_none() {
+            continue;
+        }
+
+        let mut batch = IngestBatchRequest::new(events);
+        batch.heartbeat = heartbeat;
+        send(&client, &batch, &stats).await;
+    }
+}
+
+async fn send(client: &AnticheatClient, batch: &IngestBatchRequest, stats: &Stats) {
+    let ingest = &stats.ingest;
+    ingest.requests.fetch_add(1, Ordering::Relaxed);
+    ingest.events_sent.fetch_add(batch.events.len() as u64, Ordering::Relaxed);
+    if batch.heartbeat.is_some() {
+        ingest.heartbeats.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let started = Instant::now();
+    let result = client.ingest_batch(batch).await;
+    ingest.latency.record(started.elapsed());
+
+    match result {
+        Ok(response) => {
+            ingest.events_accepted.fetch_add(response.processed as u64, Ordering::Relaxed);
+            ingest.events_rejected.fetch_add(response.failed as u64, Ordering::Relaxed);
+        }
+        Err(e) => {
+            ingest.events_rejected.fetch_add(batch.events.len() as u64, Ordering::Relaxed);
+            ingest.record_error(&e);
+        }
+    }
+}
+
+/// Knuth's method for small means, a normal approximation above that
+fn poisson(rng: &mut impl Rng, lambda: f64) -> usize {
+    if lambda <= 0.0 {
+        return 0;
+    }
+    if lambda > 30.0 {
+        // Box-Muller
+        let (u1, u2): (f64, f64) = (rng.gen_range(f64::EPSILON..1.0), rng.gen());
+        let normal = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+        return (lambda + normal * lambda.sqrt()).round().max(0.0) as usize;
+    }
+    let limit = (-lambda).exp();
+    let mut product: f64 = rng.gen();
+    let mut count = 0;
+    while product > limit {
+        product *= rng.gen::<f64>();
+        count += 1;
+    }
+    count
+}
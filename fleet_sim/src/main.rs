@@ -0,0 +1,187 @@
+//! Fleet simulator for load and soak testing a single backend instance.
+//!
+//! Spins up virtual agents with distinct API keys that send realistic batches and
+//! heartbeats to `/ingest/batch`, keeps dashboard sockets open on
+//! `/realtime/dashboard`, and reports latency percentiles, error rates and throughput.
+
+mod agent;
+mod provision;
+mod realtime;
+mod stats;
+
+use std::{error::Error, path::PathBuf, sync::Arc, time::Duration};
+use clap::Parser;
+use tokio::time::Instant;
+use client_sdk::{AnticheatClient, RetryPolicy};
+use agent::AgentProfile;
+use stats::Stats;
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Args {
+    #[arg(long, env = "ANTICHEAT_URL", default_value = "http://localhost:3000")]
+    server_url: String,
+
+    /// Number of virtual agents
+    #[arg(long, default_value_t = 1000)]
+    agents: usize,
+
+    /// Enrollment token used to create one agent per virtual agent (needs enough uses)
+    #[arg(long, env = "ANTICHEAT_ENROLLMENT_TOKEN", required_unless_present = "keys_file")]
+    enrollment_token: Option<String>,
+
+    /// File with one API key per line; reused round-robin if shorter than --agents
+    #[arg(long, conflicts_with = "enrollment_token")]
+    keys_file: Option<PathBuf>,
+
+    /// Write the enrolled keys here so later runs can reuse the same agents via --keys-file
+    #[arg(long)]
+    save_keys: Option<PathBuf>,
+
+    /// Mean events per second, per agent
+    #[arg(long, default_value_t = 0.2)]
+    event_rate: f64,
+
+    /// Seconds between batches from one agent
+    #[arg(long, default_value_t = 5)]
+    flush_secs: u64,
+
+    /// Seconds between heartbeats from one agent
+    #[arg(long, default_value_t = 30)]
+    heartbeat_secs: u64,
+
+    /// Agents start spread over this many seconds
+    #[arg(long, default_value_t = 10)]
+    ramp_up_secs: u64,
+
+    /// Dashboard sockets kept open on /realtime/dashboard
+    #[arg(long, default_value_t = 1)]
+    ws_listeners: usize,
+
+    /// Run length; omit to run until Ctrl-C (soak test)
+    #[arg(long)]
+    duration_secs: Option<u64>,
+
+    /// Seconds between progress lines
+    #[arg(long, default_value_t = 10)]
+    report_every: u64,
+
+    /// Per-request timeout in seconds
+    #[arg(long, default_value_t = 30)]
+    timeout_secs: u64,
+
+    /// Also write the final report as JSON
+    #[arg(long)]
+    report_json: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() {
+    if let Err(e) = run(Args::parse()).await {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(args: Args) -> Result<(), Box<dyn Error>> {
+    if args.agents == 0 {
+        return Err("--agents must be positive".into());
+    }
+
+    let keys = match (&args.keys_file, &args.enrollment_token) {
+        (Some(path), _) => provision::read_keys(path)?,
+        (None, Some(token)) => {
+            println!("Enrolling {} agents...", args.agents);
+            let keys = provision::enroll(&args.server_url, token, args.agents).await?;
+            if let Some(path) = &args.save_keys {
+                provision::write_keys(path, &keys)?;
+                println!("Saved {} keys to {}", keys.len(), path.display());
+            }
+            keys
+        }
+        (None, None) => unreachable!("clap requires a key source"),
+    };
+
+    let profile = AgentProfile {
+        event_rate: args.event_rate.max(0.0),
+        flush_interval: Duration::from_secs(args.flush_secs.max(1)),
+        heartbeat_interval: Duration::from_secs(args.heartbeat_secs.max(1)),
+        ramp_up: Duration::from_secs(args.ramp_up_secs),
+    };
+    println!(
+        "Simulating {} agents ({} distinct keys) against {}: {} events/s each, batches every {:?}, heartbeats every {:?}",
+        args.agents,
+        keys.len().min(args.agents),
+        args.server_url,
+        profile.event_rate,
+        profile.flush_interval,
+        profile.heartbeat_interval
+    );
+
+    let stats = Arc::new(Stats::default());
+    let mut tasks = Vec::with_capacity(args.agents + args.ws_listeners);
+    for index in 0..args.ws_listeners {
+        tasks.push(tokio::spawn(realtime::listen(
+            args.server_url.clone(),
+            keys[index % keys.len()].clone(),
+            stats.clone(),
+        )));
+    }
+    for index in 0..args.agents {
+        // Measure the backend as it is: no retries or circuit breaking on top
+        let client = AnticheatClient::builder()
+            .base_url(&args.server_url)
+            .api_key(&keys[index % keys.len()])
+            .timeout(Duration::from_secs(args.timeout_secs))
+            .retry_policy(RetryPolicy::none())
+            .without_circuit_breaker()
+            .build()?;
+        tasks.push(tokio::spawn(agent::run(client, profile.clone(), stats.clone())));
+    }
+
+    let started = Instant::now();
+    let deadline = args.duration_secs.map(|secs| started + Duration::from_secs(secs));
+    let mut progress = tokio::time::interval(Duration::from_secs(args.report_every.max(1)));
+    progress.tick().await;
+    loop {
+        tokio::select! {
+            _ = progress.tick() => print_progress(&stats, started.elapsed()),
+            _ = sleep_until(deadline) => break,
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    for task in &tasks {
+        task.abort();
+    }
+    let report = stats.report(args.agents, started.elapsed());
+    report.print();
+    if let Some(path) = &args.report_json {
+        std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+        println!("Report written to {}", path.display());
+    }
+    Ok(())
+}
+
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+fn print_progress(stats: &Stats, elapsed: Duration) {
+    use std::sync::atomic::Ordering;
+
+    let window = stats.ingest.latency.take_window();
+    println!(
+        "[{:>7.1}s] requests={} failed={} events_accepted={} ws_messages={} | window p50={:.1}ms p99={:.1}ms",
+        elapsed.as_secs_f64(),
+        stats.ingest.requests.load(Ordering::Relaxed),
+        stats.ingest.failed_requests.load(Ordering::Relaxed),
+        stats.ingest.events_accepted.load(Ordering::Relaxed),
+        stats.realtime.messages.load(Ordering::Relaxed),
+        window.p50_ms,
+        window.p99_ms,
+    );
+}
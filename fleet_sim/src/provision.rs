@@ -0,0 +1,179 @@
+//! Obtains one API key per virtual agent: from a file or by enrolling through an enrollment token.
+
+use std::{error::Error, path::Path, sync::Arc, time::Duration};
+use reqwest::{header::RETRY_AFTER, StatusCode};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+
+/// Concurrent enrollment requests, so provisioning thousands of agents does not look like an attack
+const ENROLL_CONCURRENCY: usize = 32;
+
+/// Wait before retrying a throttled enrollment that came without a usable `Retry-After`
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest `Retry-After` honoured for one throttled enrollment
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Serialize)]
+struct EnrollAgentRequest<'a> {
+    token: &'a str,
+    hostname: String,
+    platform: &'a str,
+    agent_version: &'a str,
+}
+
+#[derive(Deserialize)]
+struct EnrollAgentResponse {
+    api_key: String,
+}
+
+pub fn read_keys(path: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+    let keys: Vec<String> = std::fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(String::from)
+        .collect();
+    if keys.is_empty() {
+        return Err(format!("no API keys in {}", path.display()).into());
+    }
+    Ok(keys)
+}
+
+pub fn write_keys(path: &Path, keys: &[String]) -> std::io::Result<()> {
+    std::fs::write(path, keys.join("\n") + "\n")
+}
+
+/// Enrolls `count` agents named `sim-00001`, `sim-00002`, ...; the token needs at least `count` uses.
+/// Throttled requests are retried after the server's `Retry-After`, so a fleet larger than the
+/// enrollment rate limit takes longer instead of failing.
+pub async fn enroll(server_url: &str, token: &str, count: usize) -> Result<Vec<String>, Box<dyn Error>> {
+    let url = format!("{}/agents/enroll", server_url.trim_end_matches('/'));
+    let http = reqwest::Client::new();
+    let permits = Arc::new(Semaphore::new(ENROLL_CONCURRENCY));
+
+    let mut tasks = Vec::with_capacity(count);
+    for index in 0..count {
+        let http = http.clone();
+        let url = url.clone();
+        let token = token.to_string();
+        let permits = permits.clone();
+        tasks.push(tokio::spawn(async move {
+            let _permit = permits.acquire_owned().await?;
+            let request = EnrollAgentRequest {
+                token: &token,
+                hostname: format!("sim-{:05}", index + 1),
+                platform: "simulator",
+                agent_version: env!("CARGO_PKG_VERSION"),
+            };
+            loop {
+                let response = http.post(&url).json(&request).send().await?;
+                let status = response.status();
+                if status == StatusCode::TOO_MANY_REQUESTS {
+                    let delay = response
+                        .headers()
+                        .get(RETRY_AFTER)
+                        .and_then(|value| value.to_str().ok()?.trim().parse::<u64>().ok())
+                        .map_or(DEFAULT_RETRY_DELAY, |secs| Duration::from_secs(secs).min(MAX_RETRY_DELAY));
+                    tokio::time::sleep(delay).await;
+                    continue;
+                }
+                if !status.is_success() {
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(format!("enrollment failed with {}: {}", status, body).into());
+                }
+                return Ok::<_, Box<dyn Error + Send + Sync>>(response.json::<EnrollAgentResponse>().await?.api_key);
+            }
+        }));
+    }
+
+    let mut keys = Vec::with_capacity(count);
+    for task in tasks {
+        keys.push(task.await?.map_err(|e| e.to_string())?);
+    }
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::Mutex,
+        time::Instant,
+    };
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+    use super::*;
+
+    /// Enrollment endpoint that accepts `burst` requests per second and answers 429 with
+    /// `Retry-After: 1` beyond that; returns the URL and the number of requests seen
+    async fn throttled_server(burst: usize) -> (String, Arc<Mutex<usize>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(0));
+        let window = Arc::new(Mutex::new((Instant::now(), 0)));
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let seen = seen.clone();
+                let window = window.clone();
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buf = [0u8; 8192];
+                    loop {
+                        let read = socket.read(&mut buf).await.unwrap();
+                        request.extend_from_slice(&buf[..read]);
+                        let text = String::from_utf8_lossy(&request);
+                        if let Some(end) = text.find("\r\n\r\n") {
+                            let length = text[..end]
+                                .lines()
+                                .find_map(|line| line.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                                .unwrap_or(0);
+                            if request.len() >= end + 4 + length {
+                                break;
+                            }
+                        }
+                    }
+                    let number = {
+                        let mut seen = seen.lock().unwrap();
+                        *seen += 1;
+                        *seen
+                    };
+                    let allowed = {
+                        let mut window = window.lock().unwrap();
+                        if window.0.elapsed() >= Duration::from_secs(1) {
+                            *window = (Instant::now(), 0);
+                        }
+                        window.1 += 1;
+                        window.1 <= burst
+                    };
+                    let response = if allowed {
+                        let body = format!(r#"{{"api_key":"key-{}"}}"#, number);
+                        format!("HTTP/1.1 201 Created\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body)
+                    } else {
+                        "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 1\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+                    };
+                    socket.write_all(response.as_bytes()).await.unwrap();
+                    socket.shutdown().await.unwrap();
+                });
+            }
+        });
+        (url, requests)
+    }
+
+    #[tokio::test]
+    async fn enrolling_more_agents_than_the_burst_waits_for_retry_after() {
+        let (url, requests) = throttled_server(5).await;
+
+        let keys = enroll(&url, "token", 12).await.unwrap();
+
+        assert_eq!(keys.len(), 12);
+        let mut unique = keys.clone();
+        unique.sort();
+        unique.dedup();
+        assert_eq!(unique.len(), 12);
+        assert!(*requests.lock().unwrap() > 12, "some enrollments should have been throttled");
+    }
+}
@@ -0,0 +1,70 @@
+//! Dashboard socket listeners measuring how long realtime events take to arrive.
+
+use std::{
+    sync::{atomic::Ordering, Arc},
+    time::Duration,
+};
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Message};
+use crate::stats::Stats;
+
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Deserialize)]
+struct RealtimeMessage {
+    data: RealtimeData,
+}
+
+#[derive(Deserialize)]
+struct RealtimeData {
+    changed_at: Option<DateTime<Utc>>,
+}
+
+/// Stays connected to `/realtime/dashboard`, reconnecting after failures, until the task is aborted
+pub async fn listen(server_url: String, api_key: String, stats: Arc<Stats>) {
+    let url = format!("{}/realtime/dashboard", server_url.trim_end_matches('/'))
+        .replacen("http", "ws", 1);
+    let realtime = &stats.realtime;
+
+    loop {
+        let mut request = match url.as_str().into_client_request() {
+            Ok(request) => request,
+            Err(e) => {
+                eprintln!("invalid realtime URL {}: {}", url, e);
+                return;
+            }
+        };
+        request
+            .headers_mut()
+            .insert("X-API-Key", api_key.parse().expect("API keys are valid header values"));
+
+        let mut socket = match tokio_tungstenite::connect_async(request).await {
+            Ok((socket, _)) => socket,
+            Err(_) => {
+                realtime.connect_errors.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(RECONNECT_DELAY).await;
+                continue;
+            }
+        };
+        realtime.connected.fetch_add(1, Ordering::Relaxed);
+
+        while let Some(Ok(message)) = socket.next().await {
+            let Message::Text(text) = message else {
+                continue;
+            };
+            realtime.messages.fetch_add(1, Ordering::Relaxed);
+            if let Some(changed_at) = serde_json::from_str::<RealtimeMessage>(&text)
+                .ok()
+                .and_then(|message| message.data.changed_at)
+            {
+                // Wall clock against the server's timestamp; accurate when both run on one host
+                let delay = (Utc::now() - changed_at).to_std().unwrap_or_default();
+                realtime.delivery.record(delay);
+            }
+        }
+        realtime.disconnects.fetch_add(1, Ordering::Relaxed);
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
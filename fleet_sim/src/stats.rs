@@ -0,0 +1,226 @@
+//! Shared counters and latency samples, and the report built from them.
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+use serde::Serialize;
+use client_sdk::Error as SdkError;
+
+/// Latency samples in microseconds: everything since start plus the current progress window
+#[derive(Default)]
+pub struct Latencies {
+    samples: Mutex<(Vec<u32>, Vec<u32>)>,
+}
+
+impl Latencies {
+    pub fn record(&self, latency: Duration) {
+        let micros = latency.as_micros().min(u32::MAX as u128) as u32;
+        let mut samples = self.samples.lock().expect("latency lock poisoned");
+        samples.0.push(micros);
+        samples.1.push(micros);
+    }
+
+    pub fn total(&self) -> Percentiles {
+        Percentiles::from_samples(self.samples.lock().expect("latency lock poisoned").0.clone())
+    }
+
+    /// Percentiles since the previous call
+    pub fn take_window(&self) -> Percentiles {
+        Percentiles::from_samples(std::mem::take(&mut self.samples.lock().expect("latency lock poisoned").1))
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub struct Percentiles {
+    pub count: usize,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl Percentiles {
+    fn from_samples(mut samples: Vec<u32>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+        // Nearest-rank percentile
+        let at = |p: f64| {
+            let rank = ((p / 100.0) * samples.len() as f64).ceil() as usize;
+            samples[rank.clamp(1, samples.len()) - 1] as f64 / 1000.0
+        };
+        Self {
+            count: samples.len(),
+            mean_ms: samples.iter().map(|s| *s as f64).sum::<f64>() / samples.len() as f64 / 1000.0,
+            p50_ms: at(50.0),
+            p90_ms: at(90.0),
+            p95_ms: at(95.0),
+            p99_ms: at(99.0),
+            max_ms: *samples.last().unwrap_or(&0) as f64 / 1000.0,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct IngestStats {
+    pub requests: AtomicU64,
+    pub failed_requests: AtomicU64,
+    pub heartbeats: AtomicU64,
+    pub events_sent: AtomicU64,
+    pub events_accepted: AtomicU64,
+    pub events_rejected: AtomicU64,
+    pub latency: Latencies,
+    errors: Mutex<BTreeMap<String, u64>>,
+}
+
+impl IngestStats {
+    pub fn record_error(&self, error: &SdkError) {
+        self.failed_requests.fetch_add(1, Ordering::Relaxed);
+        *self
+            .errors
+            .lock()
+            .expect("error map lock poisoned")
+            .entry(error_kind(error))
+            .or_default() += 1;
+    }
+}
+
+/// Coarse error label so thousands of failures group into a readable table
+fn error_kind(error: &SdkError) -> String {
+    match error {
+        SdkError::Http(e) if e.is_timeout() => "timeout".to_string(),
+        SdkError::Http(e) if e.is_connect() => "connect".to_string(),
+        SdkError::Http(_) => "network".to_string(),
+        SdkError::Unauthorized => "http 401".to_string(),
+        SdkError::Rejected { status, .. } | SdkError::Status { status, .. } => format!("http {}", status.as_u16()),
+        other => other.to_string(),
+    }
+}
+
+#[derive(Default)]
+pub struct RealtimeStats {
+    pub connected: AtomicU64,
+    pub connect_errors: AtomicU64,
+    pub disconnects: AtomicU64,
+    pub messages: AtomicU64,
+    /// Server publish time (`changed_at`) to receipt by the listener
+    pub delivery: Latencies,
+}
+
+#[derive(Default)]
+pub struct Stats {
+    pub ingest: IngestStats,
+    pub realtime: RealtimeStats,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub duration_secs: f64,
+    pub agents: usize,
+    pub ingest: IngestReport,
+    pub realtime: RealtimeReport,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IngestReport {
+    pub requests: u64,
+    pub failed_requests: u64,
+    pub error_rate: f64,
+    pub errors: BTreeMap<String, u64>,
+    pub heartbeats: u64,
+    pub events_sent: u64,
+    pub events_accepted: u64,
+    pub events_rejected: u64,
+    pub requests_per_sec: f64,
+    pub events_per_sec: f64,
+    pub latency: Percentiles,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RealtimeReport {
+    pub connected: u64,
+    pub connect_errors: u64,
+    pub disconnects: u64,
+    pub messages: u64,
+    pub delivery_latency: Percentiles,
+}
+
+impl Stats {
+    pub fn report(&self, agents: usize, elapsed: Duration) -> Report {
+        let secs = elapsed.as_secs_f64().max(0.001);
+        let ingest = &self.ingest;
+        let requests = ingest.requests.load(Ordering::Relaxed);
+        let failed_requests = ingest.failed_requests.load(Ordering::Relaxed);
+        let events_accepted = ingest.events_accepted.load(Ordering::Relaxed);
+
+        Report {
+            duration_secs: secs,
+            agents,
+            ingest: IngestReport {
+                requests,
+                failed_requests,
+                error_rate: if requests == 0 { 0.0 } else { failed_requests as f64 / requests as f64 },
+                errors: ingest.errors.lock().expect("error map lock poisoned").clone(),
+                heartbeats: ingest.heartbeats.load(Ordering::Relaxed),
+                events_sent: ingest.events_sent.load(Ordering::Relaxed),
+                events_accepted,
+                events_rejected: ingest.events_rejected.load(Ordering::Relaxed),
+                requests_per_sec: requests as f64 / secs,
+                events_per_sec: events_accepted as f64 / secs,
+                latency: ingest.latency.total(),
+            },
+            realtime: RealtimeReport {
+                connected: self.realtime.connected.load(Ordering::Relaxed),
+                connect_errors: self.realtime.connect_errors.load(Ordering::Relaxed),
+                disconnects: self.realtime.disconnects.load(Ordering::Relaxed),
+                messages: self.realtime.messages.load(Ordering::Relaxed),
+                delivery_latency: self.realtime.delivery.total(),
+            },
+        }
+    }
+}
+
+impl Report {
+    pub fn print(&self) {
+        let ingest = &self.ingest;
+        println!();
+        println!("=== Fleet simulation report ===");
+        println!("duration           {:.1}s, {} agents", self.duration_secs, self.agents);
+        println!(
+            "requests           {} ({:.1}/s), {} failed ({:.2}%)",
+            ingest.requests,
+            ingest.requests_per_sec,
+            ingest.failed_requests,
+            ingest.error_rate * 100.0
+        );
+        for (kind, count) in &ingest.errors {
+            println!("  {:<16} {}", kind, count);
+        }
+        println!("heartbeats         {}", ingest.heartbeats);
+        println!(
+            "events             {} sent, {} accepted ({:.1}/s), {} rejected",
+            ingest.events_sent, ingest.events_accepted, ingest.events_per_sec, ingest.events_rejected
+        );
+        print_percentiles("ingest latency", &ingest.latency);
+        println!(
+            "realtime           {} connected, {} connect errors, {} disconnects, {} messages",
+            self.realtime.connected, self.realtime.connect_errors, self.realtime.disconnects, self.realtime.messages
+        );
+        print_percentiles("realtime delivery", &self.realtime.delivery_latency);
+    }
+}
+
+fn print_percentiles(label: &str, p: &Percentiles) {
+    println!(
+        "{:<18} p50={:.1}ms p90={:.1}ms p95={:.1}ms p99={:.1}ms max={:.1}ms (n={})",
+        label, p.p50_ms, p.p90_ms, p.p95_ms, p.p99_ms, p.max_ms, p.count
+    );
+}
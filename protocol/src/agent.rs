@@ -0,0 +1,48 @@
+use std::fmt;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Result of evaluating an agent version against the org version policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum VersionStatus {
+    Supported,
+    Outdated,
+    Blocked,
+    /// The version string is not valid semver
+    Unknown,
+}
+
+impl fmt::Display for VersionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            VersionStatus::Supported => "supported",
+            VersionStatus::Outdated => "outdated",
+            VersionStatus::Blocked => "blocked",
+            VersionStatus::Unknown => "unknown",
+        };
+        f.write_str(s)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct Agent {
+    pub id: String,
+    pub org_id: String,
+    pub name: String,
+    pub platform: String,
+    pub version: String,
+    pub status: String,
+    pub version_status: VersionStatus,
+    pub tags: Vec<String>,
+    pub cpu_usage: Option<f32>,
+    pub memory_usage: Option<f32>,
+    pub last_scan_at: Option<DateTime<Utc>>,
+    pub scan_count: u64,
+    pub last_heartbeat: Option<DateTime<Utc>>,
+    /// Agent clock minus server clock at the last heartbeat, in milliseconds; positive when the agent is ahead
+    pub clock_skew_ms: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
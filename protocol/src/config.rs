@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+/// Settings pushed to agents; `settings` carries detector-specific options
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, Validate, ToSchema)]
+pub struct AgentConfigBody {
+    #[validate(range(min = 1, max = 86400, message = "Scan interval must be between 1 and 86400 seconds"))]
+    pub scan_interval_secs: u32,
+
+    #[validate(length(max = 50, message = "At most 50 detectors can be enabled"))]
+    #[serde(default)]
+    pub enabled_detectors: Vec<String>,
+
+    #[validate(range(min = 0.0, max = 1.0, message = "Sampling rate must be between 0 and 1"))]
+    pub sampling_rate: f32,
+
+    #[serde(default)]
+    pub settings: serde_json::Value,
+}
+
+/// Configuration resolved for a single agent. `version` is 0 when no profile applies.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct ResolvedConfig {
+    pub version: u64,
+    pub profile_id: Option<String>,
+    pub body: Option<AgentConfigBody>,
+}
@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use crate::severity::Severity;
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct PageMeta {
+    pub page: u32,
+    pub per_page: u32,
+    pub total: u64,
+    pub total_pages: u32,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct PagedResponse<T> {
+    pub data: Vec<T>,
+    pub meta: PageMeta,
+}
+
+/// Why an event's `detected_at` is suspect: further from server time than the configured bounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TimestampFlag {
+    /// Ahead of the server clock
+    Future,
+    /// Older than the accepted age
+    Past,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct Detection {
+    pub id: String,
+    pub org_id: String,
+    pub agent_id: String,
+    pub detection_type: String,
+    pub severity: Severity,
+    pub title: String,
+    pub description: String,
+    pub metadata: serde_json::Value,
+    /// Server time the event arrived; `created_at` is the agent-reported detection time
+    pub received_at: DateTime<Utc>,
+    pub timestamp_flag: Option<TimestampFlag>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct Alert {
+    pub id: String,
+    pub org_id: String,
+    pub rule_id: String,
+    pub detection_id: String,
+    pub severity: Severity,
+    pub status: String,
+    pub title: String,
+    pub description: String,
+    pub metadata: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
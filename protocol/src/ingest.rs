@@ -0,0 +1,219 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+use crate::{config::ResolvedConfig, severity::Severity};
+
+/// Optional request header identifying a batch, so a retried `POST /ingest/batch` is not stored twice
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Body of `POST /ingest/batch`. `events` may only be empty when a heartbeat is attached.
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+#[validate(schema(function = "validate_batch_not_empty"))]
+pub struct IngestBatchRequest {
+    #[validate(length(max = 1000, message = "Events array must contain at most 1000 items"))]
+    #[serde(default)]
+    pub events: Vec<DetectionEvent>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heartbeat: Option<AgentHeartbeat>,
+}
+
+impl IngestBatchRequest {
+    pub fn new(events: Vec<DetectionEvent>) -> Self {
+        Self { events, heartbeat: None }
+    }
+
+    /// A batch carrying only agent health, sent when there is nothing to report
+    pub fn heartbeat_only(heartbeat: AgentHeartbeat) -> Self {
+        Self { events: Vec::new(), heartbeat: Some(heartbeat) }
+    }
+
+    pub fn with_heartbeat(mut self, heartbeat: AgentHeartbeat) -> Self {
+        self.heartbeat = Some(heartbeat);
+        self
+    }
+}
+
+fn validate_batch_not_empty(batch: &IngestBatchRequest) -> Result<(), ValidationError> {
+    if batch.events.is_empty() && batch.heartbeat.is_none() {
+        return Err(ValidationError::new("empty_batch")
+            .with_message("Batch must contain at least one event or a heartbeat".into()));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+pub struct DetectionEvent {
+    /// Client-generated ID; events already seen with the same ID are reported as duplicates
+    #[validate(length(min = 1, max = 128, message = "Event ID must be 1-128 characters"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<String>,
+
+    #[validate(length(min = 1, max = 100, message = "Event type must be 1-100 characters"))]
+    pub event_type: String,
+
+    /// Legacy spellings such as `HIGH` or `warning` are mapped to the canonical level
+    pub severity: Severity,
+
+    #[validate(length(max = 500, message = "Title too long"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    #[validate(length(max = 2000, message = "Description too long"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    pub metadata: serde_json::Value,
+    pub detected_at: DateTime<Utc>,
+}
+
+impl DetectionEvent {
+    /// Event detected now, with empty metadata
+    pub fn new(event_type: impl Into<String>, severity: Severity) -> Self {
+        Self {
+            event_id: None,
+            event_type: event_type.into(),
+            severity,
+            title: None,
+            description: None,
+            metadata: serde_json::Value::Object(Default::default()),
+            detected_at: Utc::now(),
+        }
+    }
+
+    pub fn event_id(mut self, event_id: impl Into<String>) -> Self {
+        self.event_id = Some(event_id.into());
+        self
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    pub fn metadata(mut self, metadata: serde_json::Value) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    pub fn detected_at(mut self, detected_at: DateTime<Utc>) -> Self {
+        self.detected_at = detected_at;
+        self
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Validate, ToSchema)]
+pub struct AgentHeartbeat {
+    #[validate(length(min = 1, max = 50, message = "Agent version must be 1-50 characters"))]
+    pub agent_version: String,
+
+    #[validate(length(min = 1, max = 50, message = "Platform must be 1-50 characters"))]
+    pub platform: String,
+
+    #[validate(range(min = 0.0, max = 100.0, message = "CPU usage must be a percentage"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpu_usage: Option<f32>,
+
+    #[validate(range(min = 0.0, max = 100.0, message = "Memory usage must be a percentage"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_usage: Option<f32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_scan_at: Option<DateTime<Utc>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scan_count: Option<u64>,
+
+    /// Config version the agent is currently running, see `/agents/config`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_version: Option<u64>,
+
+    /// Agent clock when the request was sent, used by the server to measure clock skew
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sent_at: Option<DateTime<Utc>>,
+}
+
+impl AgentHeartbeat {
+    pub fn new(agent_version: impl Into<String>, platform: impl Into<String>) -> Self {
+        Self {
+            agent_version: agent_version.into(),
+            platform: platform.into(),
+            cpu_usage: None,
+            memory_usage: None,
+            last_scan_at: None,
+            scan_count: None,
+            config_version: None,
+            sent_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct IngestResponse {
+    pub success: bool,
+    pub processed: u32,
+    pub failed: u32,
+    /// Events skipped because their ID, or the batch's `Idempotency-Key`, was already ingested
+    #[serde(default)]
+    pub duplicates: u32,
+    pub errors: Vec<String>,
+
+    /// Machine-readable reason when the whole batch was refused
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+
+    /// Present when the heartbeat reported a config version other than the current one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<ResolvedConfig>,
+}
+
+impl IngestResponse {
+    /// Formats an error for a single event as `events[<index>]: <message>`
+    pub fn event_error(index: usize, message: &str) -> String {
+        format!("events[{}]: {}", index, message)
+    }
+
+    /// Per-event errors as `(index, message)`; batch-level errors are skipped
+    pub fn event_errors(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.errors.iter().filter_map(|error| {
+            let rest = error.strip_prefix("events[")?;
+            let (index, message) = rest.split_once("]: ")?;
+            Some((index.parse().ok()?, message))
+        })
+    }
+}
+
+/// Response of `POST /ingest/stream`, summarising a newline-delimited upload of [`DetectionEvent`]s
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct StreamIngestResponse {
+    pub success: bool,
+    /// Non-blank lines read
+    pub lines: u64,
+    pub processed: u64,
+    pub failed: u64,
+    #[serde(default)]
+    pub duplicates: u64,
+    /// Failed lines, oldest first; capped, see `errors_truncated`
+    pub errors: Vec<LineError>,
+    /// More lines failed than are listed in `errors`
+    #[serde(default)]
+    pub errors_truncated: bool,
+
+    /// Machine-readable reason when the whole upload was refused
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+}
+
+/// Why one line of a streamed upload was not ingested
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct LineError {
+    /// 1-based line number in the request body; 0 when the whole upload was refused
+    pub line: u64,
+    pub error: String,
+}
@@ -0,0 +1,20 @@
+//! Wire types shared by the `anticheat` backend and `client_sdk`.
+//!
+//! Every payload exchanged over the agent and dashboard APIs is defined here once,
+//! so a schema change on one side fails to compile on the other.
+
+pub mod agent;
+pub mod config;
+pub mod dashboard;
+pub mod ingest;
+pub mod severity;
+pub mod signing;
+
+pub use agent::{Agent, VersionStatus};
+pub use config::{AgentConfigBody, ResolvedConfig};
+pub use dashboard::{Alert, Detection, PageMeta, PagedResponse, TimestampFlag};
+pub use ingest::{
+    AgentHeartbeat, DetectionEvent, IngestBatchRequest, IngestResponse, LineError, StreamIngestResponse,
+    IDEMPOTENCY_KEY_HEADER,
+};
+pub use severity::Severity;
@@ -0,0 +1,75 @@
+use std::{fmt, str::FromStr};
+use serde::{Deserialize, Deserializer, Serialize};
+use utoipa::ToSchema;
+
+/// How serious a detection or alert is, ordered from `Info` to `Critical`.
+///
+/// Always serialized in lowercase. Deserializing also accepts legacy spellings
+/// (any case, and aliases such as `warning` or `fatal`) so older agents keep working.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownSeverity(pub String);
+
+impl fmt::Display for UnknownSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown severity '{}'; expected info, low, medium, high or critical", self.0)
+    }
+}
+
+impl std::error::Error for UnknownSeverity {}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+
+    /// Numeric level, 0 for `Info` up to 4 for `Critical`
+    pub fn level(self) -> u8 {
+        self as u8
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = UnknownSeverity;
+
+    /// Parses canonical names case-insensitively, plus legacy aliases and levels `0`-`4`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let severity = match s.trim().to_ascii_lowercase().as_str() {
+            "info" | "informational" | "information" | "notice" | "debug" | "none" | "0" => Severity::Info,
+            "low" | "minor" | "1" => Severity::Low,
+            "medium" | "med" | "moderate" | "warning" | "warn" | "2" => Severity::Medium,
+            "high" | "major" | "severe" | "error" | "3" => Severity::High,
+            "critical" | "crit" | "fatal" | "emergency" | "4" => Severity::Critical,
+            _ => return Err(UnknownSeverity(s.to_string())),
+        };
+        Ok(severity)
+    }
+}
+
+impl<'de> Deserialize<'de> for Severity {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        value.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,127 @@
+//! HMAC request signing for agents, an alternative to sending the `X-API-Key` itself.
+//!
+//! The signing secret and key ID are both derived from the API key, so an agent
+//! needs nothing beyond the key it already has. Neither reveals the key, and the
+//! secret differs from the key hash the server uses for `X-API-Key` lookups:
+//!
+//! ```text
+//! X-Key-Id:              key_id(api_key)
+//! X-Signature-Timestamp: unix seconds
+//! X-Signature-Nonce:     unique per request
+//! X-Content-SHA256:      hex SHA-256 of the body as sent, or UNSIGNED-PAYLOAD for streams
+//! X-Signature:           hex HMAC-SHA256(signing_secret(api_key), canonical_request(..))
+//! ```
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+pub const KEY_ID_HEADER: &str = "X-Key-Id";
+pub const SIGNATURE_HEADER: &str = "X-Signature";
+pub const SIGNATURE_TIMESTAMP_HEADER: &str = "X-Signature-Timestamp";
+pub const SIGNATURE_NONCE_HEADER: &str = "X-Signature-Nonce";
+pub const CONTENT_SHA256_HEADER: &str = "X-Content-SHA256";
+
+/// Body hash placeholder for uploads that cannot be hashed up front; only `/ingest/stream` accepts it
+pub const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Public identifier of an API key, sent in place of the key on signed requests
+pub fn key_id(api_key: &str) -> String {
+    sha256_hex(format!("key-id:{}", api_key).as_bytes())[..32].to_string()
+}
+
+/// Label the signing secret is derived under; bumping it rotates every signing secret
+const SIGNING_SECRET_LABEL: &[u8] = b"anticheat-signing-v1";
+
+/// HMAC key for an API key: HMAC-SHA256(api_key, label), so the plain key hash kept
+/// for `X-API-Key` lookups cannot be used to sign requests
+pub fn signing_secret(api_key: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(api_key.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(SIGNING_SECRET_LABEL);
+    hex(&mac.finalize().into_bytes())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Value of `X-Content-SHA256` for a body
+pub fn content_sha256(body: &[u8]) -> String {
+    sha256_hex(body)
+}
+
+/// The string that is signed: one field per line, `path` including any query string
+pub fn canonical_request(method: &str, path: &str, timestamp: i64, nonce: &str, content_sha256: &str) -> String {
+    format!("{}\n{}\n{}\n{}\n{}", method.to_ascii_uppercase(), path, timestamp, nonce, content_sha256)
+}
+
+/// Hex HMAC-SHA256 of `canonical` under `secret`
+pub fn sign(secret: &str, canonical: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(canonical.as_bytes());
+    hex(&mac.finalize().into_bytes())
+}
+
+/// Checks a hex signature in constant time
+pub fn verify(secret: &str, canonical: &str, signature: &str) -> bool {
+    if signature.len() != 64 || !signature.is_ascii() {
+        return false;
+    }
+    let Ok(expected) = (0..signature.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&signature[i..i + 2], 16))
+        .collect::<Result<Vec<u8>, _>>()
+    else {
+        return false;
+    };
+
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(canonical.as_bytes());
+    mac.verify_slice(&expected).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CANONICAL: &str = "POST\n/ingest/batch\n1700000000\nnonce\nUNSIGNED-PAYLOAD";
+
+    #[test]
+    fn signatures_verify_only_for_the_same_request_and_secret() {
+        let secret = signing_secret("org_example_key");
+        let signature = sign(&secret, CANONICAL);
+        assert_eq!(signature.len(), 64);
+        assert!(verify(&secret, CANONICAL, &signature));
+        assert!(verify(&secret, CANONICAL, &signature.to_ascii_uppercase()));
+
+        assert!(!verify(&signing_secret("org_other_key"), CANONICAL, &signature));
+        assert!(!verify(&secret, &CANONICAL.replace("nonce", "other"), &signature));
+    }
+
+    #[test]
+    fn malformed_signatures_are_rejected() {
+        let secret = signing_secret("org_example_key");
+        let signature = sign(&secret, CANONICAL);
+        assert!(!verify(&secret, CANONICAL, &signature[..62]));
+        assert!(!verify(&secret, CANONICAL, &format!("{}zz", &signature[..62])));
+        // Multi-byte characters must not split a hex pair mid-character
+        assert!(!verify(&secret, CANONICAL, &format!("é{}", &signature[..62])));
+        assert!(!verify(&secret, CANONICAL, ""));
+    }
+
+    #[test]
+    fn derived_values_do_not_reveal_the_key_or_each_other() {
+        let api_key = "org_example_key";
+        let secret = signing_secret(api_key);
+        let key_id = key_id(api_key);
+        assert_eq!(key_id.len(), 32);
+        assert!(!secret.contains(api_key) && !key_id.contains(api_key));
+        assert!(!secret.starts_with(&key_id));
+        assert_ne!(secret, content_sha256(api_key.as_bytes()));
+    }
+}
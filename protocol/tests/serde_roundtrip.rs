@@ -0,0 +1,321 @@
+use anticheat_protocol::{
+    Agent, AgentConfigBody, AgentHeartbeat, Alert, Detection, DetectionEvent, IngestBatchRequest,
+    IngestResponse, LineError, PageMeta, PagedResponse, ResolvedConfig, Severity, StreamIngestResponse,
+    TimestampFlag, VersionStatus, signing,
+};
+use chrono::{TimeZone, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use validator::Validate;
+
+/// Serializes, deserializes and serializes again, asserting both JSON forms match
+fn roundtrip<T: Serialize + DeserializeOwned>(value: &T) -> Value {
+    let first = serde_json::to_value(value).expect("serialize");
+    let decoded: T = serde_json::from_value(first.clone()).expect("deserialize");
+    let second = serde_json::to_value(&decoded).expect("re-serialize");
+    assert_eq!(first, second);
+    first
+}
+
+fn timestamp() -> chrono::DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap()
+}
+
+#[test]
+fn ingest_batch_request_roundtrip() {
+    let mut heartbeat = AgentHeartbeat::new("1.2.3", "linux");
+    heartbeat.cpu_usage = Some(12.5);
+    heartbeat.scan_count = Some(7);
+    heartbeat.config_version = Some(3);
+
+    let batch = IngestBatchRequest::new(vec![
+        DetectionEvent::new("suspicious_process", Severity::High)
+            .title("Cheat Engine")
+            .metadata(json!({ "pid": 4242 }))
+            .detected_at(timestamp()),
+    ])
+    .with_heartbeat(heartbeat);
+
+    let value = roundtrip(&batch);
+    assert_eq!(value["events"][0]["event_type"], "suspicious_process");
+    assert_eq!(value["events"][0]["detected_at"], "2025-01-02T03:04:05Z");
+    assert_eq!(value["heartbeat"]["agent_version"], "1.2.3");
+}
+
+#[test]
+fn ingest_batch_request_messagepack_roundtrip() {
+    let batch = IngestBatchRequest::new(vec![
+        DetectionEvent::new("suspicious_process", Severity::High)
+            .event_id("evt-1")
+            .metadata(json!({ "pid": 4242, "modules": ["a.dll"], "confidence": 0.9 }))
+            .detected_at(timestamp()),
+        DetectionEvent::new("memory_scan", Severity::Low).detected_at(timestamp()),
+    ])
+    .with_heartbeat(AgentHeartbeat::new("1.2.3", "windows"));
+
+    // Optional fields are skipped when empty, so the backend requires fields encoded as maps
+    let bytes = rmp_serde::to_vec_named(&batch).expect("encode");
+    let decoded: IngestBatchRequest = rmp_serde::from_slice(&bytes).expect("decode");
+    assert_eq!(serde_json::to_value(&decoded).unwrap(), serde_json::to_value(&batch).unwrap());
+    assert!(bytes.len() < serde_json::to_vec(&batch).unwrap().len());
+}
+
+#[test]
+fn ingest_batch_request_omits_empty_optionals() {
+    let batch = IngestBatchRequest::new(vec![DetectionEvent::new("memory_scan", Severity::Low).detected_at(timestamp())]);
+
+    let value = roundtrip(&batch);
+    assert!(value.get("heartbeat").is_none());
+    assert!(value["events"][0].get("title").is_none());
+    assert!(value["events"][0].get("description").is_none());
+}
+
+#[test]
+fn ingest_batch_request_accepts_minimal_json() {
+    let batch: IngestBatchRequest = serde_json::from_value(json!({
+        "events": [{
+            "event_type": "memory_scan",
+            "severity": "low",
+            "metadata": {},
+            "detected_at": "2025-01-02T03:04:05Z"
+        }],
+        "heartbeat": { "agent_version": "1.0.0", "platform": "windows" }
+    }))
+    .expect("minimal payload");
+
+    assert_eq!(batch.events[0].title, None);
+    assert_eq!(batch.heartbeat.unwrap().cpu_usage, None);
+}
+
+#[test]
+fn severity_maps_legacy_strings() {
+    for (legacy, expected) in [
+        ("HIGH", Severity::High),
+        ("High", Severity::High),
+        ("warning", Severity::Medium),
+        ("informational", Severity::Info),
+        ("fatal", Severity::Critical),
+        ("0", Severity::Info),
+    ] {
+        let event: DetectionEvent = serde_json::from_value(json!({
+            "event_type": "memory_scan",
+            "severity": legacy,
+            "metadata": {},
+            "detected_at": "2025-01-02T03:04:05Z"
+        }))
+        .expect(legacy);
+        assert_eq!(event.severity, expected, "{}", legacy);
+    }
+
+    assert_eq!(roundtrip(&Severity::Critical), json!("critical"));
+    assert!(serde_json::from_value::<Severity>(json!("urgent")).is_err());
+}
+
+#[test]
+fn severity_orders_by_level() {
+    assert!(Severity::Info < Severity::Low);
+    assert!(Severity::High < Severity::Critical);
+    assert_eq!(Severity::Medium.level(), 2);
+}
+
+#[test]
+fn event_id_roundtrip_and_validation() {
+    let event = DetectionEvent::new("memory_scan", Severity::Low).event_id("evt-1").detected_at(timestamp());
+    assert_eq!(roundtrip(&event)["event_id"], "evt-1");
+    assert!(roundtrip(&DetectionEvent::new("memory_scan", Severity::Low)).get("event_id").is_none());
+
+    assert!(event.validate().is_ok());
+    assert!(DetectionEvent::new("memory_scan", Severity::Low).event_id("").validate().is_err());
+    assert!(DetectionEvent::new("memory_scan", Severity::Low).event_id("x".repeat(129)).validate().is_err());
+}
+
+#[test]
+fn ingest_response_without_duplicates_deserializes() {
+    let response: IngestResponse = serde_json::from_value(json!({
+        "success": true,
+        "processed": 3,
+        "failed": 0,
+        "errors": []
+    }))
+    .expect("response from an older server");
+
+    assert_eq!(response.duplicates, 0);
+}
+
+#[test]
+fn heartbeat_only_batch_is_valid() {
+    let batch: IngestBatchRequest = serde_json::from_value(json!({
+        "heartbeat": { "agent_version": "1.0.0", "platform": "linux" }
+    }))
+    .expect("heartbeat-only payload");
+
+    assert!(batch.events.is_empty());
+    assert!(batch.validate().is_ok());
+    assert_eq!(roundtrip(&IngestBatchRequest::heartbeat_only(AgentHeartbeat::new("1.0.0", "linux")))["events"], json!([]));
+}
+
+#[test]
+fn empty_batch_without_heartbeat_is_invalid() {
+    assert!(IngestBatchRequest::new(Vec::new()).validate().is_err());
+}
+
+#[test]
+fn ingest_response_roundtrip() {
+    let response = IngestResponse {
+        success: false,
+        processed: 9,
+        failed: 1,
+        duplicates: 2,
+        errors: vec!["Invalid event format".to_string()],
+        error_code: None,
+        config: Some(ResolvedConfig {
+            version: 4,
+            profile_id: Some("profile_1".to_string()),
+            body: Some(AgentConfigBody {
+                scan_interval_secs: 30,
+                enabled_detectors: vec!["process_scan".to_string()],
+                sampling_rate: 0.5,
+                settings: json!({ "depth": 2 }),
+            }),
+        }),
+    };
+
+    let value = roundtrip(&response);
+    assert!(value.get("error_code").is_none());
+    assert_eq!(value["duplicates"], 2);
+    assert_eq!(value["config"]["body"]["scan_interval_secs"], 30);
+}
+
+#[test]
+fn ingest_response_event_errors() {
+    let response = IngestResponse {
+        success: false,
+        processed: 1,
+        failed: 2,
+        duplicates: 0,
+        errors: vec![
+            IngestResponse::event_error(0, "Invalid event format"),
+            "Invalid heartbeat format".to_string(),
+            IngestResponse::event_error(12, "Unknown event type"),
+        ],
+        error_code: None,
+        config: None,
+    };
+
+    let errors: Vec<(usize, &str)> = response.event_errors().collect();
+    assert_eq!(errors, vec![(0, "Invalid event format"), (12, "Unknown event type")]);
+}
+
+#[test]
+fn stream_ingest_response_roundtrip() {
+    let response = StreamIngestResponse {
+        success: false,
+        lines: 5000,
+        processed: 4990,
+        failed: 2,
+        duplicates: 8,
+        errors: vec![LineError { line: 17, error: "Invalid event format".to_string() }],
+        errors_truncated: true,
+        error_code: None,
+    };
+
+    let value = roundtrip(&response);
+    assert_eq!(value["errors"][0]["line"], 17);
+    assert!(value.get("error_code").is_none());
+}
+
+#[test]
+fn paged_detections_roundtrip() {
+    let page = PagedResponse {
+        data: vec![Detection {
+            id: "det_001".to_string(),
+            org_id: "org_1".to_string(),
+            agent_id: "agent_001".to_string(),
+            detection_type: "suspicious_process".to_string(),
+            severity: Severity::High,
+            title: "Suspicious AI Process Detected".to_string(),
+            description: "Detected ChatGPT API calls during gameplay".to_string(),
+            metadata: json!({ "process": "chatgpt.exe", "confidence": 0.95 }),
+            received_at: timestamp(),
+            timestamp_flag: Some(TimestampFlag::Future),
+            created_at: timestamp(),
+            updated_at: timestamp(),
+        }],
+        meta: PageMeta { page: 1, per_page: 20, total: 1, total_pages: 1 },
+    };
+
+    let value = roundtrip(&page);
+    assert_eq!(value["meta"]["total"], 1);
+    assert_eq!(value["data"][0]["timestamp_flag"], "future");
+}
+
+#[test]
+fn agent_roundtrip() {
+    let agent = Agent {
+        id: "agent_001".to_string(),
+        org_id: "org_1".to_string(),
+        name: "Game Server #1".to_string(),
+        platform: "linux".to_string(),
+        version: "1.0.0".to_string(),
+        status: "online".to_string(),
+        version_status: VersionStatus::Outdated,
+        tags: vec!["eu".to_string()],
+        cpu_usage: Some(3.0),
+        memory_usage: None,
+        last_scan_at: None,
+        scan_count: 12,
+        last_heartbeat: Some(timestamp()),
+        clock_skew_ms: Some(-1500),
+        created_at: timestamp(),
+        updated_at: timestamp(),
+    };
+
+    let value = roundtrip(&agent);
+    assert_eq!(value["version_status"], "outdated");
+    assert_eq!(value["clock_skew_ms"], -1500);
+}
+
+#[test]
+fn alert_roundtrip() {
+    let alert = Alert {
+        id: "alert_001".to_string(),
+        org_id: "org_1".to_string(),
+        rule_id: "rule_001".to_string(),
+        detection_id: "det_001".to_string(),
+        severity: Severity::High,
+        status: "new".to_string(),
+        title: "High Severity Detection".to_string(),
+        description: "Multiple suspicious processes detected".to_string(),
+        metadata: json!({ "count": 5 }),
+        created_at: timestamp(),
+        updated_at: timestamp(),
+    };
+
+    roundtrip(&alert);
+}
+
+#[test]
+fn signature_covers_every_request_field() {
+    let secret = signing::signing_secret("org_example_key");
+    let body_hash = signing::content_sha256(br#"{"events":[]}"#);
+    let canonical = signing::canonical_request("post", "/ingest/batch", 1_700_000_000, "nonce-1", &body_hash);
+    let signature = signing::sign(&secret, &canonical);
+
+    assert!(signing::verify(&secret, &canonical, &signature));
+    assert_ne!(signing::key_id("org_example_key"), secret[..32]);
+    // The stored lookup hash of the key must not double as its signing secret
+    assert_ne!(secret, signing::content_sha256(b"org_example_key"));
+
+    let tampered = [
+        signing::canonical_request("GET", "/ingest/batch", 1_700_000_000, "nonce-1", &body_hash),
+        signing::canonical_request("POST", "/ingest/stream", 1_700_000_000, "nonce-1", &body_hash),
+        signing::canonical_request("POST", "/ingest/batch", 1_700_000_001, "nonce-1", &body_hash),
+        signing::canonical_request("POST", "/ingest/batch", 1_700_000_000, "nonce-2", &body_hash),
+        signing::canonical_request("POST", "/ingest/batch", 1_700_000_000, "nonce-1", signing::UNSIGNED_PAYLOAD),
+    ];
+    for canonical in &tampered {
+        assert!(!signing::verify(&secret, canonical, &signature), "{}", canonical);
+    }
+    assert!(!signing::verify(&signing::signing_secret("org_other_key"), &canonical, &signature));
+    assert!(!signing::verify(&secret, &canonical, "not-hex"));
+}
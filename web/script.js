@@ -25,6 +25,23 @@ document.addEventListener('DOMContentLoaded', () => {
         fetchAlerts();
     }
 
+    // Severity values come from the API's Severity enum; anything else gets a neutral style
+    const SEVERITY_CLASSES = {
+        info: 'severity-info',
+        low: 'severity-low',
+        medium: 'severity-medium',
+        high: 'severity-high',
+        critical: 'severity-critical',
+    };
+
+    function severityClass(severity) {
+        return SEVERITY_CLASSES[severity] || 'severity-unknown';
+    }
+
+    function severityLabel(severity) {
+        return SEVERITY_CLASSES[severity] ? severity.toUpperCase() : 'UNKNOWN';
+    }
+
     // --- Dashboard (Live Detections) ---
     function startPolling() {
         fetchDetections();
@@ -62,9 +79,9 @@ document.addEventListener('DOMContentLoaded', () => {
         }
 
         const listHtml = detections.map(d => `
-            <div class="detection-card ${d.severity}">
+            <div class="detection-card ${severityClass(d.severity)}">
                 <div class="detection-header">
-                    <span class="badg ${d.severity}">${d.severity.toUpperCase()}</span>
+                    <span class="badg ${severityClass(d.severity)}">${severityLabel(d.severity)}</span>
                     <span class="time">${new Date(d.created_at).toLocaleTimeString()}</span>
                 </div>
                 <h3>${d.title}</h3>
@@ -101,7 +118,7 @@ document.addEventListener('DOMContentLoaded', () => {
                 <td>${a.name}</td>
                 <td>${a.platform}</td>
                 <td>${a.version}</td>
-                <td><span class="badg ${a.status === 'online' ? 'low' : 'high'}">${a.status}</span></td>
+                <td><span class="badg ${severityClass(a.status === 'online' ? 'low' : 'high')}">${a.status}</span></td>
                 <td>${new Date(a.last_heartbeat).toLocaleString()}</td>
             </tr>
         `).join('');
@@ -133,9 +150,9 @@ document.addEventListener('DOMContentLoaded', () => {
         if (!alerts || alerts.length === 0) return container.innerHTML = '<p>No alerts found.</p>';
 
         const listHtml = alerts.map(a => `
-             <div class="detection-card ${a.severity}">
+             <div class="detection-card ${severityClass(a.severity)}">
                 <div class="detection-header">
-                    <span class="badg ${a.severity}">${a.severity.toUpperCase()}</span>
+                    <span class="badg ${severityClass(a.severity)}">${severityLabel(a.severity)}</span>
                     <span class="time">${new Date(a.created_at).toLocaleString()}</span>
                 </div>
                 <h3>${a.title}</h3>